@@ -0,0 +1,59 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use serde::Serialize;
+
+use crate::types::edocument_types::EdocumentAbacData;
+use crate::z3_solver::EdocumentAccessResult;
+
+/// Which users and resources a solve's results never mention, computed from the result list alone
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    /// Users that are granted nothing, in id order
+    pub users_without_grants: Vec<String>,
+    /// Resources nobody is granted anything on, in id order
+    pub resources_without_grants: Vec<String>,
+    pub grants_per_action: BTreeMap<String, usize>,
+}
+
+pub fn coverage_report(results: &[EdocumentAccessResult], data: &EdocumentAbacData) -> CoverageReport {
+    let granted_users: HashSet<&str> = results.iter().map(|r| r.user_id.as_str()).collect();
+    let granted_resources: HashSet<&str> = results.iter().map(|r| r.resource_id.as_str()).collect();
+
+    let mut users_without_grants: Vec<String> = data.users.iter()
+        .map(|u| &u.user_id)
+        .filter(|id| !granted_users.contains(id.as_str()))
+        .cloned()
+        .collect();
+    users_without_grants.sort();
+    let mut resources_without_grants: Vec<String> = data.resources.iter()
+        .map(|r| &r.resource_id)
+        .filter(|id| !granted_resources.contains(id.as_str()))
+        .cloned()
+        .collect();
+    resources_without_grants.sort();
+
+    let mut grants_per_action = BTreeMap::new();
+    for result in results {
+        *grants_per_action.entry(result.action.to_string()).or_insert(0) += 1;
+    }
+
+    CoverageReport { users_without_grants, resources_without_grants, grants_per_action }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Users without grants ({}):", self.users_without_grants.len())?;
+        for user_id in &self.users_without_grants {
+            writeln!(f, "  {}", user_id)?;
+        }
+        writeln!(f, "\nResources without grants ({}):", self.resources_without_grants.len())?;
+        for resource_id in &self.resources_without_grants {
+            writeln!(f, "  {}", resource_id)?;
+        }
+        writeln!(f, "\nGrants per action:")?;
+        for (action, count) in &self.grants_per_action {
+            writeln!(f, "  {:<24} {}", action, count)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::types::edocument_types::{EdocumentAbacData, AttributeName};
+use crate::types::types::AttributeValueExtractor;
+
+/// Quick profile of a dataset, computed without touching Z3
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetStats {
+    pub users: usize,
+    pub resources: usize,
+    pub rules: usize,
+    pub users_per_role: BTreeMap<String, usize>,
+    pub resources_per_type: BTreeMap<String, usize>,
+    pub rules_per_action: BTreeMap<String, usize>,
+    pub security_levels: BTreeMap<i32, usize>,
+    /// Share of (user, attribute) slots that hold a value, over attributes any user has
+    pub user_attribute_fill_rate: f64,
+    /// Share of (resource, attribute) slots that hold a value, over attributes any resource has
+    pub resource_attribute_fill_rate: f64,
+}
+
+pub fn dataset_stats(data: &EdocumentAbacData) -> DatasetStats {
+    let mut users_per_role = BTreeMap::new();
+    for user in &data.users {
+        // A user with several roles is counted under each of them
+        let roles = user.all_roles();
+        if roles.is_empty() {
+            *users_per_role.entry("none".to_string()).or_insert(0) += 1;
+        }
+        for role in roles {
+            *users_per_role.entry(role.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let mut resources_per_type = BTreeMap::new();
+    let mut security_levels = BTreeMap::new();
+    for resource in &data.resources {
+        let doc_type = resource.resource_type.as_ref().map_or("none".to_string(), |t| t.to_string());
+        *resources_per_type.entry(doc_type).or_insert(0) += 1;
+        if let Some(level) = resource.security_level {
+            *security_levels.entry(level).or_insert(0) += 1;
+        }
+    }
+
+    let mut rules_per_action = BTreeMap::new();
+    for rule in &data.rules {
+        for action in &rule.actions {
+            *rules_per_action.entry(action.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    DatasetStats {
+        users: data.users.len(),
+        resources: data.resources.len(),
+        rules: data.rules.len(),
+        users_per_role,
+        resources_per_type,
+        rules_per_action,
+        security_levels,
+        user_attribute_fill_rate: fill_rate(&data.users),
+        resource_attribute_fill_rate: fill_rate(&data.resources),
+    }
+}
+
+fn fill_rate<E>(entities: &[E]) -> f64
+where
+    E: AttributeValueExtractor<AttributeName = AttributeName>,
+{
+    let has_value = |entity: &E, attr_name: &AttributeName| {
+        entity.get_attribute_value(attr_name).is_some()
+            || entity.get_attribute_set(attr_name).is_some_and(|s| !s.is_empty())
+    };
+    let applicable: HashSet<AttributeName> = AttributeName::iter()
+        .filter(|attr_name| entities.iter().any(|e| has_value(e, attr_name)))
+        .collect();
+    let slots = entities.len() * applicable.len();
+    if slots == 0 {
+        return 0.0;
+    }
+    let filled: usize = entities.iter()
+        .map(|e| applicable.iter().filter(|attr_name| has_value(e, attr_name)).count())
+        .sum();
+    filled as f64 / slots as f64
+}
+
+impl fmt::Display for DatasetStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Users:     {}", self.users)?;
+        writeln!(f, "Resources: {}", self.resources)?;
+        writeln!(f, "Rules:     {}", self.rules)?;
+        writeln!(f, "\nUsers per role:")?;
+        for (role, count) in &self.users_per_role {
+            writeln!(f, "  {:<24} {}", role, count)?;
+        }
+        writeln!(f, "\nResources per type:")?;
+        for (doc_type, count) in &self.resources_per_type {
+            writeln!(f, "  {:<24} {}", doc_type, count)?;
+        }
+        writeln!(f, "\nRules per action:")?;
+        for (action, count) in &self.rules_per_action {
+            writeln!(f, "  {:<24} {}", action, count)?;
+        }
+        writeln!(f, "\nSecurity levels:")?;
+        for (level, count) in &self.security_levels {
+            writeln!(f, "  {:<24} {}", level, count)?;
+        }
+        writeln!(f, "\nAttribute fill rate: users {:.1}%, resources {:.1}%",
+                 self.user_attribute_fill_rate * 100.0, self.resource_attribute_fill_rate * 100.0)
+    }
+}
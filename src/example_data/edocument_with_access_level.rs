@@ -160,6 +160,8 @@ pub fn generate_edocument_data_with_clearance(
                 right: AttributeExpression::AttributeName(AttributeName::Department),
             },
         ],
+        effect: Effect::Permit,
+        cross_tenant: false,
     });
 
     // A simple rule allowing owners to view their own documents (as a fallback/additional rule)
@@ -176,6 +178,8 @@ pub fn generate_edocument_data_with_clearance(
                 right: AttributeExpression::AttributeName(AttributeName::Owner),
             }
         ],
+        effect: Effect::Permit,
+        cross_tenant: false,
     });
 
     EdocumentAbacData {
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use strum::IntoEnumIterator;
+
+use crate::types::edocument_types::{Action, ApprovalStatus, AttributeName, ContractType, DocumentType, Effect, Position, Role, Tenant};
+use crate::types::types::ComparisonOperator;
+
+/// JSON Schema (draft 2020-12) for edocument JSON input, i.e. `EdocumentAbacData`.
+///
+/// Enum values are the serialized form of every variant, so the schema follows the enum
+/// definitions as they change.
+pub fn edocument_json_schema() -> Value {
+    let string_set = json!({"type": "array", "items": {"type": "string"}, "uniqueItems": true});
+    let action_set = json!({"type": "array", "items": {"$ref": "#/$defs/action"}, "uniqueItems": true});
+
+    let user = object(&["user_id", "projects", "supervisee"], [
+        ("user_id", json!({"type": "string"})),
+        ("role", nullable(json!({"$ref": "#/$defs/role"}))),
+        ("roles", json!({"type": "array", "items": {"$ref": "#/$defs/role"}, "uniqueItems": true})),
+        ("position", nullable(json!({"$ref": "#/$defs/position"}))),
+        ("tenant", nullable(json!({"$ref": "#/$defs/tenant"}))),
+        ("department", nullable(json!({"type": "string"}))),
+        ("office", nullable(json!({"type": "string"}))),
+        ("region", nullable(json!({"type": "string"}))),
+        ("registered", nullable(json!({"type": "boolean"}))),
+        ("projects", string_set.clone()),
+        ("supervisor", nullable(json!({"type": "string"}))),
+        ("supervisee", string_set.clone()),
+        ("delegated_authority", string_set.clone()),
+        ("certifications", string_set.clone()),
+        ("payrolling_permissions", nullable(json!({"type": "boolean"}))),
+        ("clearance_level", nullable(json!({"type": "integer"}))),
+        ("budget_authority", nullable(json!({"type": "integer", "minimum": 0, "maximum": i64::MAX}))),
+        ("working_hours", nullable(json!({"type": "string", "pattern": "^\\d{1,2}:\\d{2}-\\d{1,2}:\\d{2}$"}))),
+        ("contract_type", nullable(json!({"$ref": "#/$defs/contract_type"}))),
+    ]);
+    let resource = object(&["resource_id", "recipients"], [
+        ("resource_id", json!({"type": "string"})),
+        ("resource_type", nullable(json!({"$ref": "#/$defs/document_type"}))),
+        ("owner", nullable(json!({"type": "string"}))),
+        ("tenant", nullable(json!({"$ref": "#/$defs/tenant"}))),
+        ("department", nullable(json!({"type": "string"}))),
+        ("office", nullable(json!({"type": "string"}))),
+        ("region", nullable(json!({"type": "string"}))),
+        ("recipients", string_set),
+        ("is_confidential", nullable(json!({"type": "boolean"}))),
+        ("contains_personal_info", nullable(json!({"type": "boolean"}))),
+        ("is_archived", nullable(json!({"type": "boolean"}))),
+        ("security_level", nullable(json!({"type": "integer"}))),
+        ("version", nullable(json!({"type": "string"}))),
+        ("document_family", nullable(json!({"type": "string"}))),
+        ("created_date", nullable(json!({"type": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}$"}))),
+        ("retention_period_days", nullable(json!({"type": "integer"}))),
+        ("supported_actions", nullable(action_set.clone())),
+        ("approval_status", nullable(json!({"$ref": "#/$defs/approval_status"}))),
+    ]);
+    let conditions = json!({"type": "array", "items": {"$ref": "#/$defs/condition"}});
+    let rule = object(&["id", "description", "user_conditions", "resource_conditions", "actions", "comparison_conditions"], [
+        ("id", json!({"type": "integer", "minimum": 0})),
+        ("description", json!({"type": "string"})),
+        ("user_conditions", conditions.clone()),
+        ("resource_conditions", conditions.clone()),
+        ("actions", action_set),
+        ("comparison_conditions", conditions),
+        ("effect", json!({"$ref": "#/$defs/effect"})),
+        ("cross_tenant", json!({"type": "boolean"})),
+    ]);
+    let condition = object(&["left", "operator", "right"], [
+        ("left", json!({"$ref": "#/$defs/expression"})),
+        ("operator", enum_schema([
+            ComparisonOperator::Contains,
+            ComparisonOperator::ContainedIn,
+            ComparisonOperator::Equals,
+            ComparisonOperator::GreaterThan,
+            ComparisonOperator::LessThan,
+            ComparisonOperator::GreaterThanOrEqual,
+            ComparisonOperator::LessThanOrEqual,
+        ])),
+        ("right", json!({"$ref": "#/$defs/expression"})),
+    ]);
+    let attribute_names = enum_schema(AttributeName::iter().filter(|name| !matches!(name, AttributeName::Custom(_))));
+    let expression = json!({"oneOf": [
+        tagged("AttributeName", json!({"oneOf": [attribute_names, tagged("Custom", json!({"type": "string"}))]})),
+        tagged("AttributeValue", json!({"$ref": "#/$defs/value"})),
+        tagged("ValueSet", json!({"type": "array", "items": {"$ref": "#/$defs/value"}})),
+    ]});
+    let value = json!({"oneOf": [
+        tagged("Role", json!({"$ref": "#/$defs/role"})),
+        tagged("Position", json!({"$ref": "#/$defs/position"})),
+        tagged("Tenant", json!({"$ref": "#/$defs/tenant"})),
+        tagged("ResourceType", json!({"$ref": "#/$defs/document_type"})),
+        tagged("String", json!({"type": "string"})),
+        tagged("Boolean", json!({"type": "boolean"})),
+        tagged("Integer", json!({"type": "integer"})),
+        tagged("StringSet", json!({"type": "array", "items": {"type": "string"}})),
+    ]});
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "EdocumentAbacData",
+        "type": "object",
+        "required": ["users", "resources", "rules"],
+        "additionalProperties": false,
+        "properties": {
+            "users": {"type": "array", "items": {"$ref": "#/$defs/user"}},
+            "resources": {"type": "array", "items": {"$ref": "#/$defs/resource"}},
+            "rules": {"type": "array", "items": {"$ref": "#/$defs/rule"}},
+        },
+        "$defs": {
+            "user": user,
+            "resource": resource,
+            "rule": rule,
+            "condition": condition,
+            "expression": expression,
+            "value": value,
+            "role": enum_schema(Role::iter()),
+            "position": enum_schema(Position::iter()),
+            "tenant": enum_schema(Tenant::iter()),
+            "document_type": enum_schema(DocumentType::iter()),
+            "action": enum_schema(Action::iter()),
+            "approval_status": enum_schema(ApprovalStatus::iter()),
+            "contract_type": enum_schema(ContractType::iter()),
+            "effect": enum_schema([Effect::Permit, Effect::Deny]),
+        },
+    })
+}
+
+/// The serialized form of each variant, e.g. `"Employee"`
+fn enum_schema<T: Serialize>(variants: impl IntoIterator<Item = T>) -> Value {
+    let values: Vec<Value> = variants.into_iter()
+        .map(|variant| serde_json::to_value(variant).expect("enum variants serialize"))
+        .collect();
+    json!({"enum": values})
+}
+
+fn object<const N: usize>(required: &[&str], properties: [(&str, Value); N]) -> Value {
+    let properties: Map<String, Value> = properties.into_iter().map(|(name, schema)| (name.to_string(), schema)).collect();
+    json!({"type": "object", "required": required, "additionalProperties": false, "properties": properties})
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({"oneOf": [schema, {"type": "null"}]})
+}
+
+/// An externally tagged enum variant, e.g. `{"Role": "Employee"}`
+fn tagged(tag: &str, schema: Value) -> Value {
+    object(&[tag], [(tag, schema)])
+}
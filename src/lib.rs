@@ -0,0 +1,44 @@
+//! ABAC policy analysis: domain types and parsers, the brute-force evaluators in
+//! `simple_loop`, and the Z3-based `EdocumentAbacSolver`.
+//!
+//! The `edocument` module gathers what is needed to embed the edocument solver:
+//!
+//! ```
+//! use abac_solver::edocument::{ActionHierarchy, EdocumentAbacData, EdocumentAbacSolver};
+//! use z3::{Config, Context};
+//!
+//! let json = r#"{"users": [], "resources": [], "rules": []}"#;
+//! let data: EdocumentAbacData = serde_json::from_str(json).unwrap();
+//!
+//! let ctx = Context::new(&Config::new());
+//! let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+//! let results = solver.solve_access_control(None, None);
+//! assert!(results.is_empty());
+//! ```
+
+pub mod types;
+pub mod simple_loop;
+pub mod example_data;
+pub mod z3_solver;
+pub mod dataset_stats;
+pub mod coverage_report;
+pub mod json_schema;
+pub mod policy_lint;
+
+/// The edocument domain types together with the Z3 solver that evaluates them.
+///
+/// `EdocumentAbacSolver::new` asserts every user and resource attribute as a constraint,
+/// and `solve_access_control` enumerates the permitted (user, resource, action) triples.
+pub mod edocument {
+    pub use crate::types::edocument_types::*;
+    pub use crate::z3_solver::{
+        EdocumentAbacSolver, EdocumentAccessResult, CappedResults, SortCache, BasePolicy, Verbosity,
+        solve_sharded, solve_access_control_parallel, check_entity_limit, DEFAULT_MAX_ENTITIES,
+    };
+}
+
+/// The university domain types, the parser for `.abac` files and the Z3 solver for them.
+pub mod university {
+    pub use crate::types::university_types::*;
+    pub use crate::z3_solver::{UniversityAbacSolver, UniversityAccessResult};
+}
@@ -1,17 +1,19 @@
+use std::collections::HashSet;
 use std::env;
 use std::io::{self, Write};
 use std::time::Instant;
 
-mod types;
-mod simple_loop;
-mod example_data;
-mod z3_solver;
-
-use crate::example_data::edocument_with_access_level::generate_and_save_json;
-use crate::types::university_types::{UniversityAbacData, UniversityAbac, UniversityDomainParser};
-use crate::types::edocument_types::{EdocumentAbacData, EdocumentAbac};
-use crate::types::types::GenericAbacParser;
-use simple_loop::{simple_loop, improved_simple_loop, parallel_indexed_loop};
+use abac_solver::z3_solver;
+use abac_solver::example_data::edocument_with_access_level::generate_and_save_json;
+use abac_solver::types::university_types::{UniversityAbacData, UniversityAbac, UniversityDomainParser};
+use abac_solver::types::edocument_types::{EdocumentAbac, EdocumentUserAttribute, EdocumentResourceAttribute, EdocumentRule, EdocumentAbacParser, EdocumentDomainParser, Action, ActionHierarchy, Tenant, default_action_hierarchy, validate_rule};
+use abac_solver::z3_solver::{CappedResults, EdocumentAbacSolver, EdocumentAccessResult, SolveStats, Verbosity};
+use abac_solver::dataset_stats::dataset_stats;
+use abac_solver::json_schema::edocument_json_schema;
+use abac_solver::policy_lint::{check_policy, combine_inputs, unused_attributes};
+use z3::{Config, Context};
+use abac_solver::types::types::{AbacError, GenericAbacParser};
+use abac_solver::simple_loop::{explain_denial, permitted_triples, simple_loop, improved_simple_loop, parallel_indexed_loop};
 
 #[derive(Debug, Clone)]
 enum Domain {
@@ -47,42 +49,382 @@ use clap::Parser;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    solver: String,
+    #[arg(short, long, required_unless_present_any = ["emit_schema", "stats", "lint"])]
+    solver: Option<String>,
+
+    /// Input files, edocument JSON or `.abac` text; several paths are merged into one dataset before solving
+    #[arg(short, long, alias = "input", num_args = 1.., required_unless_present_any = ["data_path", "emit_schema"])]
+    json_path: Vec<String>,
+
+    /// The `.abac` file the university analysis parses, instead of `data/university.abac` (analyze)
+    #[arg(long)]
+    data_path: Option<String>,
+
+    /// Treat `view` as implying `readMetaInfo` and `search` (z3 solver)
+    #[arg(long)]
+    action_hierarchy: bool,
+
+    /// Only enumerate access to resources owned by this tenant, e.g. `largeBank` (z3 solver)
+    #[arg(long, conflicts_with_all = ["shards", "parallel_actions"])]
+    tenant: Option<Tenant>,
+
+    /// Append run statistics as a JSON line to this file (z3 solver)
+    #[arg(long)]
+    stats_out: Option<String>,
+
+    /// Print an access report instead of enumerating every rule, e.g. `resource:doc3` or `user:alice` (z3 solver)
+    #[arg(long, conflicts_with_all = ["results_out", "output_csv", "json_out", "max_solutions", "shards", "parallel_actions"])]
+    report: Option<String>,
+
+    /// Check expectations such as `user5 cannot edit doc3`, one per line (z3 solver)
+    #[arg(long, conflicts_with_all = ["results_out", "output_csv", "json_out", "max_solutions", "shards", "parallel_actions"])]
+    verify: Option<String>,
+
+    /// Write the constraints and the enumeration query as SMT-LIB2 to this file instead of solving (z3 solver)
+    #[arg(long, alias = "dump-smt")]
+    emit_problem: Option<String>,
+
+    /// Fields to null out in JSON output, e.g. `owner,recipients` (analyze)
+    #[arg(long, value_delimiter = ',')]
+    redact: Vec<String>,
+
+    /// Enumerate all permitted triples with the resources split across this many threads (z3 solver)
+    #[arg(long)]
+    shards: Option<usize>,
+
+    /// Enumerate all permitted triples with one thread per action (z3 solver)
+    #[arg(long, conflicts_with_all = ["shards", "max_solutions"])]
+    parallel_actions: bool,
+
+    /// Print a profile of the input dataset instead of solving: `table` (default) or `json`
+    #[arg(long, num_args = 0..=1, default_missing_value = "table")]
+    stats: Option<StatsFormat>,
+
+    /// Write every permitted triple to this file as JSON, sorted by user, resource and action (z3 solver)
+    #[arg(long)]
+    results_out: Option<String>,
+
+    /// Stop enumerating after this many permitted triples and report whether more exist (z3 solver)
+    #[arg(long, conflicts_with = "shards")]
+    max_solutions: Option<u64>,
+
+    /// Write every permitted triple to this file as CSV with a `user,resource,action` header, sorted like --results-out (z3 solver)
+    #[arg(long)]
+    output_csv: Option<String>,
+
+    /// Write every permitted triple to this file as `{"results": [...], "total": N, "truncated": bool}` (z3 solver)
+    #[arg(long)]
+    json_out: Option<String>,
+
+    /// Refuse datasets with more users or resources than this, instead of running out of memory (z3 solver)
+    #[arg(long, default_value_t = z3_solver::DEFAULT_MAX_ENTITIES)]
+    max_entities: usize,
+
+    /// Keep only this many users, the first ones or a seeded random sample with --seed (simple, z3 and compare solvers)
+    #[arg(long)]
+    limit_users: Option<usize>,
+
+    /// Keep only this many resources, the first ones or a seeded random sample with --seed (simple, z3 and compare solvers)
+    #[arg(long)]
+    limit_resources: Option<usize>,
+
+    /// Sample --limit-users and --limit-resources at random with this seed instead of keeping the first ones
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Only enumerate these actions, e.g. `edit,approve` (z3 solver). --verify, --report and serve still check every action
+    #[arg(long, value_delimiter = ',')]
+    only_actions: Vec<Action>,
+
+    /// Print a JSON Schema for edocument JSON input and exit
+    #[arg(long)]
+    emit_schema: bool,
+
+    /// Print lint, conflict and redundant-rule issues and attributes no rule uses, instead of solving
+    #[arg(long)]
+    lint: bool,
+
+    /// Fail before solving if the inputs have any lint, conflict or redundant-rule issue, or a resource owner or recipient that is not a user
+    #[arg(long)]
+    strict: bool,
+
+    /// Suppress solver progress output
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
 
+    /// Also print every permitted triple as it is found
     #[arg(short, long)]
-    json_path: String,
+    verbose: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum StatsFormat {
+    Table,
+    Json,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    match args.solver.as_str() {
+    if args.emit_schema {
+        println!("{}", serde_json::to_string_pretty(&edocument_json_schema())?);
+        return Ok(());
+    }
+
+    if args.lint {
+        let inputs = read_edocument_inputs(&args.json_path)?;
+        for issue in check_policy(&inputs) {
+            println!("{}", issue);
+        }
+        for attr_name in unused_attributes(&combine_inputs(&inputs)) {
+            println!("unused attribute: '{}' has values but no rule references it", attr_name);
+        }
+        // Conflicting inputs cannot be merged into one solver; they are already reported above
+        if let Ok(parsed_abac) = load_edocument_inputs(&args.json_path) {
+            let ctx = Context::new(&Config::new());
+            let mut abac_solver = EdocumentAbacSolver::new(&ctx, &parsed_abac, &ActionHierarchy::new());
+            for rule_id in abac_solver.find_unreachable_rules() {
+                println!("unreachable: rule {} matches no (user, resource, action) in the data", rule_id);
+            }
+            let mut overridden = abac_solver.find_conflicts();
+            overridden.sort_by(|a, b| {
+                (&a.user_id, &a.resource_id, a.action.to_string()).cmp(&(&b.user_id, &b.resource_id, b.action.to_string()))
+            });
+            for result in overridden {
+                println!("overridden: a permit rule grants '{}' but a deny rule takes it away", result);
+            }
+        }
+        return Ok(());
+    }
+
+    if args.strict {
+        let inputs = read_edocument_inputs(&args.json_path)?;
+        let issues = check_policy(&inputs);
+        if !issues.is_empty() {
+            for issue in &issues {
+                eprintln!("{}", issue);
+            }
+            eprintln!("{} policy issue(s) found", issues.len());
+            std::process::exit(1);
+        }
+        combine_inputs(&inputs).check_resource_references()?;
+    }
+
+    if let Some(format) = &args.stats {
+        let stats = dataset_stats(&load_edocument_inputs(&args.json_path)?);
+        match format {
+            StatsFormat::Table => print!("{}", stats),
+            StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+        }
+        return Ok(());
+    }
+
+    let solver = args.solver.as_deref().expect("clap requires --solver unless --emit-schema, --stats or --lint is given");
+    match solver {
         "simple" => {
             println!("Running simple loop solver...");
-            let json_content: String = std::fs::read_to_string(&args.json_path)
-                .map_err(|e| format!("Failed to read JSON file {}: {}", &args.json_path, e))?;
-            let parsed_abac: EdocumentAbac = serde_json::from_str(&json_content)
-                .map_err(|e| format!("Failed to parse JSON from {}: {}", &args.json_path, e))?;
+            let mut parsed_abac = load_sampled_inputs(&args)?;
+            parsed_abac.canonicalize();
             parallel_indexed_loop(parsed_abac)?;
         }
         "z3" => {
             println!("Running z3 solver...");
             let start_time = Instant::now();
-            z3_solver::solve_real_world_scenario(&args.json_path)?;
+            let action_hierarchy = if args.action_hierarchy {
+                default_action_hierarchy()
+            } else {
+                ActionHierarchy::new()
+            };
+            let verbosity = if args.quiet {
+                Verbosity::Silent
+            } else if args.verbose {
+                Verbosity::Verbose
+            } else {
+                Verbosity::Normal
+            };
+            let only_actions: HashSet<Action> = args.only_actions.iter().cloned().collect();
+            let mut parsed_abac = load_sampled_inputs(&args)?;
+            // --verify explains failures by the rules as written, so only merge them when enumerating
+            if args.verify.is_none() {
+                parsed_abac.canonicalize();
+            }
+            z3_solver::check_entity_limit(&parsed_abac, args.max_entities)?;
+            if let Some(problem_path) = &args.emit_problem {
+                let ctx = Context::new(&Config::new());
+                let abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, verbosity, &only_actions);
+                std::fs::write(problem_path, abac_solver.dump_smtlib())
+                    .map_err(|e| format!("Failed to write SMT-LIB problem {}: {}", problem_path, e))?;
+                println!("SMT-LIB problem written to {}", problem_path);
+                return Ok(());
+            }
+            let stats = match (&args.verify, &args.report, args.tenant.clone()) {
+                (Some(verify_path), _, _) => {
+                    let solve_start = Instant::now();
+                    let ctx = Context::new(&Config::new());
+                    let mut abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, verbosity, &HashSet::new());
+                    let (checked, failures) = verify_expectations(&mut abac_solver, &parsed_abac, verify_path)?;
+                    println!("{}/{} expectations hold.", checked - failures, checked);
+                    if failures > 0 {
+                        std::process::exit(1);
+                    }
+                    SolveStats::new(&parsed_abac, abac_solver.constraint_count(), checked, solve_start.elapsed())
+                }
+                (None, Some(report), _) => {
+                    let solve_start = Instant::now();
+                    let ctx = Context::new(&Config::new());
+                    let mut abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, verbosity, &HashSet::new());
+                    let solutions_found = if let Some(resource_id) = report.strip_prefix("resource:") {
+                        let access_report = abac_solver.resource_access_report(resource_id)?;
+                        let mut actions: Vec<&Action> = access_report.keys().collect();
+                        actions.sort_by_key(|action| action.to_string());
+                        println!("Access report for {}:", resource_id);
+                        for action in actions {
+                            println!("  {}: {}", action, access_report[action].join(", "));
+                        }
+                        access_report.values().map(|users| users.len()).sum()
+                    } else if let Some(user_id) = report.strip_prefix("user:") {
+                        let mut results = abac_solver.enumerate_for_user(user_id)?;
+                        results.sort_by(|a, b| (&a.resource_id, a.action.to_string()).cmp(&(&b.resource_id, b.action.to_string())));
+                        println!("Access report for {}:", user_id);
+                        for result in &results {
+                            println!("  {}: {}", result.resource_id, result.action);
+                        }
+                        results.len()
+                    } else {
+                        return Err(format!("Unknown report: {}. Expected resource:<id> or user:<id>", report).into());
+                    };
+                    SolveStats::new(&parsed_abac, abac_solver.constraint_count(), solutions_found, solve_start.elapsed())
+                }
+                (None, None, Some(tenant)) => {
+                    let solve_start = Instant::now();
+                    let ctx = Context::new(&Config::new());
+                    let mut abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, verbosity, &only_actions);
+                    let mut capped = abac_solver.solve_tenant_scoped(tenant.clone(), args.max_solutions);
+                    for result in &capped.results {
+                        println!("{}", result);
+                    }
+                    if capped.truncated {
+                        println!("Found {} permitted triples for tenant {}; stopped at --max-solutions, more are permitted.", capped.results.len(), tenant);
+                    } else {
+                        println!("Found {} permitted triples for tenant {}.", capped.results.len(), tenant);
+                    }
+                    write_results(&args, &mut capped)?;
+                    SolveStats::new(&parsed_abac, abac_solver.constraint_count(), capped.results.len(), solve_start.elapsed())
+                }
+                (None, None, None) => match (args.shards, args.results_out.is_some() || args.output_csv.is_some() || args.json_out.is_some(), args.max_solutions) {
+                    (None, false, None) if !args.parallel_actions => z3_solver::solve_real_world_scenario(&parsed_abac, &action_hierarchy, verbosity, &only_actions)?,
+                    (None, _, Some(max)) => {
+                        let solve_start = Instant::now();
+                        let ctx = Context::new(&Config::new());
+                        let mut abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, verbosity, &only_actions);
+                        let mut capped = abac_solver.solve_access_control_capped(Some(max));
+                        if capped.truncated {
+                            println!("Found {} permitted triples; stopped at --max-solutions, more are permitted.", capped.results.len());
+                        } else {
+                            println!("Found {} permitted triples.", capped.results.len());
+                        }
+                        write_results(&args, &mut capped)?;
+                        SolveStats::new(&parsed_abac, abac_solver.constraint_count(), capped.results.len(), solve_start.elapsed())
+                    }
+                    (None, _, None) if args.parallel_actions => {
+                        let solve_start = Instant::now();
+                        let results = z3_solver::solve_access_control_parallel(&parsed_abac, &action_hierarchy, &only_actions);
+                        println!("Found {} permitted triples with one thread per action.", results.len());
+                        let mut complete = CappedResults { results, truncated: false };
+                        write_results(&args, &mut complete)?;
+                        // Each action has its own solver, so there is no single constraint count to report
+                        SolveStats::new(&parsed_abac, 0, complete.results.len(), solve_start.elapsed())
+                    }
+                    (shards, _, _) => {
+                        let shards = shards.unwrap_or(1);
+                        let solve_start = Instant::now();
+                        let results = z3_solver::solve_sharded(&parsed_abac, &action_hierarchy, &only_actions, shards);
+                        println!("Found {} permitted triples across {} shards.", results.len(), shards);
+                        let mut complete = CappedResults { results, truncated: false };
+                        write_results(&args, &mut complete)?;
+                        // Each shard has its own solver, so there is no single constraint count to report
+                        SolveStats::new(&parsed_abac, 0, complete.results.len(), solve_start.elapsed())
+                    }
+                },
+            };
+            if let Some(stats_path) = &args.stats_out {
+                stats.append_to(stats_path)?;
+            }
             let end_time = Instant::now();
             let duration = end_time.duration_since(start_time);
             println!("Z3 solver execution time: {:?}", duration);
             println!("Z3 solver execution time (milliseconds): {}", duration.as_millis());
             println!("Z3 solver execution time (seconds): {:.3}", duration.as_secs_f64());
         }
+        "compare" => {
+            let action_hierarchy = if args.action_hierarchy {
+                default_action_hierarchy()
+            } else {
+                ActionHierarchy::new()
+            };
+            let mut parsed_abac = load_sampled_inputs(&args)?;
+            parsed_abac.canonicalize();
+
+            let simple_start = Instant::now();
+            let simple_triples = permitted_triples(&parsed_abac, &action_hierarchy);
+            let simple_time = simple_start.elapsed();
+
+            let z3_start = Instant::now();
+            let ctx = Context::new(&Config::new());
+            let mut abac_solver = build_solver(&ctx, &parsed_abac, &action_hierarchy, Verbosity::Silent, &HashSet::new());
+            let z3_triples: HashSet<(String, String, String)> = abac_solver.solve_access_control(None, None).into_iter()
+                .map(|result| (result.user_id, result.resource_id, result.action.to_string()))
+                .collect();
+            let z3_time = z3_start.elapsed();
+
+            let mut disagreements: Vec<(&str, &(String, String, String))> = simple_triples.difference(&z3_triples).map(|t| ("simple", t))
+                .chain(z3_triples.difference(&simple_triples).map(|t| ("z3", t)))
+                .collect();
+            disagreements.sort();
+            for (solver, (user_id, resource_id, action)) in &disagreements {
+                println!("only {}: {} can {} {}", solver, user_id, action, resource_id);
+            }
+            println!("simple: {} permitted triples in {:?}", simple_triples.len(), simple_time);
+            println!("z3: {} permitted triples in {:?}", z3_triples.len(), z3_time);
+            println!("{} disagreement(s)", disagreements.len());
+            if !disagreements.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        "serve" => {
+            let action_hierarchy = if args.action_hierarchy {
+                default_action_hierarchy()
+            } else {
+                ActionHierarchy::new()
+            };
+            serve(&args.json_path, &action_hierarchy)?;
+        }
+        "validate" => {
+            let mut error_count = 0;
+            for path in &args.json_path {
+                let (counts, errors) = validate_input(path)?;
+                println!("{}: {} users, {} resources, {} rules", path, counts.0, counts.1, counts.2);
+                for error in &errors {
+                    println!("  {}", error.replace('\n', "\n  "));
+                }
+                error_count += errors.len();
+            }
+            if error_count > 0 {
+                eprintln!("{} validation error(s) found", error_count);
+                std::process::exit(1);
+            }
+        }
+        "analyze" => {
+            let domain = select_domain_interactive()?;
+            run_analysis(domain, &args)?;
+        }
         "generate-json" => {
             println!("Generating JSON file...");
             generate_and_save_json();
             println!("JSON file generated successfully");
         }
         _ => {
-            eprintln!("Unknown solver: {}. Available solvers: simple, z3", args.solver);
+            eprintln!("Unknown solver: {}. Available solvers: simple, z3, compare, serve, validate, analyze, generate-json", solver);
             std::process::exit(1);
         }
     }
@@ -90,6 +432,253 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Writes the triples to --results-out as a JSON array, to --json-out with the truncation flag
+/// and to --output-csv as CSV, if given, in a fixed order so runs over the same policy produce
+/// identical files
+fn write_results(args: &Args, capped: &mut CappedResults) -> Result<(), Box<dyn std::error::Error>> {
+    capped.results.sort();
+    if let Some(path) = &args.results_out {
+        std::fs::write(path, serde_json::to_string_pretty(&capped.results)? + "\n")
+            .map_err(|e| format!("Failed to write results {}: {}", path, e))?;
+    }
+    if let Some(path) = &args.json_out {
+        std::fs::write(path, serde_json::to_string_pretty(&capped.to_json())? + "\n")
+            .map_err(|e| format!("Failed to write results {}: {}", path, e))?;
+    }
+    if let Some(path) = &args.output_csv {
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("Failed to write CSV {}: {}", path, e))?;
+        EdocumentAccessResult::write_csv(&capped.results, io::BufWriter::new(file))?;
+    }
+    Ok(())
+}
+
+/// Answers newline-delimited JSON queries from stdin on stdout, keeping the model built between them.
+///
+/// `{"user": "user5", "resource": "doc3", "action": "edit"}` is answered with the same fields plus
+/// `"permitted": true|false`. `{"command": "reload"}` re-reads the input files and rebuilds the model;
+/// if they fail to load, the previous model stays in use. Malformed requests get `{"error": ...}`.
+fn serve(paths: &[String], action_hierarchy: &ActionHierarchy) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::BufRead;
+
+    let mut parsed_abac = load_edocument_inputs(paths)?;
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        // A fresh context per model, so a reload does not pile up sorts in the old one
+        let ctx = Context::new(&Config::new());
+        let mut abac_solver = build_solver(&ctx, &parsed_abac, action_hierarchy, Verbosity::Silent, &HashSet::new());
+        let mut reloaded = false;
+
+        for line in lines.by_ref() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<serde_json::Value>(&line) {
+                Err(e) => serde_json::json!({ "error": format!("Malformed request: {}", e) }),
+                Ok(request) if request["command"] == "reload" => match load_edocument_inputs(paths) {
+                    Ok(data) => {
+                        parsed_abac = data;
+                        reloaded = true;
+                        serde_json::json!({ "reloaded": true })
+                    }
+                    Err(e) => serde_json::json!({ "error": format!("Reload failed: {}", e) }),
+                },
+                Ok(request) => answer_query(&mut abac_solver, &request),
+            };
+            println!("{}", response);
+            io::stdout().flush()?;
+            if reloaded {
+                break;
+            }
+        }
+        if !reloaded {
+            return Ok(());
+        }
+    }
+}
+
+fn answer_query(abac_solver: &mut EdocumentAbacSolver, request: &serde_json::Value) -> serde_json::Value {
+    let (Some(user_id), Some(resource_id), Some(action)) = (request["user"].as_str(), request["resource"].as_str(), request["action"].as_str()) else {
+        return serde_json::json!({ "error": "Expected string fields user, resource and action, or command" });
+    };
+    let Ok(parsed_action) = action.parse::<Action>() else {
+        return serde_json::json!({ "error": format!("Unknown action: {}", action) });
+    };
+    match abac_solver.check_access(user_id, resource_id, parsed_action) {
+        Ok(permitted) => serde_json::json!({ "user": user_id, "resource": resource_id, "action": action, "permitted": permitted }),
+        Err(e) => serde_json::json!({ "error": e }),
+    }
+}
+
+/// Creates a solver over the dataset with the CLI's verbosity and action filter applied
+fn build_solver<'ctx>(
+    ctx: &'ctx Context,
+    parsed_abac: &EdocumentAbac,
+    action_hierarchy: &ActionHierarchy,
+    verbosity: Verbosity,
+    only_actions: &HashSet<Action>,
+) -> EdocumentAbacSolver<'ctx> {
+    let mut abac_solver = EdocumentAbacSolver::new(ctx, parsed_abac, action_hierarchy);
+    abac_solver.set_verbosity(verbosity);
+    abac_solver.restrict_actions(only_actions);
+    abac_solver
+}
+
+/// Reads and parses every input file, without merging them
+fn read_edocument_inputs(paths: &[String]) -> Result<Vec<EdocumentAbac>, Box<dyn std::error::Error>> {
+    paths.iter().map(|path| read_edocument_input(path)).collect()
+}
+
+/// Reads one input, choosing the format by extension: `.abac` files are parsed as policy
+/// text, anything else is deserialized as JSON
+fn read_edocument_input(path: &str) -> Result<EdocumentAbac, Box<dyn std::error::Error>> {
+    if path.ends_with(".abac") {
+        return EdocumentAbacParser::new(EdocumentDomainParser).parse_file(path)
+            .map_err(|e| format!("Failed to parse {}: {}", path, e).into());
+    }
+    let json_content: String = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read JSON file {}: {}", path, e))?;
+    let parsed_abac: EdocumentAbac = serde_json::from_str(&json_content)
+        .map_err(|e| format!("Failed to parse JSON from {}: {}", path, e))?;
+    Ok(parsed_abac)
+}
+
+/// Number of (users, resources, rules) in an input
+type EntityCounts = (usize, usize, usize);
+
+/// Parses one input without solving and collects every error instead of stopping at the first.
+/// `.abac` files are read with the edocument parser, like `read_edocument_input`; anything else as
+/// edocument JSON, where each user, resource and rule is deserialized on its own so one bad value
+/// does not hide the next.
+/// Returns the (users, resources, rules) that parsed, and the errors.
+fn validate_input(path: &str) -> Result<(EntityCounts, Vec<String>), Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if path.ends_with(".abac") {
+        let (data, errors) = EdocumentAbacParser::new(EdocumentDomainParser).parse_collecting(&content);
+        let counts = (data.users.len(), data.resources.len(), data.rules.len());
+        return Ok((counts, errors.iter().map(|e| e.to_string()).collect()));
+    }
+
+    let json: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(json) => json,
+        Err(e) => return Ok(((0, 0, 0), vec![format!("invalid JSON: {}", e)])),
+    };
+    let mut errors = Vec::new();
+    let users = validate_entries::<EdocumentUserAttribute>(&json, "users", &mut errors);
+    let resources = validate_entries::<EdocumentResourceAttribute>(&json, "resources", &mut errors);
+    let rules = validate_entries::<EdocumentRule>(&json, "rules", &mut errors);
+    Ok(((users, resources, rules), errors))
+}
+
+// Deserializes each element of `json[field]` separately; returns how many succeeded
+fn validate_entries<T: serde::de::DeserializeOwned>(json: &serde_json::Value, field: &str, errors: &mut Vec<String>) -> usize {
+    let Some(entries) = json[field].as_array() else {
+        errors.push(format!("{}: expected an array", field));
+        return 0;
+    };
+    let mut valid = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        match serde_json::from_value::<T>(entry.clone()) {
+            Ok(_) => valid += 1,
+            Err(e) => errors.push(format!("{}[{}]: {}", field, i, e)),
+        }
+    }
+    valid
+}
+
+/// Loads every input file and merges them into a single dataset
+fn load_edocument_inputs(paths: &[String]) -> Result<EdocumentAbac, Box<dyn std::error::Error>> {
+    let mut merged: Option<EdocumentAbac> = None;
+    for (path, parsed_abac) in paths.iter().zip(read_edocument_inputs(paths)?) {
+        merged = Some(match merged {
+            Some(data) => data.merge(parsed_abac)
+                .map_err(|e| format!("Failed to merge {}: {}", path, e))?,
+            None => parsed_abac,
+        });
+    }
+    let merged = merged.ok_or("No input given: pass --json-path (--data-path is only read by the university analysis)")?;
+
+    let errors: Vec<String> = merged.rules.iter()
+        .filter_map(|rule| validate_rule(rule).err())
+        .flatten()
+        .map(|e| e.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Invalid rules:\n{}", errors.join("\n")).into());
+    }
+    merged.check_rule_references()?;
+    if let Err(AbacError::DanglingReferences(dangling)) = merged.check_resource_references() {
+        for reference in &dangling {
+            eprintln!("warning: resource {} is not a known user", reference);
+        }
+    }
+    Ok(merged)
+}
+
+/// Loads the inputs like `load_edocument_inputs`, then cuts them down to --limit-users and --limit-resources
+fn load_sampled_inputs(args: &Args) -> Result<EdocumentAbac, Box<dyn std::error::Error>> {
+    let mut parsed_abac = load_edocument_inputs(&args.json_path)?;
+    parsed_abac.sample(args.limit_users, args.limit_resources, args.seed);
+    Ok(parsed_abac)
+}
+
+/// Checks each `<user> can|cannot <action> <resource>` line of an expectations file.
+/// A failed `cannot` prints the rules that grant the access and their satisfied conditions.
+/// Returns the number of expectations checked and how many of them failed.
+fn verify_expectations(abac_solver: &mut EdocumentAbacSolver, parsed_abac: &EdocumentAbac, path: &str) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read expectations file {}: {}", path, e))?;
+    let mut checked = 0;
+    let mut failures = 0;
+
+    for (line_num, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (user_id, expected, action, resource_id) = match parts.as_slice() {
+            [user_id, "can", action, resource_id] => (*user_id, true, *action, *resource_id),
+            [user_id, "cannot", action, resource_id] => (*user_id, false, *action, *resource_id),
+            _ => return Err(format!("{}:{}: expected `<user> can|cannot <action> <resource>`", path, line_num + 1).into()),
+        };
+        let action: Action = action.parse()
+            .map_err(|_| format!("{}:{}: unknown action {}", path, line_num + 1, action))?;
+
+        checked += 1;
+        if abac_solver.check_access(user_id, resource_id, action.clone())? == expected {
+            continue;
+        }
+        failures += 1;
+        println!("FAIL: {}", line);
+        if expected {
+            let granting = abac_solver.granting_rules(user_id, resource_id, action.clone())?;
+            if granting.is_empty() {
+                println!("  no rule grants {} on {} to {}", action, resource_id, user_id);
+                for explanation in explain_denial(parsed_abac, user_id, resource_id, &action)? {
+                    println!("    {}", explanation);
+                }
+            }
+            for rule in granting {
+                println!("  granted by rule {}: {}", rule.id, rule.description);
+            }
+            for reason in abac_solver.denial_reasons(user_id, resource_id, action)? {
+                println!("  taken away by {}", reason);
+            }
+        } else {
+            for rule in abac_solver.granting_rules(user_id, resource_id, action)? {
+                println!("  granted by rule {}: {}", rule.id, rule.description);
+                for cond in rule.user_conditions.iter().chain(&rule.resource_conditions).chain(&rule.comparison_conditions) {
+                    println!("    {}", cond);
+                }
+            }
+        }
+    }
+    Ok((checked, failures))
+}
+
 fn select_domain_interactive() -> Result<Domain, Box<dyn std::error::Error>> {
     println!("=== ABAC ソルバー ===");
     println!("実行するドメインを選択してください:");
@@ -112,30 +701,29 @@ fn select_domain_interactive() -> Result<Domain, Box<dyn std::error::Error>> {
 }
 
 /// Generic function to run analysis for any domain
-fn run_analysis(domain: Domain) -> Result<(), Box<dyn std::error::Error>> {
+/// The university domain parses `--data-path` (default `Domain::file_path`), the edocument
+/// domain reads `--json-path`
+fn run_analysis(domain: Domain, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     println!("=== {}.abacファイルをパースします ===", domain.name());
     
     match domain {
         Domain::University => {
             let parser = GenericAbacParser::new(UniversityDomainParser);
-            let parsed_abac = parser.parse_file(domain.file_path())?;
+            let data_path = args.data_path.as_deref().unwrap_or(domain.file_path());
+            let parsed_abac = parser.parse_file(data_path)?;
             
             println!("=== パース結果をJSONに出力中... ===");
             let parsed_abac_copy = parsed_abac.clone();
-            output_to_json(parsed_abac_copy, domain)?;
+            output_to_json(parsed_abac_copy, domain, &args.redact)?;
 
             println!("=== 詳細分析を実行します ===");
             parallel_indexed_loop(parsed_abac);
             // improved_simple_loop(parsed_abac);
         },
         Domain::Edocument => {
-            let json_file_path = "output/edocument_with_clearance.json";
-            println!("=== {} を読み込み中... ===", json_file_path);
-            let json_content: String = std::fs::read_to_string(json_file_path)
-                .map_err(|e| format!("Failed to read JSON file {}: {}", json_file_path, e))?;
-            let parsed_abac: EdocumentAbacData = serde_json::from_str(&json_content)
-                .map_err(|e| format!("Failed to parse JSON from {}: {}", json_file_path, e))?;
-            
+            println!("=== {} を読み込み中... ===", args.json_path.join(", "));
+            let parsed_abac = load_edocument_inputs(&args.json_path)?;
+
             println!("=== 詳細分析を実行します ===");
             parallel_indexed_loop(parsed_abac);
         },
@@ -145,11 +733,35 @@ fn run_analysis(domain: Domain) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Generic function to output any ABAC data to JSON
-fn output_to_json<T: serde::Serialize>(parsed_abac: T, domain: Domain) -> Result<(), Box<dyn std::error::Error>> {
-    let json_string = serde_json::to_string_pretty(&parsed_abac)?;
+/// Fields named in `redact` are replaced with null wherever they appear
+fn output_to_json<T: serde::Serialize>(parsed_abac: T, domain: Domain, redact: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut json_value = serde_json::to_value(&parsed_abac)?;
+    redact_fields(&mut json_value, redact);
+    let json_string = serde_json::to_string_pretty(&json_value)?;
     let output_file = format!("output/{}", domain.output_filename());
     std::fs::create_dir_all("output")?;
     std::fs::write(&output_file, json_string)?;
     println!("=== 出力完了: {} ===", output_file);
     Ok(())
 }
+
+/// Recursively replaces the values of the named fields with null
+fn redact_fields(value: &mut serde_json::Value, redact: &[String]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if redact.contains(key) {
+                    *field = serde_json::Value::Null;
+                } else {
+                    redact_fields(field, redact);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_fields(item, redact);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use strum::IntoEnumIterator;
+
+use crate::types::edocument_types::{EdocumentAbacData, EdocumentRule, AttributeName, AttributeExpression, validate_rule};
+use crate::types::types::AttributeValueExtractor;
+
+/// A problem found in a policy before solving it
+#[derive(Debug, Clone)]
+pub enum PolicyIssue {
+    /// A rule that is malformed or almost certainly a mistake
+    Lint(String),
+    /// The same user or resource id defined with different attributes
+    Conflict(String),
+    /// A rule that never grants anything another rule does not already grant
+    Redundant(String),
+}
+
+impl fmt::Display for PolicyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyIssue::Lint(msg) => write!(f, "lint: {}", msg),
+            PolicyIssue::Conflict(msg) => write!(f, "conflict: {}", msg),
+            PolicyIssue::Redundant(msg) => write!(f, "redundant: {}", msg),
+        }
+    }
+}
+
+/// Runs every check over the inputs as given, before merging and canonicalization
+/// so that duplicates and conflicting definitions are still visible.
+pub fn check_policy(inputs: &[EdocumentAbacData]) -> Vec<PolicyIssue> {
+    let mut issues = find_conflicts(inputs);
+    let combined = combine_inputs(inputs);
+    issues.extend(lint_rules(&combined));
+    issues.extend(find_redundant_rules(&combined.rules));
+    issues
+}
+
+/// Concatenates the inputs without checking for conflicts. Rules from later inputs are
+/// renumbered the same way `EdocumentAbac::merge` does.
+pub fn combine_inputs(inputs: &[EdocumentAbacData]) -> EdocumentAbacData {
+    let mut rules: Vec<EdocumentRule> = Vec::new();
+    for (n, data) in inputs.iter().enumerate() {
+        let next_id = rules.iter().map(|r| r.id + 1).max().unwrap_or(0);
+        for (offset, rule) in data.rules.iter().enumerate() {
+            let mut rule = rule.clone();
+            if n > 0 {
+                rule.id = next_id + offset;
+            }
+            rules.push(rule);
+        }
+    }
+    EdocumentAbacData {
+        users: inputs.iter().flat_map(|data| data.users.iter().cloned()).collect(),
+        resources: inputs.iter().flat_map(|data| data.resources.iter().cloned()).collect(),
+        rules,
+    }
+}
+
+fn find_conflicts(inputs: &[EdocumentAbacData]) -> Vec<PolicyIssue> {
+    let mut issues = Vec::new();
+    let mut users = HashMap::new();
+    let mut resources = HashMap::new();
+    for data in inputs {
+        for user in &data.users {
+            match users.get(&user.user_id) {
+                Some(existing) if existing != user => {
+                    issues.push(PolicyIssue::Conflict(format!("user '{}' is defined more than once with different attributes", user.user_id)));
+                },
+                Some(_) => {},
+                None => { users.insert(user.user_id.clone(), user.clone()); },
+            }
+        }
+        for resource in &data.resources {
+            match resources.get(&resource.resource_id) {
+                Some(existing) if existing != resource => {
+                    issues.push(PolicyIssue::Conflict(format!("resource '{}' is defined more than once with different attributes", resource.resource_id)));
+                },
+                Some(_) => {},
+                None => { resources.insert(resource.resource_id.clone(), resource.clone()); },
+            }
+        }
+    }
+    issues
+}
+
+fn lint_rules(data: &EdocumentAbacData) -> Vec<PolicyIssue> {
+    let mut issues = Vec::new();
+    for rule in &data.rules {
+        if let Err(errors) = validate_rule(rule) {
+            issues.extend(errors.into_iter().map(|e| PolicyIssue::Lint(e.to_string())));
+        }
+        if rule.actions.is_empty() {
+            issues.push(PolicyIssue::Lint(format!("rule {} grants no actions", rule.id)));
+        }
+        if rule.user_conditions.is_empty() && rule.resource_conditions.is_empty() && rule.comparison_conditions.is_empty() {
+            issues.push(PolicyIssue::Lint(format!("rule {} has no conditions and grants its actions to every user on every resource", rule.id)));
+        }
+    }
+    if let Err(e) = data.check_rule_references() {
+        issues.push(PolicyIssue::Lint(e.to_string()));
+    }
+    issues
+}
+
+/// A rule is redundant when another rule's conditions are a subset of its own
+/// and that rule grants at least the same actions. Identical rules report only the later one.
+fn find_redundant_rules(rules: &[EdocumentRule]) -> Vec<PolicyIssue> {
+    let mut issues = Vec::new();
+    for (i, rule) in rules.iter().enumerate() {
+        let covering = rules.iter().enumerate().find(|(j, other)| {
+            *j != i && covers(other, rule) && (*j < i || !covers(rule, other))
+        });
+        if let Some((_, other)) = covering {
+            issues.push(PolicyIssue::Redundant(format!("rule {} is already covered by rule {}", rule.id, other.id)));
+        }
+    }
+    issues
+}
+
+// `general` grants (or denies) everything `specific` does: the same effect and tenant scope,
+// fewer (or equal) conditions, at least the same actions
+fn covers(general: &EdocumentRule, specific: &EdocumentRule) -> bool {
+    general.effect == specific.effect
+        && general.cross_tenant == specific.cross_tenant
+        && specific.actions.is_subset(&general.actions)
+        && general.user_conditions.iter().all(|c| specific.user_conditions.contains(c))
+        && general.resource_conditions.iter().all(|c| specific.resource_conditions.contains(c))
+        && general.comparison_conditions.iter().all(|c| specific.comparison_conditions.contains(c))
+}
+
+/// Attribute names that some user or resource has a value for but no rule condition mentions,
+/// i.e. either dead data or a policy that was never written. `uid` and `rid` are the entity
+/// ids rather than data, so they are never reported.
+pub fn unused_attributes(data: &EdocumentAbacData) -> Vec<AttributeName> {
+    let referenced: HashSet<&AttributeName> = data.rules.iter()
+        .flat_map(|rule| rule.user_conditions.iter().chain(&rule.resource_conditions).chain(&rule.comparison_conditions))
+        .flat_map(|cond| [&cond.left, &cond.right])
+        .filter_map(|expr| match expr {
+            AttributeExpression::AttributeName(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    AttributeName::iter()
+        .filter(|attr_name| !matches!(attr_name, AttributeName::Uid | AttributeName::Rid))
+        .filter(|attr_name| !referenced.contains(attr_name))
+        .filter(|attr_name| {
+            data.users.iter().any(|u| has_value(u, attr_name)) || data.resources.iter().any(|r| has_value(r, attr_name))
+        })
+        .collect()
+}
+
+fn has_value<E: AttributeValueExtractor<AttributeName = AttributeName>>(entity: &E, attr_name: &AttributeName) -> bool {
+    entity.get_attribute_value(attr_name).is_some()
+        || entity.get_attribute_set(attr_name).is_some_and(|s| !s.is_empty())
+}
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::any::Any;
 use crate::types::types::{self, AttributeValueExtractor, Condition, ComparisonOperator};
 use rayon::prelude::*;
+use indicatif::ProgressBar;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Import specific types to avoid ambiguity
@@ -11,9 +12,9 @@ use crate::types::university_types::{
     AttributeExpression as UniversityAttributeExpression
 };
 use crate::types::edocument_types::{
-    EdocumentAbac, EdocumentRule, EdocumentUserAttribute, EdocumentResourceAttribute,
+    EdocumentAbac, EdocumentRule, EdocumentUserAttribute, EdocumentResourceAttribute, Effect, ActionHierarchy,
     AttributeName as EdocAttributeName, AttributeValue as EdocAttributeValue, 
-    AttributeExpression as EdocAttributeExpression
+    AttributeExpression as EdocAttributeExpression, Action as EdocAction
 };
 
 // Common trait for Rule types
@@ -234,9 +235,125 @@ impl AbacAnalyzer for EdocumentAbac {
     }
 }
 
+/// Explains why no rule grants `action` on `resource_id` to `user_id` by evaluating every rule
+/// against the concrete attributes, without Z3. Reports each failed condition, and a matching
+/// rule that lacks the action, for the permit rules that come closest (fewest failures). When a
+/// permit rule does grant the access, reports the deny rules that take it away instead.
+///
+/// Returns an empty list when the access is granted and no deny rule matches, e.g. when the
+/// denial came from a restriction on the solver rather than from the rules.
+pub fn explain_denial(abac_data: &EdocumentAbac, user_id: &str, resource_id: &str, action: &EdocAction) -> Result<Vec<String>, String> {
+    let user = abac_data.users.iter().find(|u| u.user_id == user_id)
+        .ok_or_else(|| format!("Unknown user: {}", user_id))?;
+    let resource = abac_data.resources.iter().find(|r| r.resource_id == resource_id)
+        .ok_or_else(|| format!("Unknown resource: {}", resource_id))?;
+    let (permits, denials): (Vec<&EdocumentRule>, Vec<&EdocumentRule>) = abac_data.rules.iter()
+        .partition(|rule| rule.effect == Effect::Permit);
+    if permits.is_empty() {
+        return Ok(vec!["there are no permit rules".to_string()]);
+    }
+
+    let mut explanations: Vec<Vec<String>> = Vec::new();
+    for rule in permits {
+        let failures = rule_failures(abac_data, user, resource, rule, action);
+        if failures.is_empty() {
+            return Ok(denials.into_iter()
+                .filter(|deny| rule_failures(abac_data, user, resource, deny, action).is_empty())
+                .map(|deny| format!("rule {}: grants {} but deny rule {} matches", rule.id, action, deny.id))
+                .collect());
+        }
+        explanations.push(failures);
+    }
+
+    let closest = explanations.iter().map(Vec::len).min().unwrap_or(0);
+    Ok(explanations.into_iter().filter(|failures| failures.len() == closest).flatten().collect())
+}
+
+/// Every permitted (user, resource, action) triple, found by evaluating each rule on every
+/// (user, resource) pair without Z3. Follows the solver's default policy: permit rules grant
+/// their actions closed under `action_hierarchy`, deny rules take theirs away along with the
+/// actions that imply them, a resource's `supported_actions` limit what anyone may do on it,
+/// and archived resources are read-only.
+/// Actions are in their camelCase form.
+pub fn permitted_triples(abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy) -> HashSet<(String, String, String)> {
+    abac_data.users.par_iter()
+        .flat_map_iter(|user| abac_data.resources.iter().map(move |resource| (user, resource)))
+        .flat_map_iter(|(user, resource)| {
+            let matching = |effect: Effect| -> HashSet<EdocAction> {
+                abac_data.rules.iter()
+                    .filter(|rule| rule.effect == effect && conditions_hold(abac_data, user, resource, rule))
+                    .flat_map(|rule| rule.affected_actions(action_hierarchy))
+                    .collect()
+            };
+            let denied = matching(Effect::Deny);
+            matching(Effect::Permit).into_iter()
+                .filter(|action| !denied.contains(action))
+                .filter(|action| resource.supported_actions.as_ref().is_none_or(|supported| supported.contains(action)))
+                .filter(|action| resource.is_archived != Some(true) || action.is_read_only())
+                .map(|action| (user.user_id.clone(), resource.resource_id.clone(), action.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn conditions_hold(abac_data: &EdocumentAbac, user: &EdocumentUserAttribute, resource: &EdocumentResourceAttribute, rule: &EdocumentRule) -> bool {
+    let holds = |result: Result<bool, Box<dyn std::error::Error + Send + Sync>>| result.unwrap_or(false);
+    rule.user_conditions.iter().all(|cond| holds(abac_data.evaluate_user_condition(user, cond)))
+        && rule.resource_conditions.iter().all(|cond| holds(abac_data.evaluate_resource_condition(resource, cond)))
+        && rule.comparison_conditions.iter().all(|cond| holds(abac_data.evaluate_comparison_condition(user, resource, cond)))
+}
+
+// Every condition of `rule` the pair fails, plus the missing action; empty when the rule matches
+fn rule_failures(
+    abac_data: &EdocumentAbac,
+    user: &EdocumentUserAttribute,
+    resource: &EdocumentResourceAttribute,
+    rule: &EdocumentRule,
+    action: &EdocAction,
+) -> Vec<String> {
+    let holds = |result: Result<bool, Box<dyn std::error::Error + Send + Sync>>| result.unwrap_or(false);
+    let mut failures = Vec::new();
+    for cond in &rule.user_conditions {
+        if !holds(abac_data.evaluate_user_condition(user, cond)) {
+            failures.push(format!("rule {}: user condition `{}` does not hold", rule.id, cond));
+        }
+    }
+    for cond in &rule.resource_conditions {
+        if !holds(abac_data.evaluate_resource_condition(resource, cond)) {
+            failures.push(format!("rule {}: resource condition `{}` does not hold", rule.id, cond));
+        }
+    }
+    for cond in &rule.comparison_conditions {
+        if !holds(abac_data.evaluate_comparison_condition(user, resource, cond)) {
+            failures.push(format!("rule {}: comparison condition `{}` does not hold", rule.id, cond));
+        }
+    }
+    if !rule.actions.contains(action) {
+        let mut granted: Vec<String> = rule.actions.iter().map(|a| a.to_string()).collect();
+        granted.sort();
+        let matched = if failures.is_empty() { "matches every condition but " } else { "" };
+        failures.push(format!("rule {}: {}grants {{{}}}, not {}", rule.id, matched, granted.join(" "), action));
+    }
+    failures
+}
+
 pub fn simple_loop<T: AbacAnalyzer>(
     abac_data: T,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    T::UserAttribute: std::fmt::Debug,
+    T::ResourceAttribute: std::fmt::Debug,
+    T::Rule: std::fmt::Debug,
+{
+    simple_loop_with_progress(abac_data, None)
+}
+
+/// `simple_loop` that advances `progress` by the rule's (user, resource) pairs after each rule.
+/// The bar's length is set to rules × users × resources.
+pub fn simple_loop_with_progress<T: AbacAnalyzer>(
+    abac_data: T,
+    progress: Option<&ProgressBar>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
 where
     T::UserAttribute: std::fmt::Debug,
     T::ResourceAttribute: std::fmt::Debug,
@@ -245,6 +362,8 @@ where
     let users = abac_data.get_users();
     let resources = abac_data.get_resources();
     let rules = abac_data.get_rules();
+    let pairs_per_rule = (users.len() * resources.len()) as u64;
+    if let Some(bar) = progress { bar.set_length(rules.len() as u64 * pairs_per_rule); }
 
     let rule_start_time = std::time::Instant::now();
     for rule in rules {
@@ -328,6 +447,7 @@ where
         }
 
         println!("Rule {}: Valid (user, resource) combinations count: {}", rule.id(), valid_combinations.len());
+        if let Some(bar) = progress { bar.inc(pairs_per_rule); }
     }
     let rule_duration = rule_start_time.elapsed();
     println!("Rule processing time: {:.2?}", rule_duration);
@@ -338,6 +458,22 @@ where
 pub fn improved_simple_loop<T, N, V>(
     abac_data: T,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    T: AbacAnalyzer<AttributeExpression = crate::types::types::AttributeExpression<N, V>>,
+    T::UserAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V>,
+    T::ResourceAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V>,
+    T::Rule: std::fmt::Debug,
+    N: Eq + std::hash::Hash + Clone + std::fmt::Debug + 'static,
+    V: Eq + std::hash::Hash + Clone + std::fmt::Debug,
+{
+    improved_simple_loop_with_progress(abac_data, None)
+}
+
+/// `improved_simple_loop` that advances `progress` the same way as `simple_loop_with_progress`
+pub fn improved_simple_loop_with_progress<T, N, V>(
+    abac_data: T,
+    progress: Option<&ProgressBar>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
 where
     T: AbacAnalyzer<AttributeExpression = crate::types::types::AttributeExpression<N, V>>,
     T::UserAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V>,
@@ -349,6 +485,8 @@ where
     let users = abac_data.get_users();
     let resources = abac_data.get_resources();
     let rules = abac_data.get_rules();
+    let pairs_per_rule = (users.len() * resources.len()) as u64;
+    if let Some(bar) = progress { bar.set_length(rules.len() as u64 * pairs_per_rule); }
 
     let rule_start_time = std::time::Instant::now();
     for rule in rules {
@@ -528,6 +666,7 @@ where
         }
 
         println!("Rule {}: Valid (user, resource) combinations count: {}", rule.id(), valid_combinations_count);
+        if let Some(bar) = progress { bar.inc(pairs_per_rule); }
     }
     let rule_duration = rule_start_time.elapsed();
     println!("Rule processing time: {:.2?}", rule_duration);
@@ -538,6 +677,26 @@ where
 pub fn parallel_indexed_loop<T, N, V>(
     abac_data: T,
 ) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: AbacAnalyzer<AttributeExpression = crate::types::types::AttributeExpression<N, V>> + Send + Sync,
+    T::UserAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V> + Send + Sync,
+    T::ResourceAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V> + Send + Sync,
+    T::Rule: std::fmt::Debug + Send + Sync,
+    N: Eq + std::hash::Hash + Clone + std::fmt::Debug + Send + Sync + 'static,
+    V: Eq + std::hash::Hash + Clone + std::fmt::Debug + Send + Sync,
+    <T as AbacAnalyzer>::UserAttribute: Send + Sync,
+    <T as AbacAnalyzer>::ResourceAttribute: Send + Sync,
+{
+    parallel_indexed_loop_with_progress(abac_data, None)
+}
+
+/// `parallel_indexed_loop` that advances `progress` as each rule finishes. The rules run on
+/// rayon threads; `ProgressBar::inc` adds to one shared atomic position, so the bar shows
+/// the total over all of them.
+pub fn parallel_indexed_loop_with_progress<T, N, V>(
+    abac_data: T,
+    progress: Option<&ProgressBar>,
+) -> Result<(), Box<dyn std::error::Error>>
 where
     T: AbacAnalyzer<AttributeExpression = crate::types::types::AttributeExpression<N, V>> + Send + Sync,
     T::UserAttribute: std::fmt::Debug + AttributeValueExtractor<AttributeName = N, AttributeValue = V> + Send + Sync,
@@ -551,6 +710,8 @@ where
     let users = abac_data.get_users();
     let resources = abac_data.get_resources();
     let rules = abac_data.get_rules();
+    let pairs_per_rule = (users.len() * resources.len()) as u64;
+    if let Some(bar) = progress { bar.set_length(rules.len() as u64 * pairs_per_rule); }
 
     let rule_start_time = std::time::Instant::now();
 
@@ -744,6 +905,7 @@ where
             }
         }
         println!("Rule {}: Valid (user, resource) combinations count: {}", rule.id(), valid_combinations_count);
+        if let Some(bar) = progress { bar.inc(pairs_per_rule); }
 
         Ok((rule.id(), valid_combinations_count))
     }).collect(); // Collect results from parallel processing
@@ -783,9 +945,9 @@ enum AttributeSource {
 fn get_edoc_attribute_source_internal(attr_name: &EdocAttributeName) -> AttributeSource {
      use EdocAttributeName::*;
      match attr_name {
-        Role | Position | Registered | Projects | Supervisor | Supervisee | PayrollingPermissions | ClearanceLevel | Uid => AttributeSource::User,
+        Role | Position | Registered | Projects | Supervisor | Supervisee | DelegatedAuthority | Certifications | PayrollingPermissions | ClearanceLevel | BudgetAuthority | Uid => AttributeSource::User,
         SecurityLevel | Type | Owner | Recipients | IsConfidential | ContainsPersonalInfo | Rid => AttributeSource::Resource,
-        Tenant | Department | Office => AttributeSource::Both, //
+        Tenant | Department | Office | Region => AttributeSource::Both, //
         _ => AttributeSource::None, // Should not happen if all
     }
 }
@@ -903,6 +1065,10 @@ fn evaluate_set_membership<T: PartialEq + std::fmt::Debug>(value: &GenericCondit
         (GenericConditionValue::Single(val), GenericConditionValue::Set(set_vals)) => {
             Ok(set_vals.contains(val))
         },
+        // A set on the left is a member only if every one of its values is
+        (GenericConditionValue::Set(vals), GenericConditionValue::Set(set_vals)) => {
+            Ok(vals.iter().all(|val| set_vals.contains(val)))
+        },
         (GenericConditionValue::None, _) => Ok(false),
         // This is a special case to handle single value containment as equality check
         (GenericConditionValue::Single(left_val), GenericConditionValue::Single(right_val)) => {
@@ -914,6 +1080,10 @@ fn evaluate_set_membership<T: PartialEq + std::fmt::Debug>(value: &GenericCondit
 
 /// ContainedIn演算子の評価: 左の値が右の集合に含まれる
 fn evaluate_contained_in<T: PartialEq + std::fmt::Debug>(left: &GenericConditionValue<T>, right: &GenericConditionValue<T>) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    // 左が複数値の属性（ユーザーのロールなど）の場合は、いずれかの値が右の集合に含まれればよい
+    if let (GenericConditionValue::Set(vals), GenericConditionValue::Set(set_vals)) = (left, right) {
+        return Ok(vals.iter().any(|val| set_vals.contains(val)));
+    }
     evaluate_set_membership(left, right)
         .map_err(|e| format!("Invalid ContainedIn operation: {:?} [ {:?} ({})", left, right, e).into())
 }
@@ -930,9 +1100,14 @@ fn evaluate_equals<T: PartialEq + std::fmt::Debug>(left: &GenericConditionValue<
         (GenericConditionValue::Single(left_val), GenericConditionValue::Single(right_val)) => {
             Ok(left_val == right_val)
         },
+        // 値の集合との比較は「いずれかに等しい」、複数値の属性は「いずれかの値が等しい」
+        (GenericConditionValue::Single(_), GenericConditionValue::Set(_))
+        | (GenericConditionValue::Set(_), GenericConditionValue::Set(_)) => evaluate_contained_in(left, right),
+        (GenericConditionValue::Set(left_vals), GenericConditionValue::Single(right_val)) => {
+            Ok(left_vals.contains(right_val))
+        },
         (GenericConditionValue::None, GenericConditionValue::None) => Ok(true),
         (GenericConditionValue::None, _) | (_, GenericConditionValue::None) => Ok(false),
-        _ => Err(format!("Invalid Equals operation: {:?} = {:?}", left, right).into()),
     }
 }
 
@@ -1,11 +1,37 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use serde::{Serialize, Deserialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 // 再エクスポートして他のモジュールから使えるようにする
-pub use crate::types::types::{ComparisonOperator, AttributeValueExtractor, UserAttribute, ResourceAttribute, Condition, AbacData, ParseError, DomainParser, GenericAbacParser};
+pub use crate::types::types::{ComparisonOperator, AttributeValueExtractor, UserAttribute, ResourceAttribute, Condition, AbacData, ParseError, AbacError, DomainParser, GenericAbacParser, suggest_closest};
+
+// 集合は要素の文字列表現の順に並べて書き出す
+// HashSet の反復順は実行ごとに変わるため、同じデータが常に同じ JSON になるようにする
+fn sorted_set<T: Serialize + std::fmt::Display, S: serde::Serializer>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut items: Vec<&T> = set.iter().collect();
+    items.sort_by_key(|item| item.to_string());
+    items.serialize(serializer)
+}
+
+// 予算額は整数属性として i64 で符号化されるため、i64::MAX を超える値は読み込み時にエラーにする
+fn encodable_budget<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let budget = Option::<u64>::deserialize(deserializer)?;
+    match budget {
+        Some(b) if i64::try_from(b).is_err() => Err(serde::de::Error::custom(format!("budget_authority {} is larger than {}", b, i64::MAX))),
+        _ => Ok(budget),
+    }
+}
+
+fn sorted_optional_set<T: Serialize + std::fmt::Display, S: serde::Serializer>(set: &Option<HashSet<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match set {
+        Some(set) => sorted_set(set, serializer),
+        None => serializer.serialize_none(),
+    }
+}
 
 // ユーザーの役職を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Role {
     Employee,
     Manager,
@@ -15,7 +41,8 @@ pub enum Role {
 }
 
 // ポジションを表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Position {
     Secretary,
     Director,
@@ -26,7 +53,8 @@ pub enum Position {
 }
 
 // ドキュメントの種類を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum DocumentType {
     Invoice,
     Contract,
@@ -38,7 +66,8 @@ pub enum DocumentType {
 }
 
 // テナントを表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Tenant {
     LargeBank,
     LargeBankLeasing,
@@ -132,7 +161,8 @@ pub enum Office {
 }
 
 // アクション（権限）を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Action {
     View,
     Send,
@@ -142,20 +172,60 @@ pub enum Action {
     Approve,
 }
 
+impl Action {
+    // ドキュメントを変更しないアクションかどうか
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Action::View | Action::Search | Action::ReadMetaInfo)
+    }
+}
+
+// ルールの効果: Permit は一致した三つ組を許可し、Deny は他のルールが許可していても拒否する
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum Effect {
+    #[default]
+    Permit,
+    Deny,
+}
+
+// 一部の属性だけを指定したユーザー像（例: 「CS部門の faculty」）
+// 指定しなかった属性は任意の値をとりうるものとして扱う
+#[derive(Debug, Clone, Default)]
+pub struct PartialUserAttributes {
+    pub values: HashMap<AttributeName, AttributeValue>,
+    pub sets: HashMap<AttributeName, HashSet<AttributeValue>>,
+}
+
+// 上位アクションから、それが含意する下位アクションへの対応表
+// 例: view を許可されたユーザーは readMetaInfo と search も行える
+pub type ActionHierarchy = HashMap<Action, Vec<Action>>;
+
+// 組み込みのアクション階層（view ⇒ readMetaInfo, search）
+pub fn default_action_hierarchy() -> ActionHierarchy {
+    let mut hierarchy = HashMap::new();
+    hierarchy.insert(Action::View, vec![Action::ReadMetaInfo, Action::Search]);
+    hierarchy
+}
+
 // 属性名の型
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum AttributeName {
     Role,
     Position,
     Tenant,
     Department,
     Office,
+    Region,
     Registered,
     Projects,
     Supervisor,
     Supervisee,
+    DelegatedAuthority,
+    Certifications,
     PayrollingPermissions,
     ClearanceLevel, // Added
+    BudgetAuthority,
     SecurityLevel, // Added
     Type,
     Owner,
@@ -164,6 +234,10 @@ pub enum AttributeName {
     ContainsPersonalInfo,
     Uid,
     Rid,
+    // ソルバーに登録されたカスタム述語（未知の属性名はここに入る）
+    // .abac では custom.onCall のように接頭辞を付けて参照する
+    #[strum(default)]
+    Custom(String),
 }
 
 // 属性値の型
@@ -175,10 +249,26 @@ pub enum AttributeValue {
     ResourceType(DocumentType),
     String(String),
     Boolean(bool),
-    Integer(i32),
+    Integer(i64),
     StringSet(Vec<String>),
 }
 
+// 属性値を.abacファイルの表記で表示
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Role(role) => write!(f, "{}", role),
+            AttributeValue::Position(position) => write!(f, "{}", position),
+            AttributeValue::Tenant(tenant) => write!(f, "{}", tenant),
+            AttributeValue::ResourceType(doc_type) => write!(f, "{}", doc_type),
+            AttributeValue::String(s) => write!(f, "{}", s),
+            AttributeValue::Boolean(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            AttributeValue::Integer(i) => write!(f, "{}", i),
+            AttributeValue::StringSet(vals) => write!(f, "{{{}}}", vals.join(" ")),
+        }
+    }
+}
+
 impl PartialOrd for AttributeValue {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match (self, other) {
@@ -198,11 +288,15 @@ impl Ord for AttributeValue {
 pub type AttributeExpression = crate::types::types::AttributeExpression<AttributeName, AttributeValue>;
 
 // ユーザー属性の具体的な型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EdocumentUserAttribute {
     pub user_id: String,
+    // 非推奨: 単一ロールの旧形式。読み込みの後方互換のために残しており、roles と合わせて扱う
     #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<Role>,
+    // ユーザーが持つロール（複数可）
+    #[serde(default, skip_serializing_if = "HashSet::is_empty", serialize_with = "sorted_set")]
+    pub roles: HashSet<Role>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<Position>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -211,16 +305,36 @@ pub struct EdocumentUserAttribute {
     pub department: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub office: Option<String>,
+    // データ所在地の地域（例: "eu"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub registered: Option<bool>,
+    #[serde(serialize_with = "sorted_set")]
     pub projects: HashSet<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub supervisor: Option<String>,
+    #[serde(serialize_with = "sorted_set")]
     pub supervisee: HashSet<String>,
+    // このユーザーが自分の権限を委任しているユーザーのID
+    #[serde(default, skip_serializing_if = "HashSet::is_empty", serialize_with = "sorted_set")]
+    pub delegated_authority: HashSet<String>,
+    // 保有資格（例: "ISO27001"）
+    #[serde(default, skip_serializing_if = "HashSet::is_empty", serialize_with = "sorted_set")]
+    pub certifications: HashSet<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payrolling_permissions: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub clearance_level: Option<i32>,
+    // 承認できる金額の上限。i64::MAX を超える値は JSON でも .abac でも読み込めない
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "encodable_budget")]
+    pub budget_authority: Option<u64>,
+    // "09:00-17:00" 形式の勤務時間帯。日付をまたぐ "22:00-06:00" も可
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_hours: Option<String>,
+    // 雇用形態
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_type: Option<ContractType>,
 }
 
 impl EdocumentUserAttribute {
@@ -228,18 +342,30 @@ impl EdocumentUserAttribute {
         Self {
             user_id,
             role: None,
+            roles: HashSet::new(),
             position: None,
             tenant: None,
             department: None,
             office: None,
+            region: None,
             registered: None,
             projects: HashSet::new(),
             supervisor: None,
             supervisee: HashSet::new(),
+            delegated_authority: HashSet::new(),
+            certifications: HashSet::new(),
             payrolling_permissions: None,
             clearance_level: None,
+            budget_authority: None,
+            working_hours: None,
+            contract_type: None,
         }
     }
+
+    /// `roles` に旧形式の `role` を加えた、ユーザーが持つすべてのロール
+    pub fn all_roles(&self) -> HashSet<Role> {
+        self.roles.iter().chain(&self.role).cloned().collect()
+    }
 }
 
 impl AttributeValueExtractor for EdocumentUserAttribute {
@@ -248,9 +374,6 @@ impl AttributeValueExtractor for EdocumentUserAttribute {
 
     fn get_attribute_value(&self, attr_name: &AttributeName) -> Option<AttributeValue> {
         match attr_name {
-            AttributeName::Role => {
-                self.role.as_ref().map(|r| AttributeValue::Role(r.clone()))
-            },
             AttributeName::Position => {
                 self.position.as_ref().map(|p| AttributeValue::Position(p.clone()))
             },
@@ -263,6 +386,9 @@ impl AttributeValueExtractor for EdocumentUserAttribute {
             AttributeName::Office => {
                 self.office.as_ref().map(|o| AttributeValue::String(o.clone()))
             },
+            AttributeName::Region => {
+                self.region.as_ref().map(|r| AttributeValue::String(r.clone()))
+            },
             AttributeName::Registered => {
                 self.registered.map(|r| AttributeValue::Boolean(r))
             },
@@ -270,7 +396,10 @@ impl AttributeValueExtractor for EdocumentUserAttribute {
                 self.payrolling_permissions.map(|p| AttributeValue::Boolean(p))
             },
             AttributeName::ClearanceLevel => {
-                self.clearance_level.map(|cl| AttributeValue::Integer(cl))
+                self.clearance_level.map(|cl| AttributeValue::Integer(cl.into()))
+            },
+            AttributeName::BudgetAuthority => {
+                self.budget_authority.map(|b| AttributeValue::Integer(i64::try_from(b).expect("budgets above i64::MAX are rejected when read")))
             },
             AttributeName::Uid => {
                 Some(AttributeValue::String(self.user_id.clone()))
@@ -281,12 +410,21 @@ impl AttributeValueExtractor for EdocumentUserAttribute {
 
     fn get_attribute_set(&self, attr_name: &AttributeName) -> Option<Vec<AttributeValue>> {
         match attr_name {
+            AttributeName::Role => {
+                Some(self.all_roles().into_iter().map(AttributeValue::Role).collect())
+            },
             AttributeName::Projects => {
                 Some(self.projects.iter().map(|p| AttributeValue::String(p.clone())).collect())
             },
             AttributeName::Supervisee => {
                 Some(self.supervisee.iter().map(|s| AttributeValue::String(s.clone())).collect())
             },
+            AttributeName::DelegatedAuthority => {
+                Some(self.delegated_authority.iter().map(|d| AttributeValue::String(d.clone())).collect())
+            },
+            AttributeName::Certifications => {
+                Some(self.certifications.iter().map(|c| AttributeValue::String(c.clone())).collect())
+            },
             _ => None,
         }
     }
@@ -299,7 +437,7 @@ impl UserAttribute for EdocumentUserAttribute {
 }
 
 // リソース属性の具体的な型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EdocumentResourceAttribute {
     pub resource_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -312,13 +450,37 @@ pub struct EdocumentResourceAttribute {
     pub department: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub office: Option<String>,
+    // データ所在地の地域（例: "eu"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(serialize_with = "sorted_set")]
     pub recipients: HashSet<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_confidential: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub contains_personal_info: Option<bool>,
+    // アーカイブ済みのドキュメントは読み取り専用のアクションしか受け付けない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_archived: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security_level: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // 同じ論理ドキュメントの版どうしが共有する識別子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_family: Option<String>,
+    // 作成日（"YYYY-MM-DD"）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_date: Option<String>,
+    // 作成日からの保存期間（日数）。経過後は読み取り専用になる
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_period_days: Option<i64>,
+    // リソース自体が受け付けるアクション。指定がある場合、それ以外のアクションは誰にも許可されない
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "sorted_optional_set")]
+    pub supported_actions: Option<HashSet<Action>>,
+    // 承認ワークフロー上の状態
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_status: Option<ApprovalStatus>,
 }
 
 impl EdocumentResourceAttribute {
@@ -332,26 +494,23 @@ impl EdocumentResourceAttribute {
             tenant: None,
             department: None,
             office: None,
+            region: None,
             recipients: HashSet::new(),
             is_confidential: None,
+            is_archived: None,
             contains_personal_info: None,
             security_level: None,
+            version: None,
+            document_family: None,
+            created_date: None,
+            retention_period_days: None,
+            supported_actions: None,
+            approval_status: None,
         })
     }
 
     fn parse_document_type(doc_type: &str) -> Result<DocumentType, ParseError> {
-        match doc_type {
-            "bankingNote" => Ok(DocumentType::BankingNote),
-            "trafficFine" => Ok(DocumentType::TrafficFine),
-            "salesOffer" => Ok(DocumentType::SalesOffer),
-            "contract" => Ok(DocumentType::Contract),
-            "invoice" => Ok(DocumentType::Invoice),
-            "paycheck" => Ok(DocumentType::Paycheck),
-            _ => Err(ParseError::UnknownValue(
-                "resource_type".to_string(), 
-                doc_type.to_string()
-            )),
-        }
+        parse_enum("resource_type", doc_type)
     }
 }
 
@@ -376,6 +535,9 @@ impl AttributeValueExtractor for EdocumentResourceAttribute {
             AttributeName::Office => {
                 self.office.as_ref().map(|o| AttributeValue::String(o.clone()))
             },
+            AttributeName::Region => {
+                self.region.as_ref().map(|r| AttributeValue::String(r.clone()))
+            },
             AttributeName::IsConfidential => {
                 self.is_confidential.map(|ic| AttributeValue::Boolean(ic))
             },
@@ -383,7 +545,7 @@ impl AttributeValueExtractor for EdocumentResourceAttribute {
                 self.contains_personal_info.map(|cpi| AttributeValue::Boolean(cpi))
             },
             AttributeName::SecurityLevel => {
-                self.security_level.map(|sl| AttributeValue::Integer(sl))
+                self.security_level.map(|sl| AttributeValue::Integer(sl.into()))
             },
             AttributeName::Rid => {
                 Some(AttributeValue::String(self.resource_id.clone()))
@@ -415,8 +577,15 @@ pub struct EdocumentRule {
     pub description: String,
     pub user_conditions: Vec<Condition<AttributeExpression>>,      // ユーザー条件
     pub resource_conditions: Vec<Condition<AttributeExpression>>,  // リソース条件
+    #[serde(serialize_with = "sorted_set")]
     pub actions: HashSet<Action>,             // アクション
-    pub comparison_conditions: Vec<Condition<AttributeExpression>>, // 比較条件
+    // 比較条件。owner = uid や recipients ] uid のように、要求したユーザーとリソースを結びつける条件はここに書く
+    pub comparison_conditions: Vec<Condition<AttributeExpression>>,
+    #[serde(default)]
+    pub effect: Effect,                       // 省略時は Permit
+    // テナント分離を有効にしたとき、別テナントのリソースへのアクセスを許可できるのはこの印のあるルールだけ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cross_tenant: bool,
 }
 
 impl EdocumentRule {
@@ -428,6 +597,49 @@ impl EdocumentRule {
             resource_conditions: Vec::new(),
             actions: HashSet::new(),
             comparison_conditions: Vec::new(),
+            effect: Effect::Permit,
+            cross_tenant: false,
+        }
+    }
+
+    /// ルールが許可するアクションに、階層で含意される下位アクションを加えた集合を返します
+    /// 含意は一方向のみで、下位アクションから上位アクションは導かれません
+    pub fn implied_actions(&self, hierarchy: &ActionHierarchy) -> HashSet<Action> {
+        let mut implied = self.actions.clone();
+        let mut pending: Vec<Action> = self.actions.iter().cloned().collect();
+        while let Some(action) = pending.pop() {
+            for lower in hierarchy.get(&action).into_iter().flatten() {
+                if implied.insert(lower.clone()) {
+                    pending.push(lower.clone());
+                }
+            }
+        }
+        implied
+    }
+
+    /// 拒否ルールが禁止するアクションの集合を返します
+    /// 指定されたアクションに加え、それを含意する上位アクションも禁止されます（view を禁止すれば edit も禁止）
+    /// 下位アクションには広げないので、view を禁止しても readMetaInfo は許可されたままです
+    pub fn denied_actions(&self, hierarchy: &ActionHierarchy) -> HashSet<Action> {
+        let mut denied = self.actions.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (higher, lowers) in hierarchy {
+                if !denied.contains(higher) && lowers.iter().any(|lower| denied.contains(lower)) {
+                    denied.insert(higher.clone());
+                    changed = true;
+                }
+            }
+        }
+        denied
+    }
+
+    /// ルールの効果に応じて、許可または禁止するアクションの集合を返します
+    pub fn affected_actions(&self, hierarchy: &ActionHierarchy) -> HashSet<Action> {
+        match self.effect {
+            Effect::Permit => self.implied_actions(hierarchy),
+            Effect::Deny => self.denied_actions(hierarchy),
         }
     }
 }
@@ -435,28 +647,685 @@ impl EdocumentRule {
 pub type EdocumentAbac = AbacData<EdocumentUserAttribute, EdocumentResourceAttribute, EdocumentRule>;
 pub use EdocumentAbac as EdocumentAbacData;
 
-impl std::fmt::Display for AttributeName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+// 文書管理ドメイン固有のパーサー（data/edocument.abac の形式）
+// 値は camelCase で書き、"none" は属性が無いことを表す
+pub struct EdocumentDomainParser;
+
+pub type EdocumentAbacParser = GenericAbacParser<EdocumentDomainParser>;
+
+impl DomainParser for EdocumentDomainParser {
+    type UserAttribute = EdocumentUserAttribute;
+    type ResourceAttribute = EdocumentResourceAttribute;
+    type Rule = EdocumentRule;
+    type AttributeExpression = AttributeExpression;
+    type AttributeName = AttributeName;
+    type AttributeValue = AttributeValue;
+    type Action = Action;
+
+    fn parse_attribute_name(&self, s: &str) -> Result<AttributeName, ParseError> {
+        // カスタム述語は custom. の接頭辞で明示する
+        if let Some(name) = s.strip_prefix("custom.").filter(|name| !name.is_empty()) {
+            return Ok(AttributeName::Custom(name.to_string()));
+        }
+        // 未知の名前は Custom になるので、ここでは既知の属性名だけを受け付ける
+        match AttributeName::from_str(s) {
+            Ok(AttributeName::Custom(_)) | Err(_) => Err(ParseError::UnknownValue("attribute_name".to_string(), s.to_string())),
+            Ok(name) => Ok(name),
+        }
+    }
+
+    fn parse_attribute_value(&self, s: &str) -> Result<AttributeValue, ParseError> {
+        if s == "True" || s == "true" || s == "False" || s == "false" {
+            return Ok(AttributeValue::Boolean(parse_boolean(s)?));
+        }
+        if let Some(role) = parse_variant::<Role>(s) {
+            return Ok(AttributeValue::Role(role));
+        }
+        if let Some(position) = parse_variant::<Position>(s) {
+            return Ok(AttributeValue::Position(position));
+        }
+        if let Some(tenant) = parse_variant::<Tenant>(s) {
+            return Ok(AttributeValue::Tenant(tenant));
+        }
+        if let Some(doc_type) = parse_variant::<DocumentType>(s) {
+            return Ok(AttributeValue::ResourceType(doc_type));
+        }
+        if let Ok(i) = s.parse::<i64>() {
+            return Ok(AttributeValue::Integer(i));
+        }
+        // 部門やオフィス、ユーザーIDなどは文字列として扱う
+        Ok(AttributeValue::String(s.to_string()))
+    }
+
+    fn parse_action(&self, s: &str) -> Result<Action, ParseError> {
+        parse_enum("action", s)
+    }
+
+    fn parse_attribute_expression(&self, expr_str: &str) -> Result<AttributeExpression, ParseError> {
+        let expr_str = expr_str.trim();
+
+        if let Ok(name) = self.parse_attribute_name(expr_str) {
+            return Ok(AttributeExpression::AttributeName(name));
+        }
+
+        if expr_str.starts_with('{') && expr_str.ends_with('}') {
+            let values = expr_str[1..expr_str.len() - 1]
+                .split_whitespace()
+                .map(|s| self.parse_attribute_value(s))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(AttributeExpression::ValueSet(values));
+        }
+
+        Ok(AttributeExpression::AttributeValue(self.parse_attribute_value(expr_str)?))
+    }
+
+    fn parse_user_attribute_field(
+        &self,
+        user: &mut EdocumentUserAttribute,
+        key: &str,
+        value: &str
+    ) -> Result<(), ParseError> {
+        match key {
+            // role=employee;auditor、role={employee auditor}、role= の繰り返しのいずれも可
+            "role" => {
+                for role in parse_string_set(&value.replace(';', " ")) {
+                    user.roles.insert(parse_enum("role", &role)?);
+                }
+            }
+            "position" => user.position = Some(parse_enum("position", value)?),
+            "tenant" => user.tenant = Some(parse_enum("tenant", value)?),
+            "department" => user.department = parse_optional_string(value),
+            "office" => user.office = parse_optional_string(value),
+            "region" => user.region = parse_optional_string(value),
+            "registered" => user.registered = Some(parse_boolean(value)?),
+            "projects" => user.projects = parse_string_set(value),
+            "supervisor" => user.supervisor = parse_optional_string(value),
+            "supervisee" => user.supervisee = parse_string_set(value),
+            "delegatedAuthority" => user.delegated_authority = parse_string_set(value),
+            "certifications" => user.certifications = parse_string_set(value),
+            "payrollingPermissions" => user.payrolling_permissions = Some(parse_boolean(value)?),
+            "clearanceLevel" => user.clearance_level = Some(parse_integer(value)?),
+            "budgetAuthority" => user.budget_authority = Some(parse_budget(value)?),
+            "workingHours" => user.working_hours = parse_optional_string(value),
+            "contractType" => user.contract_type = Some(parse_enum("contractType", value)?),
+            _ => {
+                // 未知の属性は無視
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_resource_attribute_field(
+        &self,
+        resource: &mut EdocumentResourceAttribute,
+        key: &str,
+        value: &str
+    ) -> Result<(), ParseError> {
+        match key {
+            "owner" => resource.owner = parse_optional_string(value),
+            "tenant" => resource.tenant = Some(parse_enum("tenant", value)?),
+            "department" => resource.department = parse_optional_string(value),
+            "office" => resource.office = parse_optional_string(value),
+            "region" => resource.region = parse_optional_string(value),
+            "recipients" => resource.recipients = parse_string_set(value),
+            "isConfidential" => resource.is_confidential = Some(parse_boolean(value)?),
+            "isArchived" => resource.is_archived = Some(parse_boolean(value)?),
+            "containsPersonalInfo" => resource.contains_personal_info = Some(parse_boolean(value)?),
+            "securityLevel" => resource.security_level = Some(parse_integer(value)?),
+            "version" => resource.version = parse_optional_string(value),
+            "documentFamily" => resource.document_family = parse_optional_string(value),
+            "createdDate" => resource.created_date = parse_optional_string(value),
+            "retentionPeriodDays" => resource.retention_period_days = Some(parse_integer(value)?),
+            "supportedActions" => {
+                let actions = parse_string_set(value).iter()
+                    .map(|action| self.parse_action(action))
+                    .collect::<Result<HashSet<_>, _>>()?;
+                resource.supported_actions = Some(actions);
+            }
+            "approvalStatus" => resource.approval_status = Some(parse_enum("approvalStatus", value)?),
+            _ => {
+                // 未知の属性は無視
+            }
+        }
+        Ok(())
+    }
+
+    fn create_user_attribute(&self, user_id: String) -> EdocumentUserAttribute {
+        EdocumentUserAttribute::new(user_id)
+    }
+
+    fn create_resource_attribute(&self, resource_id: String, resource_type: &str) -> Result<EdocumentResourceAttribute, ParseError> {
+        EdocumentResourceAttribute::new(resource_id, resource_type)
+    }
+
+    fn create_rule(
+        &self,
+        id: usize,
+        user_conditions: Vec<Condition<AttributeExpression>>,
+        resource_conditions: Vec<Condition<AttributeExpression>>,
+        actions: HashSet<Action>,
+        comparison_conditions: Vec<Condition<AttributeExpression>>,
+    ) -> EdocumentRule {
+        EdocumentRule {
+            user_conditions,
+            resource_conditions,
+            actions,
+            comparison_conditions,
+            ..EdocumentRule::new(id)
+        }
+    }
+
+    fn parse_rule_effect(&self, rule: &mut EdocumentRule, value: &str) -> Result<(), ParseError> {
+        rule.effect = match value {
+            "permit" => Effect::Permit,
+            "deny" => Effect::Deny,
+            _ => return Err(ParseError::UnknownValue("effect".to_string(), value.to_string())),
+        };
+        Ok(())
+    }
+}
+
+fn parse_enum<E: FromStr + IntoEnumIterator + std::fmt::Display>(value_type: &str, value: &str) -> Result<E, ParseError> {
+    parse_variant(value).ok_or_else(|| unknown_variant::<E>(value_type, value))
+}
+
+// 列挙型の値を、大文字小文字と "_" "-" の違いを無視して探す（senior_office_manager も SeniorOfficeManager も可）
+// 正規化すると複数の値に一致する綴りは曖昧なので、どれにも解釈しない
+fn parse_variant<E: FromStr + IntoEnumIterator + std::fmt::Display>(value: &str) -> Option<E> {
+    if let Ok(variant) = E::from_str(value) {
+        return Some(variant);
+    }
+    let normalize = |s: &str| -> String {
+        s.chars().filter(|c| *c != '_' && *c != '-').map(|c| c.to_ascii_lowercase()).collect()
+    };
+    let wanted = normalize(value);
+    let mut matching = E::iter().filter(|variant| normalize(&variant.to_string()) == wanted);
+    let variant = matching.next()?;
+    matching.next().is_none().then_some(variant)
+}
+
+// 列挙型として解釈できない値のエラー。綴りの近い値があれば候補として示す
+fn unknown_variant<E: IntoEnumIterator + std::fmt::Display>(value_type: &str, value: &str) -> ParseError {
+    let names: Vec<String> = E::iter().map(|variant| variant.to_string()).collect();
+    let candidates: Vec<&str> = names.iter().map(String::as_str).collect();
+    match suggest_closest(value, &candidates) {
+        Some(suggestion) => ParseError::Misspelled(value_type.to_string(), value.to_string(), suggestion.to_string()),
+        None => ParseError::UnknownValue(value_type.to_string(), value.to_string()),
+    }
+}
+
+fn parse_boolean(value: &str) -> Result<bool, ParseError> {
+    match value {
+        "True" | "true" => Ok(true),
+        "False" | "false" => Ok(false),
+        _ => Err(ParseError::UnknownValue("boolean".to_string(), value.to_string())),
+    }
+}
+
+fn parse_integer<T: FromStr>(value: &str) -> Result<T, ParseError> {
+    T::from_str(value).map_err(|_| ParseError::UnknownValue("integer".to_string(), value.to_string()))
+}
+
+// i64 に収まらない予算額は、符号化で値を変えてしまわないようにエラーにする
+fn parse_budget(value: &str) -> Result<u64, ParseError> {
+    let budget: u64 = parse_integer(value)?;
+    i64::try_from(budget).map(|_| budget).map_err(|_| ParseError::UnknownValue(format!("budget (at most {})", i64::MAX), value.to_string()))
+}
+
+fn parse_optional_string(value: &str) -> Option<String> {
+    (value != "none").then(|| value.to_string())
+}
+
+// "{a b c}" 形式の集合（波括弧なしの単一値も可）
+fn parse_string_set(value: &str) -> HashSet<String> {
+    let content = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')).unwrap_or(value);
+    content.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+// "YYYY-MM-DD" 形式の日付を 1970-01-01 からの日数に変換する（解釈できなければ None）
+pub fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+    // 3月始まりの暦で数えると、うるう日が年の最後に来るので計算が単純になる
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+// "HH:MM-HH:MM" 形式の時間帯を、0時からの分数の組 (開始, 終了) に変換する（解釈できなければ None）
+// 開始が終了以降なら日付をまたぐ時間帯を表す
+pub fn parse_working_hours(window: &str) -> Option<(u32, u32)> {
+    let parse_time = |time: &str| -> Option<u32> {
+        let (hour, minute) = time.trim().split_once(':')?;
+        let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+        (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+    };
+    let (start, end) = window.split_once('-')?;
+    Some((parse_time(start)?, parse_time(end)?))
+}
+
+// delegator の権限を、直接または最大 max_depth 人の仲介を経て委任されているユーザーの集合を返す
+// max_depth が 0 なら直接の委任先だけ。delegator 自身は（循環していても）含めない
+pub fn delegates_within(delegations: &HashMap<String, HashSet<String>>, delegator: &str, max_depth: usize) -> HashSet<String> {
+    let mut reached: HashSet<String> = HashSet::new();
+    let mut frontier: Vec<&str> = vec![delegator];
+    for _ in 0..=max_depth {
+        frontier = frontier.iter()
+            .filter_map(|d| delegations.get(*d))
+            .flatten()
+            .filter(|d| d.as_str() != delegator && reached.insert((*d).clone()))
+            .map(String::as_str)
+            .collect();
+        if frontier.is_empty() {
+            break;
+        }
+    }
+    reached
+}
+
+// user_id の上司を、直属の上司から最大 max_levels 段上までたどった集合を返す（supervisors は EdocumentAbac::supervisors の対応）
+// max_levels が 0 なら空。A が B を、B が A を管理するような循環があっても止まり、本人は含めない
+pub fn supervisors_within(supervisors: &HashMap<String, HashSet<String>>, user_id: &str, max_levels: usize) -> HashSet<String> {
+    match max_levels {
+        0 => HashSet::new(),
+        levels => delegates_within(supervisors, user_id, levels - 1),
+    }
+}
+
+// security_level と clearance_level の段階に付けた名前（Public < Internal < Confidential < Secret < TopSecret）
+// データ上は rank の整数で表す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum SecurityLevel {
+    Public,
+    Internal,
+    Confidential,
+    Secret,
+    TopSecret,
+}
+
+impl SecurityLevel {
+    // security_level / clearance_level に入る整数値（Public が 1）
+    pub const fn rank(&self) -> u8 {
         match self {
-            AttributeName::Role => write!(f, "role"),
-            AttributeName::Position => write!(f, "position"),
-            AttributeName::Tenant => write!(f, "tenant"),
-            AttributeName::Department => write!(f, "department"),
-            AttributeName::Office => write!(f, "office"),
-            AttributeName::Registered => write!(f, "registered"),
-            AttributeName::Projects => write!(f, "projects"),
-            AttributeName::Supervisor => write!(f, "supervisor"),
-            AttributeName::Supervisee => write!(f, "supervisee"),
-            AttributeName::PayrollingPermissions => write!(f, "payrollingPermissions"),
-            AttributeName::ClearanceLevel => write!(f, "clearanceLevel"),
-            AttributeName::SecurityLevel => write!(f, "securityLevel"),
-            AttributeName::Type => write!(f, "type"),
-            AttributeName::Owner => write!(f, "owner"),
-            AttributeName::Recipients => write!(f, "recipients"),
-            AttributeName::IsConfidential => write!(f, "isConfidential"),
-            AttributeName::ContainsPersonalInfo => write!(f, "containsPersonalInfo"),
-            AttributeName::Uid => write!(f, "uid"),
-            AttributeName::Rid => write!(f, "rid"),
+            SecurityLevel::Public => 1,
+            SecurityLevel::Internal => 2,
+            SecurityLevel::Confidential => 3,
+            SecurityLevel::Secret => 4,
+            SecurityLevel::TopSecret => 5,
+        }
+    }
+}
+
+// 承認ワークフローにおけるドキュメントの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum ApprovalStatus {
+    Draft,
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    // まだ承認・却下されておらず、approve できる状態かどうか
+    pub fn awaits_approval(&self) -> bool {
+        matches!(self, ApprovalStatus::Draft | ApprovalStatus::Pending)
+    }
+}
+
+// 雇用形態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum ContractType {
+    Permanent,
+    Contractor,
+    Temporary,
+}
+
+impl ContractType {
+    // 社外・期間限定の要員で、機密文書へのアクセスを制限できる雇用形態かどうか
+    pub fn is_external(&self) -> bool {
+        matches!(self, ContractType::Contractor | ContractType::Temporary)
+    }
+}
+
+// 公開文書とみなすセキュリティレベル（最も低いレベル）
+pub const PUBLIC_SECURITY_LEVEL: i32 = SecurityLevel::Public.rank() as i32;
+
+// 版の (major, minor, patch)
+pub type Version = (u64, u64, u64);
+
+// "1.2.3" 形式の版を (major, minor, patch) に変換する
+// 先頭の "v" と "-rc1" などのプレリリース部分は無視し、省略された部分は0とみなす
+// 数値として解釈できない場合は None
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+// 属性の型の分類（条件の演算子との整合性チェックに使う）
+fn is_numeric_attribute(name: &AttributeName) -> bool {
+    matches!(name, AttributeName::ClearanceLevel | AttributeName::BudgetAuthority | AttributeName::SecurityLevel)
+}
+
+// ロールも複数値だが、ルールでは単一値と同じく = で「そのロールを持つ」を表せるので含めない
+fn is_set_attribute(name: &AttributeName) -> bool {
+    matches!(name, AttributeName::Projects | AttributeName::Supervisee | AttributeName::DelegatedAuthority | AttributeName::Certifications | AttributeName::Recipients)
+}
+
+// ルールの各条件で、属性の型と演算子が整合しているかを検証する
+// 数値演算子は数値属性のみ、包含演算子は集合属性のみ、等価は集合以外に使える
+pub fn validate_rule(rule: &EdocumentRule) -> Result<(), Vec<AbacError>> {
+    use crate::types::types::ComparisonOperator::*;
+
+    let mut errors = Vec::new();
+    let conditions = rule.user_conditions.iter()
+        .chain(rule.resource_conditions.iter())
+        .chain(rule.comparison_conditions.iter());
+
+    for cond in conditions {
+        let mut invalid = |reason: String| errors.push(AbacError::InvalidCondition(rule.id, reason));
+        match cond.operator {
+            GreaterThan | LessThan | GreaterThanOrEqual | LessThanOrEqual => {
+                for expr in [&cond.left, &cond.right] {
+                    match expr {
+                        AttributeExpression::AttributeName(name) if !is_numeric_attribute(name) => {
+                            invalid(format!("'{}' is not numeric and cannot be used with {:?}", name, cond.operator));
+                        },
+                        AttributeExpression::AttributeValue(val) if !matches!(val, AttributeValue::Integer(_)) => {
+                            invalid(format!("{:?} is not numeric and cannot be used with {:?}", val, cond.operator));
+                        },
+                        AttributeExpression::ValueSet(_) => {
+                            invalid(format!("a value set cannot be used with {:?}", cond.operator));
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            Contains | ContainedIn => {
+                let set_expr = if cond.operator == Contains { &cond.left } else { &cond.right };
+                match set_expr {
+                    AttributeExpression::AttributeName(name) if !is_set_attribute(name) => {
+                        invalid(format!("'{}' is not a set attribute and cannot be used with {:?}", name, cond.operator));
+                    },
+                    AttributeExpression::AttributeValue(val) => {
+                        invalid(format!("{:?} is not a set and cannot be used with {:?}", val, cond.operator));
+                    },
+                    _ => {},
+                }
+            },
+            Equals => {
+                // 右辺の値の集合は「いずれかに等しい」と解釈する
+                for expr in [&cond.left, &cond.right] {
+                    if let AttributeExpression::AttributeName(name) = expr {
+                        if is_set_attribute(name) {
+                            invalid(format!("set attribute '{}' cannot be compared with Equals", name));
+                        }
+                    }
+                }
+                if let AttributeExpression::ValueSet(_) = cond.left {
+                    invalid("a value set can only appear on the right of Equals".to_string());
+                }
+            },
+        }
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+impl EdocumentAbac {
+    /// 2つのデータセットを統合します
+    /// 同じIDで属性が異なるユーザー・リソースはエラーとし、完全に同一な重複は1つにまとめます。
+    /// `other` のルールIDは `self` のルールIDと衝突しないよう振り直します。
+    pub fn merge(mut self, other: EdocumentAbac) -> Result<EdocumentAbac, AbacError> {
+        for user in other.users {
+            match self.users.iter().find(|u| u.user_id == user.user_id) {
+                Some(existing) if *existing != user => {
+                    return Err(AbacError::ConflictingDefinition("user".to_string(), user.user_id));
+                },
+                Some(_) => {},
+                None => self.users.push(user),
+            }
+        }
+
+        for resource in other.resources {
+            match self.resources.iter().find(|r| r.resource_id == resource.resource_id) {
+                Some(existing) if *existing != resource => {
+                    return Err(AbacError::ConflictingDefinition("resource".to_string(), resource.resource_id));
+                },
+                Some(_) => {},
+                None => self.resources.push(resource),
+            }
+        }
+
+        let next_id = self.rules.iter().map(|r| r.id + 1).max().unwrap_or(0);
+        for (offset, mut rule) in other.rules.into_iter().enumerate() {
+            rule.id = next_id + offset;
+            self.rules.push(rule);
+        }
+
+        Ok(self)
+    }
+
+    // ルールが具体的なユーザー/リソースIDを参照している場合、それらがすべて存在するか検証する
+    // 最初の欠落で止めずに、欠落しているIDをすべてまとめて返す
+    pub fn check_rule_references(&self) -> Result<(), AbacError> {
+        let user_ids: HashSet<&str> = self.users.iter().map(|u| u.user_id.as_str()).collect();
+        let resource_ids: HashSet<&str> = self.resources.iter().map(|r| r.resource_id.as_str()).collect();
+
+        let mut unknown = Vec::new();
+        for rule in &self.rules {
+            let conditions = rule.user_conditions.iter()
+                .chain(rule.resource_conditions.iter())
+                .chain(rule.comparison_conditions.iter());
+            for cond in conditions {
+                for (name_expr, value_expr) in [(&cond.left, &cond.right), (&cond.right, &cond.left)] {
+                    let AttributeExpression::AttributeName(name) = name_expr else { continue };
+                    let known_ids = match name {
+                        AttributeName::Uid | AttributeName::Owner | AttributeName::Supervisor
+                        | AttributeName::Supervisee | AttributeName::DelegatedAuthority
+                        | AttributeName::Recipients => &user_ids,
+                        AttributeName::Rid | AttributeName::Projects => &resource_ids,
+                        _ => continue,
+                    };
+                    let values = match value_expr {
+                        AttributeExpression::AttributeValue(val) => std::slice::from_ref(val),
+                        AttributeExpression::ValueSet(vals) => vals.as_slice(),
+                        AttributeExpression::AttributeName(_) => continue,
+                    };
+                    for val in values {
+                        if let AttributeValue::String(id) = val {
+                            if !known_ids.contains(id.as_str()) && !unknown.contains(id) {
+                                unknown.push(id.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if unknown.is_empty() { Ok(()) } else { Err(AbacError::UnknownReferences(unknown)) }
+    }
+
+    // リソースの owner と recipients がすべて既存のユーザーを指しているか検証する
+    // 欠落はすべてまとめて Err で返す。エラーにするか警告に留めるかは呼び出し側が決める
+    pub fn check_resource_references(&self) -> Result<(), AbacError> {
+        let user_ids: HashSet<&str> = self.users.iter().map(|u| u.user_id.as_str()).collect();
+        let mut dangling = Vec::new();
+        for resource in &self.resources {
+            if let Some(owner) = resource.owner.as_deref().filter(|owner| !user_ids.contains(owner)) {
+                dangling.push(format!("{}: owner '{}'", resource.resource_id, owner));
+            }
+            let mut recipients: Vec<&String> = resource.recipients.iter()
+                .filter(|recipient| !user_ids.contains(recipient.as_str()))
+                .collect();
+            recipients.sort();
+            for recipient in recipients {
+                dangling.push(format!("{}: recipient '{}'", resource.resource_id, recipient));
+            }
+        }
+
+        if dangling.is_empty() { Ok(()) } else { Err(AbacError::DanglingReferences(dangling)) }
+    }
+
+    // リソースIDから、同じ document_family の中での版の順位への対応を返す
+    // 最新版が 0、1つ前の版が -1 のように数え、同じ版は同じ順位になる
+    // family が無いか、版が解釈できないリソースは含めない（古い版とも最新版ともみなさない）
+    pub fn resource_version_rank(&self) -> HashMap<String, i64> {
+        let mut families: HashMap<&str, Vec<(&str, Version)>> = HashMap::new();
+        for r in &self.resources {
+            let Some(family) = r.document_family.as_deref() else { continue };
+            let Some(version) = r.version.as_deref().and_then(parse_version) else { continue };
+            families.entry(family).or_default().push((r.resource_id.as_str(), version));
+        }
+        let mut ranks = HashMap::new();
+        for members in families.values() {
+            let mut versions: Vec<Version> = members.iter().map(|(_, version)| *version).collect();
+            versions.sort_unstable_by(|a, b| b.cmp(a));
+            versions.dedup();
+            for (resource_id, version) in members {
+                let behind = versions.iter().position(|v| v == version).unwrap();
+                ranks.insert(resource_id.to_string(), -(behind as i64));
+            }
+        }
+        ranks
+    }
+
+    // 同じ document_family に、より新しい版が存在するリソースのIDを返す
+    pub fn superseded_resources(&self) -> HashSet<String> {
+        self.resource_version_rank().into_iter()
+            .filter(|(_, rank)| *rank < 0)
+            .map(|(resource_id, _)| resource_id)
+            .collect()
+    }
+
+    // リソースIDから、保存期間が終わる日（1970-01-01 からの日数）への対応を返す
+    // 作成日や保存期間が無い、作成日が解釈できない、または終了日が桁あふれするリソースは含めない
+    pub fn retention_expiry(&self) -> HashMap<String, i64> {
+        self.resources.iter()
+            .filter_map(|r| {
+                let created = parse_date(r.created_date.as_deref()?)?;
+                Some((r.resource_id.clone(), created.checked_add(r.retention_period_days?)?))
+            })
+            .collect()
+    }
+
+    // ユーザーIDから、勤務時間帯 (開始, 終了)（0時からの分数）への対応を返す
+    // 勤務時間帯が無い、または解釈できないユーザーは含めない
+    pub fn working_hours(&self) -> HashMap<String, (u32, u32)> {
+        self.users.iter()
+            .filter_map(|u| Some((u.user_id.clone(), parse_working_hours(u.working_hours.as_deref()?)?)))
+            .collect()
+    }
+
+    // ユーザーIDから、そのユーザーが権限を委任しているユーザーIDの集合への対応を返す
+    // 誰にも委任していないユーザーは含めない
+    pub fn delegations(&self) -> HashMap<String, HashSet<String>> {
+        self.users.iter()
+            .filter(|u| !u.delegated_authority.is_empty())
+            .map(|u| (u.user_id.clone(), u.delegated_authority.clone()))
+            .collect()
+    }
+
+    // ユーザーIDから、その直属の上司のユーザーIDの集合への対応を返す
+    // supervisor と、上司側の supervisee のどちらに書かれていても上司とみなす
+    pub fn supervisors(&self) -> HashMap<String, HashSet<String>> {
+        let mut supervisors: HashMap<String, HashSet<String>> = HashMap::new();
+        for user in &self.users {
+            if let Some(supervisor) = &user.supervisor {
+                supervisors.entry(user.user_id.clone()).or_default().insert(supervisor.clone());
+            }
+            for report in &user.supervisee {
+                supervisors.entry(report.clone()).or_default().insert(user.user_id.clone());
+            }
+        }
+        supervisors
+    }
+
+    // ユーザーとリソースをそれぞれ最大 max_users 件、max_resources 件に減らす（None なら減らさない）
+    // seed が無ければ先頭から、あれば seed で決まる無作為な件数分を元の順序のまま残す
+    // ルールはそのまま残すので、取り除かれたユーザーやリソースを参照するルールは何にも一致しなくなる
+    pub fn sample(&mut self, max_users: Option<usize>, max_resources: Option<usize>, seed: Option<u64>) {
+        use rand::SeedableRng;
+
+        fn keep<T>(items: &mut Vec<T>, max: Option<usize>, rng: Option<&mut rand::rngs::StdRng>) {
+            let Some(max) = max.filter(|max| *max < items.len()) else { return };
+            match rng {
+                Some(rng) => {
+                    let mut kept = rand::seq::index::sample(rng, items.len(), max).into_vec();
+                    kept.sort_unstable();
+                    let mut kept = kept.into_iter().peekable();
+                    let mut index = 0;
+                    items.retain(|_| {
+                        let retained = kept.next_if_eq(&index).is_some();
+                        index += 1;
+                        retained
+                    });
+                }
+                None => items.truncate(max),
+            }
+        }
+
+        let mut rng = seed.map(rand::rngs::StdRng::seed_from_u64);
+        keep(&mut self.users, max_users, rng.as_mut());
+        keep(&mut self.resources, max_resources, rng.as_mut());
+    }
+
+    // ルールを正規化する：条件を一定の順序に並べ替え、条件が同じルールはアクションを統合して1つにまとめる
+    // 許可される (user, resource, action) の集合は変わらない
+    pub fn canonicalize(&mut self) {
+        fn canonicalize_conditions(conditions: &mut Vec<Condition<AttributeExpression>>) {
+            for cond in conditions.iter_mut() {
+                if let AttributeExpression::ValueSet(vals) = &mut cond.right {
+                    vals.sort_by_key(|v| v.to_string());
+                    vals.dedup();
+                }
+            }
+            conditions.sort_by_key(|c| c.to_string());
+            conditions.dedup();
+        }
+
+        let mut canonical: Vec<EdocumentRule> = Vec::new();
+        for mut rule in self.rules.drain(..) {
+            canonicalize_conditions(&mut rule.user_conditions);
+            canonicalize_conditions(&mut rule.resource_conditions);
+            canonicalize_conditions(&mut rule.comparison_conditions);
+
+            let same_conditions = canonical.iter_mut().find(|r| {
+                r.effect == rule.effect
+                    && r.cross_tenant == rule.cross_tenant
+                    && r.user_conditions == rule.user_conditions
+                    && r.resource_conditions == rule.resource_conditions
+                    && r.comparison_conditions == rule.comparison_conditions
+            });
+            match same_conditions {
+                Some(existing) => existing.actions.extend(rule.actions),
+                None => canonical.push(rule),
+            }
         }
+        self.rules = canonical;
     }
 }
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use serde::{Serialize, Deserialize};
+use strum::IntoEnumIterator;
+
+use crate::types::types::{AttributeExpression, AttributeValueExtractor, Condition, UserAttribute, ResourceAttribute};
+use crate::types::edocument_types::{self, EdocumentAbac, EdocumentRule};
+use crate::types::university_types::{self, UniversityAbac, UniversityAbacData, UniversityRule};
+
+// ドメインに依存しない属性値
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GenericValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Set(Vec<String>),
+}
+
+pub type GenericExpression = AttributeExpression<String, GenericValue>;
+pub type GenericCondition = Condition<GenericExpression>;
+
+// ユーザー・リソース共通のエンティティ（IDと属性名→値のマップ）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenericEntity {
+    pub id: String,
+    pub attributes: BTreeMap<String, GenericValue>,
+}
+
+// ドメインに依存しないルール
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenericRule {
+    pub id: usize,
+    pub description: String,
+    pub user_conditions: Vec<GenericCondition>,
+    pub resource_conditions: Vec<GenericCondition>,
+    pub comparison_conditions: Vec<GenericCondition>,
+    pub actions: Vec<String>,
+}
+
+// 各ドメインのデータを変換して得る共通のABAC表現
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenericAbacData {
+    pub subjects: Vec<GenericEntity>,
+    pub resources: Vec<GenericEntity>,
+    pub rules: Vec<GenericRule>,
+}
+
+impl From<&edocument_types::AttributeValue> for GenericValue {
+    fn from(value: &edocument_types::AttributeValue) -> Self {
+        use edocument_types::AttributeValue::*;
+        match value {
+            Boolean(b) => GenericValue::Bool(*b),
+            Integer(i) => GenericValue::Int(*i),
+            StringSet(vals) => GenericValue::Set(vals.clone()),
+            other => GenericValue::String(other.to_string()),
+        }
+    }
+}
+
+impl From<&university_types::AttributeValue> for GenericValue {
+    fn from(value: &university_types::AttributeValue) -> Self {
+        use university_types::AttributeValue::*;
+        match value {
+            Boolean(b) => GenericValue::Bool(*b),
+            Set(vals) => GenericValue::Set(vals.clone()),
+            other => GenericValue::String(other.to_string()),
+        }
+    }
+}
+
+// 属性名を列挙し、値を持つものだけを属性マップに入れる
+fn to_generic_entity<E, N, V>(id: String, entity: &E) -> GenericEntity
+where
+    E: AttributeValueExtractor<AttributeName = N, AttributeValue = V>,
+    N: IntoEnumIterator + Display,
+    V: Display,
+    for<'a> GenericValue: From<&'a V>,
+{
+    let mut attributes = BTreeMap::new();
+    for attr_name in N::iter() {
+        if let Some(value) = entity.get_attribute_value(&attr_name) {
+            attributes.insert(attr_name.to_string(), GenericValue::from(&value));
+        } else if let Some(values) = entity.get_attribute_set(&attr_name) {
+            let mut items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            items.sort();
+            attributes.insert(attr_name.to_string(), GenericValue::Set(items));
+        }
+    }
+    GenericEntity { id, attributes }
+}
+
+fn to_generic_expression<N, V>(expr: &AttributeExpression<N, V>) -> GenericExpression
+where
+    N: Display,
+    for<'a> GenericValue: From<&'a V>,
+{
+    match expr {
+        AttributeExpression::AttributeName(name) => AttributeExpression::AttributeName(name.to_string()),
+        AttributeExpression::AttributeValue(value) => AttributeExpression::AttributeValue(GenericValue::from(value)),
+        AttributeExpression::ValueSet(values) => {
+            AttributeExpression::ValueSet(values.iter().map(GenericValue::from).collect())
+        },
+    }
+}
+
+fn to_generic_conditions<N, V>(conditions: &[Condition<AttributeExpression<N, V>>]) -> Vec<GenericCondition>
+where
+    N: Display,
+    for<'a> GenericValue: From<&'a V>,
+{
+    conditions.iter()
+        .map(|cond| Condition {
+            left: to_generic_expression(&cond.left),
+            operator: cond.operator.clone(),
+            right: to_generic_expression(&cond.right),
+        })
+        .collect()
+}
+
+fn sorted_action_names<A: Display>(actions: impl IntoIterator<Item = A>) -> Vec<String> {
+    let mut names: Vec<String> = actions.into_iter().map(|a| a.to_string()).collect();
+    names.sort();
+    names
+}
+
+impl From<&EdocumentRule> for GenericRule {
+    fn from(rule: &EdocumentRule) -> Self {
+        Self {
+            id: rule.id,
+            description: rule.description.clone(),
+            user_conditions: to_generic_conditions(&rule.user_conditions),
+            resource_conditions: to_generic_conditions(&rule.resource_conditions),
+            comparison_conditions: to_generic_conditions(&rule.comparison_conditions),
+            actions: sorted_action_names(&rule.actions),
+        }
+    }
+}
+
+impl From<&UniversityRule> for GenericRule {
+    fn from(rule: &UniversityRule) -> Self {
+        Self {
+            id: rule.id,
+            description: rule.description.clone(),
+            user_conditions: to_generic_conditions(&rule.user_conditions),
+            resource_conditions: to_generic_conditions(&rule.resource_conditions),
+            comparison_conditions: to_generic_conditions(&rule.comparison_conditions),
+            actions: sorted_action_names(&rule.actions),
+        }
+    }
+}
+
+impl From<EdocumentAbac> for GenericAbacData {
+    fn from(data: EdocumentAbac) -> Self {
+        Self {
+            subjects: data.users.iter().map(|u| to_generic_entity(u.get_user_id(), u)).collect(),
+            resources: data.resources.iter().map(|r| to_generic_entity(r.get_resource_id(), r)).collect(),
+            rules: data.rules.iter().map(GenericRule::from).collect(),
+        }
+    }
+}
+
+impl From<UniversityAbac> for GenericAbacData {
+    fn from(data: UniversityAbac) -> Self {
+        Self {
+            subjects: data.users.iter().map(|u| to_generic_entity(u.get_user_id(), u)).collect(),
+            resources: data.resources.iter().map(|r| to_generic_entity(r.get_resource_id(), r)).collect(),
+            rules: data.rules.iter().map(GenericRule::from).collect(),
+        }
+    }
+}
+
+impl From<UniversityAbacData> for GenericAbacData {
+    fn from(data: UniversityAbacData) -> Self {
+        UniversityAbac::from(data).into()
+    }
+}
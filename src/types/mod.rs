@@ -1,3 +1,4 @@
 pub mod edocument_types;
+pub mod generic_types;
 pub mod university_types;
 pub mod types;
\ No newline at end of file
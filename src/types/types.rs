@@ -68,11 +68,48 @@ pub struct AbacData<U: UserAttribute, R: ResourceAttribute, Rule> {
 pub enum ParseError {
     InvalidLine(String),
     UnknownValue(String, String), // value_type, value
+    Misspelled(String, String, String), // value_type, value, 最も近い正しい値
     MissingAttribute(String),
     InvalidFormat(String),
     InvalidCondition(String),
     FileError(String),
-    ParseErrorAtLine(usize, String, String), // line_number, line_content, error_message
+    ParseErrorAtLine(usize, usize, String, String), // line_number, column, line_content, error_message
+}
+
+/// The candidate closest to `input` by Levenshtein distance, ignoring ASCII case, when it is
+/// close enough to be a likely typo: within a third of the input's length, and always within one
+/// edit. A candidate differing only in case is at distance 0, so it is suggested as well.
+///
+/// ```
+/// use abac_solver::types::types::suggest_closest;
+///
+/// assert_eq!(suggest_closest("manger", &["employee", "manager", "admin"]), Some("manager"));
+/// assert_eq!(suggest_closest("Admin", &["employee", "manager", "admin"]), Some("admin"));
+/// assert_eq!(suggest_closest("auditor", &["employee", "manager", "admin"]), None);
+/// ```
+pub fn suggest_closest<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (input.chars().count() / 3).max(1);
+    // One row of the edit-distance table, reused for every candidate
+    let mut row: Vec<usize> = Vec::new();
+    let mut best: Option<(usize, &'a str)> = None;
+    for &candidate in candidates {
+        row.clear();
+        row.extend(0..=candidate.chars().count());
+        for (i, a) in input.chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, b) in candidate.chars().enumerate() {
+                let substitution = diagonal + usize::from(!a.eq_ignore_ascii_case(&b));
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        let distance = row[row.len() - 1];
+        if distance <= max_distance && best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
 }
 
 impl std::fmt::Display for ParseError {
@@ -80,12 +117,15 @@ impl std::fmt::Display for ParseError {
         match self {
             ParseError::InvalidLine(line) => write!(f, "Invalid line: {}", line),
             ParseError::UnknownValue(value_type, value) => write!(f, "Unknown {}: {}", value_type, value),
+            ParseError::Misspelled(value_type, value, suggestion) => {
+                write!(f, "Unknown {}: '{}', did you mean '{}'?", value_type, value, suggestion)
+            },
             ParseError::MissingAttribute(attr) => write!(f, "Missing attribute: {}", attr),
             ParseError::InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
             ParseError::InvalidCondition(cond) => write!(f, "Invalid condition: {}", cond),
             ParseError::FileError(msg) => write!(f, "File error: {}", msg),
-            ParseError::ParseErrorAtLine(line_num, line_content, error_msg) => {
-                write!(f, "Parse error at line {}: {}\nLine content: '{}'", line_num, error_msg, line_content)
+            ParseError::ParseErrorAtLine(line_num, column, line_content, error_msg) => {
+                write!(f, "Parse error at line {}, column {}: {}\nLine content: '{}'", line_num, column, error_msg, line_content)
             },
         }
     }
@@ -96,6 +136,50 @@ impl std::error::Error for ParseError {}
 unsafe impl Send for ParseError {}
 unsafe impl Sync for ParseError {}
 
+// ABACデータ全体に関するエラー（一般的）
+#[derive(Debug, Clone)]
+pub enum AbacError {
+    Parse(ParseError),
+    ConflictingDefinition(String, String), // entity_kind, id
+    InvalidCondition(usize, String),       // rule_id, reason
+    UnknownReferences(Vec<String>),        // ルールが参照しているが存在しないID
+    DanglingReferences(Vec<String>),       // 存在しないユーザーを owner や recipients に持つリソース
+    Solver(String),                        // 求解処理そのものの失敗
+    TooLarge { count: usize, limit: usize }, // ユーザー数またはリソース数が上限を超えている
+}
+
+impl std::fmt::Display for AbacError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbacError::Parse(e) => write!(f, "{}", e),
+            AbacError::ConflictingDefinition(kind, id) => {
+                write!(f, "Conflicting definitions for {} '{}'", kind, id)
+            },
+            AbacError::InvalidCondition(rule_id, reason) => {
+                write!(f, "Invalid condition in rule {}: {}", rule_id, reason)
+            },
+            AbacError::UnknownReferences(ids) => {
+                write!(f, "Rules reference unknown ids: {}", ids.join(", "))
+            },
+            AbacError::DanglingReferences(refs) => {
+                write!(f, "Resources reference unknown users: {}", refs.join(", "))
+            },
+            AbacError::Solver(msg) => write!(f, "Solver failed: {}", msg),
+            AbacError::TooLarge { count, limit } => {
+                write!(f, "Dataset has {} users or resources, over the limit of {}; split the input or raise --max-entities", count, limit)
+            },
+        }
+    }
+}
+
+impl std::error::Error for AbacError {}
+
+impl From<ParseError> for AbacError {
+    fn from(e: ParseError) -> Self {
+        AbacError::Parse(e)
+    }
+}
+
 // ドメイン固有のパース処理を抽象化するトレイト
 pub trait DomainParser {
     type UserAttribute: UserAttribute;
@@ -145,85 +229,151 @@ pub trait DomainParser {
         actions: std::collections::HashSet<Self::Action>,
         comparison_conditions: Vec<Condition<Self::AttributeExpression>>,
     ) -> Self::Rule;
+
+    // ルールの効果（effect=deny など）を設定する。効果を持たないドメインではエラーにする
+    fn parse_rule_effect(&self, _rule: &mut Self::Rule, value: &str) -> Result<(), ParseError> {
+        Err(ParseError::UnknownValue("effect".to_string(), value.to_string()))
+    }
 }
 
 // 汎用的なABACパーサー
+// パーサーが返すドメインのデータ
+pub type ParsedAbac<D> = AbacData<<D as DomainParser>::UserAttribute, <D as DomainParser>::ResourceAttribute, <D as DomainParser>::Rule>;
+
 pub struct GenericAbacParser<D: DomainParser> {
     domain_parser: D,
+    comment_marker: String,
 }
 
 impl<D: DomainParser> GenericAbacParser<D> {
     pub fn new(domain_parser: D) -> Self {
-        Self { domain_parser }
+        Self { domain_parser, comment_marker: "#".to_string() }
+    }
+
+    /// コメント記号を変更します（デフォルトは "#"）
+    pub fn with_comment_marker(mut self, marker: &str) -> Self {
+        self.comment_marker = marker.to_string();
+        self
     }
 
     /// ファイルパスからファイルを読み取ってパースします
-    pub fn parse_file(&self, file_path: &str) -> Result<AbacData<D::UserAttribute, D::ResourceAttribute, D::Rule>, ParseError> {
-        let content = std::fs::read_to_string(file_path)
+    /// ファイル全体をメモリに読み込まず、1行ずつストリーミングで処理するため、大きなファイルでもピークメモリを抑えられます
+    pub fn parse_file(&self, file_path: &str) -> Result<ParsedAbac<D>, ParseError> {
+        let file = std::fs::File::open(file_path)
             .map_err(|e| ParseError::FileError(format!("Failed to read file '{}': {}", file_path, e)))?;
-        self.parse(&content)
+        self.parse_reader(std::io::BufReader::new(file))
     }
 
     /// 文字列コンテンツをパースします
-    pub fn parse(&self, content: &str) -> Result<AbacData<D::UserAttribute, D::ResourceAttribute, D::Rule>, ParseError> {
+    ///
+    /// エラーには1始まりの行番号と、問題のあるフィールドの列番号が入ります
+    ///
+    /// ```
+    /// use abac_solver::university::{UniversityAbacParser, UniversityDomainParser};
+    ///
+    /// let content = "userAttrib(stu1, position=student)\n\nuserAttrib(stu2, position=studnet)\n";
+    /// let err = UniversityAbacParser::new(UniversityDomainParser).parse(content).unwrap_err();
+    /// assert!(err.to_string().starts_with(
+    ///     "Parse error at line 3, column 18: Error parsing user attribute field 'position': Unknown position: studnet"
+    /// ), "{}", err);
+    /// ```
+    pub fn parse(&self, content: &str) -> Result<ParsedAbac<D>, ParseError> {
+        self.parse_reader(std::io::Cursor::new(content))
+    }
+
+    /// リーダーから1行ずつ読み取ってパースします（大きな.abacファイル向け）
+    pub fn parse_reader(&self, reader: impl std::io::BufRead) -> Result<ParsedAbac<D>, ParseError> {
+        let (data, errors) = self.parse_lines(reader, false);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(data),
+        }
+    }
+
+    /// 最初のエラーで止まらずに全行をパースし、パースできた部分と全エラーを返します（検証用）
+    pub fn parse_collecting(&self, content: &str) -> (ParsedAbac<D>, Vec<ParseError>) {
+        self.parse_lines(std::io::Cursor::new(content), true)
+    }
+
+    /// コメントの開始位置を返します
+    /// 括弧の外にあるコメント記号だけをコメントとみなし、`name=Q#3` のような値の中の記号は残します
+    fn comment_start(&self, line: &str) -> Option<usize> {
+        let mut depth = 0usize;
+        for (pos, c) in line.char_indices() {
+            match c {
+                '(' | '{' => depth += 1,
+                ')' | '}' => depth = depth.saturating_sub(1),
+                _ if depth == 0 && line[pos..].starts_with(self.comment_marker.as_str()) => return Some(pos),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn parse_lines(&self, reader: impl std::io::BufRead, keep_going: bool) -> (ParsedAbac<D>, Vec<ParseError>) {
+        let mut errors = Vec::new();
         let mut users = Vec::new();
         let mut resources = Vec::new();
         let mut rules = Vec::new();
 
-        for (line_num, line) in content.lines().enumerate() {
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    errors.push(ParseError::FileError(format!("Failed to read line {}: {}", line_num + 1, e)));
+                    break;
+                }
+            };
+            // コメント記号以降（行末コメントを含む）を取り除く。行番号は読み飛ばした行も数える
+            let line = match self.comment_start(&line) {
+                Some(pos) => &line[..pos],
+                None => line.as_str(),
+            };
             let line = line.trim();
             
-            // コメントや空行をスキップ
-            if line.is_empty() || line.starts_with('#') {
+            // 空行をスキップ
+            if line.is_empty() {
                 continue;
             }
 
-            if line.starts_with("userAttrib(") {
-                users.push(self.parse_user_attribute(line_num, line)?);
+            let parsed = if line.starts_with("userAttrib(") {
+                self.parse_user_attribute(line_num, line).map(|user| users.push(user))
             } else if line.starts_with("resourceAttrib(") {
-                resources.push(self.parse_resource_attribute(line_num, line)?);
+                self.parse_resource_attribute(line_num, line).map(|resource| resources.push(resource))
             } else if line.starts_with("rule(") {
-                rules.push(self.parse_rule(line_num, line, rules.len())?);
+                self.parse_rule(line_num, line, rules.len()).map(|rule| rules.push(rule))
+            } else {
+                Ok(())
+            };
+            if let Err(e) = parsed {
+                errors.push(e);
+                if !keep_going {
+                    break;
+                }
             }
         }
 
-        Ok(AbacData {
-            users,
-            resources,
-            rules,
-        })
+        (AbacData { users, resources, rules }, errors)
     }
 
     fn parse_user_attribute(&self, line_num: usize, line: &str) -> Result<D::UserAttribute, ParseError> {
-        let content = self.extract_parentheses_content(line)
-            .map_err(|e| ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                format!("Error in user attribute: {}", e)
-            ))?;
-        let parts: Vec<&str> = content.split(',').map(|s| s.trim()).collect();
+        let (content_at, content) = self.extract_parentheses_content(line)
+            .map_err(|e| self.error_at(line_num, line, 0, format!("Error in user attribute: {}", e)))?;
+        let parts = split_fields(content, ',', content_at);
         
         if parts.is_empty() {
-            return Err(ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                "Empty user attribute".to_string()
-            ));
+            return Err(self.error_at(line_num, line, 0, "Empty user attribute".to_string()));
         }
 
-        let user_id = parts[0].to_string();
+        let user_id = parts[0].1.to_string();
         let mut user_attr = self.domain_parser.create_user_attribute(user_id);
 
-        for part in &parts[1..] {
+        for &(part_at, part) in &parts[1..] {
             if let Some((key, value)) = part.split_once('=') {
                 let key = key.trim();
                 let value = value.trim();
                 self.domain_parser.parse_user_attribute_field(&mut user_attr, key, value)
-                    .map_err(|e| ParseError::ParseErrorAtLine(
-                        line_num + 1,
-                        line.to_string(),
-                        format!("Error parsing user attribute field '{}': {}", key, e)
-                    ))?;
+                    .map_err(|e| self.error_at(line_num, line, part_at, format!("Error parsing user attribute field '{}': {}", key, e)))?;
             }
         }
 
@@ -231,60 +381,41 @@ impl<D: DomainParser> GenericAbacParser<D> {
     }
 
     fn parse_resource_attribute(&self, line_num: usize, line: &str) -> Result<D::ResourceAttribute, ParseError> {
-        let content = self.extract_parentheses_content(line)
-            .map_err(|e| ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                format!("Error in resource attribute: {}", e)
-            ))?;
-        let parts: Vec<&str> = content.split(',').map(|s| s.trim()).collect();
+        let (content_at, content) = self.extract_parentheses_content(line)
+            .map_err(|e| self.error_at(line_num, line, 0, format!("Error in resource attribute: {}", e)))?;
+        let parts = split_fields(content, ',', content_at);
         
         if parts.len() < 2 {
-            return Err(ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                "Resource attribute needs at least type".to_string()
-            ));
+            return Err(self.error_at(line_num, line, 0, "Resource attribute needs at least type".to_string()));
         }
 
-        let resource_id = parts[0].to_string();
+        let resource_id = parts[0].1.to_string();
         let mut resource_type = None;
 
-        // typeを先に見つける
-        for part in &parts[1..] {
+        // typeを先に見つける（列番号は値の位置）
+        for &(part_at, part) in &parts[1..] {
             if let Some((key, value)) = part.split_once('=') {
                 if key.trim() == "type" {
-                    resource_type = Some(value.trim());
+                    let value_at = part_at + key.len() + 1 + (value.len() - value.trim_start().len());
+                    resource_type = Some((value_at, value.trim()));
                     break;
                 }
             }
         }
 
-        let resource_type = resource_type.ok_or_else(|| 
-            ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                "Missing required attribute: type".to_string()
-            ))?;
+        let (resource_type_at, resource_type) = resource_type.ok_or_else(|| 
+            self.error_at(line_num, line, 0, "Missing required attribute: type".to_string()))?;
 
         let mut resource_attr = self.domain_parser.create_resource_attribute(resource_id, resource_type)
-            .map_err(|e| ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                format!("Error creating resource attribute: {}", e)
-            ))?;
+            .map_err(|e| self.error_at(line_num, line, resource_type_at, format!("Error creating resource attribute: {}", e)))?;
 
-        for part in &parts[1..] {
+        for &(part_at, part) in &parts[1..] {
             if let Some((key, value)) = part.split_once('=') {
                 let key = key.trim();
                 let value = value.trim();
                 if key != "type" { // typeは既に処理済み
                     self.domain_parser.parse_resource_attribute_field(&mut resource_attr, key, value)
-                        .map_err(|e| ParseError::ParseErrorAtLine(
-                            line_num + 1,
-                            line.to_string(),
-                            format!("Error parsing resource attribute field '{}': {}", key, e)
-                        ))?;
+                        .map_err(|e| self.error_at(line_num, line, part_at, format!("Error parsing resource attribute field '{}': {}", key, e)))?;
                 }
             }
         }
@@ -293,73 +424,83 @@ impl<D: DomainParser> GenericAbacParser<D> {
     }
 
     fn parse_rule(&self, line_num: usize, line: &str, id: usize) -> Result<D::Rule, ParseError> {
-        let content = self.extract_parentheses_content(line)?;
-        let sections: Vec<&str> = content.split(';').collect();
-        
+        let (content_at, content) = self.extract_parentheses_content(line)
+            .map_err(|e| self.error_at(line_num, line, 0, format!("Error in rule: {}", e)))?;
+        let mut sections = split_fields(content, ';', content_at);
+
+        // 最後のセクションが effect=deny のような効果指定なら取り出す（省略時はドメインの既定）
+        let effect = sections.last()
+            .and_then(|&(section_at, section)| section.split_once('=').map(|(key, value)| (section_at, key, value)))
+            .filter(|(_, key, _)| key.trim() == "effect")
+            .map(|(section_at, key, value)| (section_at + key.len() + 1 + (value.len() - value.trim_start().len()), value.trim()));
+        if effect.is_some() {
+            sections.pop();
+        }
+
         if sections.len() < 3 || sections.len() > 4 {
-            return Err(ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                "Rule must have 3 or 4 sections separated by semicolons".to_string()
-            ));
+            return Err(self.error_at(line_num, line, 0, "Rule must have 3 or 4 sections separated by semicolons".to_string()));
         }
 
         // セクション1: ユーザー条件
-        let user_conditions = if sections[0].trim().is_empty() {
+        let user_conditions = if sections[0].1.is_empty() {
             Vec::new()
         } else {
-            self.parse_conditions_section(sections[0].trim())
-                .map_err(|e| ParseError::ParseErrorAtLine(
-                    line_num + 1,
-                    line.to_string(),
-                    format!("Error parsing user conditions: {}", e)
-                ))?
+            self.parse_conditions_section(sections[0].1)
+                .map_err(|e| self.error_at(line_num, line, sections[0].0, format!("Error parsing user conditions: {}", e)))?
         };
 
         // セクション2: リソース条件
-        let resource_conditions = if sections[1].trim().is_empty() {
+        let resource_conditions = if sections[1].1.is_empty() {
             Vec::new()
         } else {
-            self.parse_conditions_section(sections[1].trim())
-                .map_err(|e| ParseError::ParseErrorAtLine(
-                    line_num + 1,
-                    line.to_string(),
-                    format!("Error parsing resource conditions: {}", e)
-                ))?
+            self.parse_conditions_section(sections[1].1)
+                .map_err(|e| self.error_at(line_num, line, sections[1].0, format!("Error parsing resource conditions: {}", e)))?
         };
 
         // セクション3: アクション
-        let actions = self.parse_actions_section(sections[2].trim())
-            .map_err(|e| ParseError::ParseErrorAtLine(
-                line_num + 1,
-                line.to_string(),
-                format!("Error parsing actions: {}", e)
-            ))?;
+        let actions = self.parse_actions_section(sections[2].1)
+            .map_err(|e| self.error_at(line_num, line, sections[2].0, format!("Error parsing actions: {}", e)))?;
 
         // セクション4: 比較条件（存在する場合）
-        let comparison_conditions = if sections.len() == 4 && !sections[3].trim().is_empty() {
-            self.parse_conditions_section(sections[3].trim())
-                .map_err(|e| ParseError::ParseErrorAtLine(
-                    line_num + 1,
-                    line.to_string(),
-                    format!("Error parsing comparison conditions: {}", e)
-                ))?
+        let comparison_conditions = if sections.len() == 4 && !sections[3].1.is_empty() {
+            self.parse_conditions_section(sections[3].1)
+                .map_err(|e| self.error_at(line_num, line, sections[3].0, format!("Error parsing comparison conditions: {}", e)))?
         } else {
             Vec::new()
         };
 
-        Ok(self.domain_parser.create_rule(id, user_conditions, resource_conditions, actions, comparison_conditions))
+        let mut rule = self.domain_parser.create_rule(id, user_conditions, resource_conditions, actions, comparison_conditions);
+        if let Some((effect_at, effect)) = effect {
+            self.domain_parser.parse_rule_effect(&mut rule, effect)
+                .map_err(|e| self.error_at(line_num, line, effect_at, format!("Error parsing effect: {}", e)))?;
+        }
+        Ok(rule)
     }
 
     fn parse_conditions_section(&self, section: &str) -> Result<Vec<Condition<D::AttributeExpression>>, ParseError> {
         let mut conditions = Vec::new();
         
-        for condition_str in section.split(',') {
-            let condition_str = condition_str.trim();
-            if !condition_str.is_empty() {
-                conditions.push(self.parse_single_condition(condition_str)?);
+        // 値の集合 {a, b} の中のカンマでは区切らない
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in section.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    let condition_str = section[start..i].trim();
+                    if !condition_str.is_empty() {
+                        conditions.push(self.parse_single_condition(condition_str)?);
+                    }
+                    start = i + 1;
+                }
+                _ => {}
             }
         }
+        let condition_str = section[start..].trim();
+        if !condition_str.is_empty() {
+            conditions.push(self.parse_single_condition(condition_str)?);
+        }
 
         Ok(conditions)
     }
@@ -385,10 +526,17 @@ impl<D: DomainParser> GenericAbacParser<D> {
         };
 
         let left_str = condition_str[..pos].trim();
-        let right_str = condition_str[pos + operator_len..].trim();
+        let mut right_str = condition_str[pos + operator_len..].trim();
+
+        // 括弧形式 department[{a, b}] の閉じ括弧を取り除く
+        if operator == "[" && right_str.starts_with('{') && right_str.ends_with("}]") {
+            right_str = &right_str[..right_str.len() - 1];
+        }
+        // 値の集合はカンマ区切りも受け付ける（{a, b} → {a b}）
+        let right_str = if right_str.starts_with('{') { right_str.replace(',', " ") } else { right_str.to_string() };
 
         let left = self.domain_parser.parse_attribute_expression(left_str)?;
-        let right = self.domain_parser.parse_attribute_expression(right_str)?;
+        let right = self.domain_parser.parse_attribute_expression(&right_str)?;
         let op = ComparisonOperator::from_str(operator)
             .map_err(|_| ParseError::UnknownValue("operator".to_string(), operator.to_string()))?;
 
@@ -416,7 +564,14 @@ impl<D: DomainParser> GenericAbacParser<D> {
         Ok(actions)
     }
 
-    fn extract_parentheses_content(&self, line: &str) -> Result<String, ParseError> {
+    /// An error on 0-based line `line_num`, at byte offset `at` in `line` (the line as shown
+    /// in the error, comments and surrounding whitespace removed)
+    fn error_at(&self, line_num: usize, line: &str, at: usize, message: String) -> ParseError {
+        ParseError::ParseErrorAtLine(line_num + 1, at + 1, line.to_string(), message)
+    }
+
+    /// The text between the outermost parentheses, with the byte offset it starts at
+    fn extract_parentheses_content<'a>(&self, line: &'a str) -> Result<(usize, &'a str), ParseError> {
         let start = line.find('(').ok_or_else(|| 
             ParseError::InvalidFormat("Missing opening parenthesis".to_string()))?;
         let end = line.rfind(')').ok_or_else(|| 
@@ -426,7 +581,55 @@ impl<D: DomainParser> GenericAbacParser<D> {
             return Err(ParseError::InvalidFormat("Invalid parentheses".to_string()));
         }
 
-        Ok(line[start + 1..end].to_string())
+        Ok((start + 1, &line[start + 1..end]))
+    }
+}
+
+// `s` を区切り文字で分割し、前後の空白を除いた各フィールドとその行内の開始位置を返す
+// （`offset` は `s` 自体の行内の開始位置）
+fn split_fields(s: &str, separator: char, offset: usize) -> Vec<(usize, &str)> {
+    let mut start = offset;
+    s.split(separator)
+        .map(|field| {
+            let field_at = start + (field.len() - field.trim_start().len());
+            start += field.len() + separator.len_utf8();
+            (field_at, field.trim())
+        })
+        .collect()
+}
+
+// 比較演算子を.abacファイルの記号で表示
+impl std::fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComparisonOperator::Contains => write!(f, "]"),
+            ComparisonOperator::ContainedIn => write!(f, "["),
+            ComparisonOperator::Equals => write!(f, "="),
+            ComparisonOperator::GreaterThan => write!(f, ">"),
+            ComparisonOperator::LessThan => write!(f, "<"),
+            ComparisonOperator::GreaterThanOrEqual => write!(f, ">="),
+            ComparisonOperator::LessThanOrEqual => write!(f, "<="),
+        }
+    }
+}
+
+// 属性表現を.abacファイルの表記で表示（値の集合は {a b}）
+impl<N: std::fmt::Display, V: std::fmt::Display> std::fmt::Display for AttributeExpression<N, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeExpression::AttributeName(name) => write!(f, "{}", name),
+            AttributeExpression::AttributeValue(val) => write!(f, "{}", val),
+            AttributeExpression::ValueSet(vals) => {
+                let vals: Vec<String> = vals.iter().map(|v| v.to_string()).collect();
+                write!(f, "{{{}}}", vals.join(" "))
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Condition<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.left, self.operator, self.right)
     }
 }
 
@@ -1,11 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use serde::{Serialize, Deserialize};
+use once_cell::sync::Lazy;
+use strum::{Display, EnumIter, IntoEnumIterator};
 // 再エクスポートして他のモジュールから使えるようにする
 pub use crate::types::types::{ComparisonOperator, AttributeValueExtractor, UserAttribute, ResourceAttribute, Condition, AbacData, ParseError, DomainParser, GenericAbacParser};
 
 // ユーザーの役職を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Position {
     Applicant,
     Student,
@@ -14,7 +17,8 @@ pub enum Position {
 }
 
 // 部署を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Department {
     Cs,
     Ee,
@@ -23,7 +27,8 @@ pub enum Department {
 }
 
 // コース名を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Course {
     Cs101,
     Cs601,
@@ -34,7 +39,8 @@ pub enum Course {
 }
 
 // リソースタイプを表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum ResourceType {
     Application,
     Gradebook,
@@ -43,7 +49,8 @@ pub enum ResourceType {
 }
 
 // アクション（権限）を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum Action {
     ReadMyScores,
     AddScore,
@@ -68,6 +75,20 @@ pub enum AttributeValue {
     Set(Vec<String>),
 }
 
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Position(position) => write!(f, "{}", position),
+            AttributeValue::Department(department) => write!(f, "{}", department),
+            AttributeValue::Course(course) => write!(f, "{}", course),
+            AttributeValue::ResourceType(resource_type) => write!(f, "{}", resource_type),
+            AttributeValue::Boolean(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            AttributeValue::String(s) => write!(f, "{}", s),
+            AttributeValue::Set(vals) => write!(f, "{{{}}}", vals.join(" ")),
+        }
+    }
+}
+
 impl PartialOrd for AttributeValue {
     fn partial_cmp(&self, _other: &Self) -> Option<std::cmp::Ordering> {
         None // No natural ordering for university attributes
@@ -81,7 +102,8 @@ impl Ord for AttributeValue {
 }
 
 // 属性名を表現
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display)]
+#[strum(serialize_all = "camelCase")]
 pub enum AttributeName {
     Position,
     Department,
@@ -313,18 +335,7 @@ impl DomainParser for UniversityDomainParser {
     }
 
     fn parse_action(&self, s: &str) -> Result<Action, ParseError> {
-        match s {
-            "readMyScores" => Ok(Action::ReadMyScores),
-            "addScore" => Ok(Action::AddScore),
-            "readScore" => Ok(Action::ReadScore),
-            "changeScore" => Ok(Action::ChangeScore),
-            "assignGrade" => Ok(Action::AssignGrade),
-            "read" => Ok(Action::Read),
-            "write" => Ok(Action::Write),
-            "checkStatus" => Ok(Action::CheckStatus),
-            "setStatus" => Ok(Action::SetStatus),
-            _ => Err(ParseError::UnknownValue("action".to_string(), s.to_string())),
-        }
+        lookup(&ACTIONS, "action", s)
     }
 
     fn parse_attribute_expression(&self, expr_str: &str) -> Result<Self::AttributeExpression, ParseError> {
@@ -432,45 +443,19 @@ impl DomainParser for UniversityDomainParser {
 
 impl UniversityDomainParser {
     fn parse_position(&self, value: &str) -> Result<Position, ParseError> {
-        match value {
-            "applicant" => Ok(Position::Applicant),
-            "student" => Ok(Position::Student),
-            "faculty" => Ok(Position::Faculty),
-            "staff" => Ok(Position::Staff),
-            _ => Err(ParseError::UnknownValue("position".to_string(), value.to_string())),
-        }
+        lookup(&POSITIONS, "position", value)
     }
 
     fn parse_department(&self, value: &str) -> Result<Department, ParseError> {
-        match value {
-            "cs" => Ok(Department::Cs),
-            "ee" => Ok(Department::Ee),
-            "registrar" => Ok(Department::Registrar),
-            "admissions" => Ok(Department::Admissions),
-            _ => Err(ParseError::UnknownValue("department".to_string(), value.to_string())),
-        }
+        lookup(&DEPARTMENTS, "department", value)
     }
 
     fn parse_course(&self, value: &str) -> Result<Course, ParseError> {
-        match value {
-            "cs101" => Ok(Course::Cs101),
-            "cs601" => Ok(Course::Cs601),
-            "cs602" => Ok(Course::Cs602),
-            "ee101" => Ok(Course::Ee101),
-            "ee601" => Ok(Course::Ee601),
-            "ee602" => Ok(Course::Ee602),
-            _ => Err(ParseError::UnknownValue("course".to_string(), value.to_string())),
-        }
+        lookup(&COURSES, "course", value)
     }
 
     fn parse_resource_type(&self, value: &str) -> Result<ResourceType, ParseError> {
-        match value {
-            "application" => Ok(ResourceType::Application),
-            "gradebook" => Ok(ResourceType::Gradebook),
-            "roster" => Ok(ResourceType::Roster),
-            "transcript" => Ok(ResourceType::Transcript),
-            _ => Err(ParseError::UnknownValue("resource_type".to_string(), value.to_string())),
-        }
+        lookup(&RESOURCE_TYPES, "resource_type", value)
     }
 
     fn parse_course_set(&self, value: &str) -> Result<HashSet<Course>, ParseError> {
@@ -512,6 +497,25 @@ impl UniversityDomainParser {
     }
 }
 
+// 列挙型の文字列表現から値を引くテーブル
+// 各バリアントの表示名（camelCase）から生成するので、パース結果は Display と常に一致する
+fn lookup_table<E: IntoEnumIterator + std::fmt::Display>() -> HashMap<String, E> {
+    E::iter().map(|v| (v.to_string(), v)).collect()
+}
+
+fn lookup<E: Clone>(table: &HashMap<String, E>, value_type: &str, value: &str) -> Result<E, ParseError> {
+    table.get(value)
+        .cloned()
+        .ok_or_else(|| ParseError::UnknownValue(value_type.to_string(), value.to_string()))
+}
+
+static POSITIONS: Lazy<HashMap<String, Position>> = Lazy::new(lookup_table);
+static DEPARTMENTS: Lazy<HashMap<String, Department>> = Lazy::new(lookup_table);
+static COURSES: Lazy<HashMap<String, Course>> = Lazy::new(lookup_table);
+static RESOURCE_TYPES: Lazy<HashMap<String, ResourceType>> = Lazy::new(lookup_table);
+static ACTIONS: Lazy<HashMap<String, Action>> = Lazy::new(lookup_table);
+static ATTRIBUTE_NAMES: Lazy<HashMap<String, AttributeName>> = Lazy::new(lookup_table);
+
 // 後方互換性のための型エイリアス
 pub type UniversityAbacParser = GenericAbacParser<UniversityDomainParser>;
 
@@ -520,18 +524,6 @@ impl std::str::FromStr for AttributeName {
     type Err = ParseError;
     
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "position" => Ok(AttributeName::Position),
-            "department" => Ok(AttributeName::Department),
-            "type" => Ok(AttributeName::Type),
-            "crsTaken" => Ok(AttributeName::CrsTaken),
-            "crsTaught" => Ok(AttributeName::CrsTaught),
-            "isChair" => Ok(AttributeName::IsChair),
-            "student" => Ok(AttributeName::Student),
-            "departments" => Ok(AttributeName::Departments),
-            "crs" => Ok(AttributeName::Crs),
-            "uid" => Ok(AttributeName::Uid),
-            _ => Err(ParseError::UnknownValue("attribute_name".to_string(), s.to_string())),
-        }
+        lookup(&ATTRIBUTE_NAMES, "attribute_name", s)
     }
 }
@@ -0,0 +1,587 @@
+use z3::ast::{Ast, Bool, Dynamic, Int};
+use z3::{Context, DatatypeBuilder, DatatypeSort, FuncDecl, Model, SatResult, Solver, Sort, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use strum::IntoEnumIterator;
+
+use crate::types::edocument_types::{EdocumentAbac, EdocumentUserAttribute, AttributeName, AttributeValue, AttributeExpression, Action};
+use crate::types::types::AttributeValueExtractor;
+use super::z3_solver::{CappedResults, EdocumentAccessResult, Verbosity, Z3Func, encode_value, get_set_attribute_names};
+
+/// Z3 encoding of an edocument dataset: the User, Resource and Action sorts, the id mappings,
+/// the attribute functions and the base axioms asserted on `solver`.
+///
+/// It knows nothing about rules or query strategies, so one model can serve single checks,
+/// full enumeration and per-user queries alike. Queries add their constraints inside their own
+/// push/pop scope on `solver` and leave the base axioms untouched.
+pub struct ConstraintModel<'ctx> {
+    pub ctx: &'ctx Context,
+    pub solver: Solver<'ctx>,
+    pub value_to_int: HashMap<AttributeValue, i64>,
+    pub attr_funcs: HashMap<AttributeName, (Option<Z3Func<'ctx>>, Option<Z3Func<'ctx>>)>,
+    pub user_mapping: HashMap<String, Dynamic<'ctx>>,
+    pub resource_mapping: HashMap<String, Dynamic<'ctx>>,
+    pub action_mapping: HashMap<Action, Dynamic<'ctx>>,
+    /// Reverse maps from a model value (a sort variant) back to the original id
+    pub user_ids: HashMap<Dynamic<'ctx>, String>,
+    pub resource_ids: HashMap<Dynamic<'ctx>, String>,
+    pub actions: HashMap<Dynamic<'ctx>, Action>,
+    /// Reserved User constructors not yet given to a user, each with the flag that keeps `u_var`
+    /// off that constructor while `check` assumes it false
+    spare_users: Vec<(Dynamic<'ctx>, Bool<'ctx>)>,
+    /// Free query variables shared by every query
+    pub u_var: Dynamic<'ctx>,
+    pub r_var: Dynamic<'ctx>,
+    pub a_var: Dynamic<'ctx>,
+}
+
+/// The sorts that depend only on the schema, not on the dataset. Build it once per `Context`
+/// and pass it to `EdocumentAbacSolver::with_sorts` so repeated solves reuse it.
+///
+/// Attribute values are encoded as integers, so Action is the only such datatype; the User
+/// and Resource sorts list the dataset's ids and are built by each model.
+pub struct SortCache<'ctx> {
+    pub action_sort: Sort<'ctx>,
+    pub action_mapping: HashMap<Action, Dynamic<'ctx>>,
+}
+
+impl<'ctx> SortCache<'ctx> {
+    pub fn new(ctx: &'ctx Context) -> Self {
+        let action_variants: Vec<(String, Action)> = Action::iter()
+            .map(|action| (action.to_string(), action))
+            .collect();
+        let (action_dt, action_mapping) = enumeration_sort(ctx, "Action", &action_variants);
+        Self { action_sort: action_dt.sort, action_mapping }
+    }
+}
+
+impl<'ctx> ConstraintModel<'ctx> {
+    /// Builds the model for `abac_data`, reserving `spare_users` extra constructors in the User
+    /// sort for `add_user`. Datatype sorts cannot grow once declared, so this is the only way
+    /// to add users later without building a new model.
+    pub fn with_spare_users(ctx: &'ctx Context, abac_data: &EdocumentAbac, spare_users: usize) -> Self {
+        Self::with_sorts(ctx, &SortCache::new(ctx), abac_data, spare_users)
+    }
+
+    /// Like `with_spare_users`, but takes the schema sorts from `sorts` instead of declaring them.
+    pub fn with_sorts(ctx: &'ctx Context, sorts: &SortCache<'ctx>, abac_data: &EdocumentAbac, spare_users: usize) -> Self {
+        let solver = Solver::new(ctx);
+
+        // 1. Create a unified map for all attribute values (String to Int mapping)
+        let (value_to_int, _int_to_value) = create_value_mappings(abac_data);
+
+        // 2. Define User and Resource types as Datatypes (Closed World); Action comes from `sorts`
+        let spare_ids: Vec<String> = (0..spare_users).map(|i| format!("__spare_user_{}", i)).collect();
+        let user_variants: Vec<(String, String)> = abac_data.users.iter()
+            .map(|user| user.user_id.clone())
+            .chain(spare_ids.iter().cloned())
+            .map(|id| (id.clone(), id))
+            .collect();
+        let resource_variants: Vec<(String, String)> = abac_data.resources.iter()
+            .map(|resource| (resource.resource_id.clone(), resource.resource_id.clone()))
+            .collect();
+        let (user_dt, mut user_mapping) = enumeration_sort(ctx, "User", &user_variants);
+        let (resource_dt, resource_mapping) = enumeration_sort(ctx, "Resource", &resource_variants);
+        let action_mapping = sorts.action_mapping.clone();
+
+        let int_sort = Sort::int(ctx);
+        let bool_sort = Sort::bool(ctx);
+
+        // 3. Model all unique attributes as Z3 functions
+        let set_attributes = get_set_attribute_names();
+
+        let mut attr_funcs: HashMap<AttributeName, (Option<Z3Func>, Option<Z3Func>)> = HashMap::new();
+
+        for attr_name in AttributeName::iter() {
+            let is_set_attr = set_attributes.contains(&attr_name);
+            let mut user_func = None;
+            let mut resource_func = None;
+
+            if abac_data.users.iter().any(|u| u.get_attribute_value(&attr_name).is_some() || u.get_attribute_set(&attr_name).is_some()) {
+                user_func = Some(if is_set_attr {
+                    Z3Func::Set(FuncDecl::new(ctx, format!("user_{}", attr_name), &[&user_dt.sort, &int_sort], &bool_sort))
+                } else {
+                    Z3Func::Single(
+                        FuncDecl::new(ctx, format!("user_{}", attr_name), &[&user_dt.sort], &int_sort),
+                        FuncDecl::new(ctx, format!("user_defines_{}", attr_name), &[&user_dt.sort], &bool_sort),
+                    )
+                });
+            }
+            if abac_data.resources.iter().any(|r| r.get_attribute_value(&attr_name).is_some() || r.get_attribute_set(&attr_name).is_some()) {
+                resource_func = Some(if is_set_attr {
+                    Z3Func::Set(FuncDecl::new(ctx, format!("resource_has_{}", attr_name), &[&resource_dt.sort, &int_sort], &bool_sort))
+                } else {
+                    Z3Func::Single(
+                        FuncDecl::new(ctx, format!("resource_{}", attr_name), &[&resource_dt.sort], &int_sort),
+                        FuncDecl::new(ctx, format!("resource_defines_{}", attr_name), &[&resource_dt.sort], &bool_sort),
+                    )
+                });
+            }
+            attr_funcs.insert(attr_name, (user_func, resource_func));
+        }
+
+        // 4. Assert all user and resource attributes as axioms
+        for user in &abac_data.users {
+            let user_funcs = attr_funcs.iter().filter_map(|(name, (user_func, _))| Some((name, user_func.as_ref()?)));
+            assert_attribute_axioms(&solver, &user_mapping[&user.user_id], user, user_funcs, &value_to_int);
+        }
+        for resource in &abac_data.resources {
+            let resource_funcs = attr_funcs.iter().filter_map(|(name, (_, resource_func))| Some((name, resource_func.as_ref()?)));
+            assert_attribute_axioms(&solver, &resource_mapping[&resource.resource_id], resource, resource_funcs, &value_to_int);
+        }
+
+        // 5. Free query variables shared by every query
+        let u_var = Dynamic::new_const(ctx, Symbol::String("u".to_string()), &user_dt.sort);
+        let r_var = Dynamic::new_const(ctx, Symbol::String("r".to_string()), &resource_dt.sort);
+        let a_var = Dynamic::new_const(ctx, Symbol::String("a".to_string()), &sorts.action_sort);
+        // Spare constructors have no attribute axioms yet, so `check` keeps every query off them
+        let spare_users = spare_ids.iter()
+            .map(|id| {
+                let u_const = user_mapping.remove(id).unwrap();
+                let active = Bool::new_const(ctx, format!("active_{}", id));
+                solver.assert(&Bool::or(ctx, &[&active, &u_var._eq(&u_const).not()]));
+                (u_const, active)
+            })
+            .collect();
+        let user_ids = user_mapping.iter().map(|(id, c)| (c.clone(), id.clone())).collect();
+        let resource_ids = resource_mapping.iter().map(|(id, c)| (c.clone(), id.clone())).collect();
+        let actions = action_mapping.iter().map(|(action, c)| (c.clone(), action.clone())).collect();
+
+        Self {
+            ctx,
+            solver,
+            value_to_int,
+            attr_funcs,
+            user_mapping,
+            resource_mapping,
+            action_mapping,
+            user_ids,
+            resource_ids,
+            actions,
+            spare_users,
+            u_var,
+            r_var,
+            a_var,
+        }
+    }
+
+    /// Gives `user` the next spare User constructor and asserts its attributes, leaving the
+    /// rest of the model as it is. Values no entity had before get fresh ids, and every
+    /// existing entity is asserted not to hold them in its set attributes.
+    ///
+    /// This asserts one axiom per single attribute of the user and one per (set attribute, known
+    /// value), plus one per (entity, set attribute) for each new value, instead of the axioms
+    /// of every entity that `new` asserts. It fails when no spare constructor is left, or when
+    /// the user has an attribute no user had when the model was built; both need a new model.
+    pub fn add_user(&mut self, user: &EdocumentUserAttribute) -> Result<(), String> {
+        if self.user_mapping.contains_key(&user.user_id) {
+            return Err(format!("User already exists: {}", user.user_id));
+        }
+        let mut values = Vec::new();
+        for attr_name in AttributeName::iter() {
+            let value = user.get_attribute_value(&attr_name);
+            let set = user.get_attribute_set(&attr_name).filter(|set| !set.is_empty());
+            if value.is_none() && set.is_none() {
+                continue;
+            }
+            if !self.attr_funcs.get(&attr_name).is_some_and(|(user_func, _)| user_func.is_some()) {
+                return Err(format!("No user had attribute {} when the model was built", attr_name));
+            }
+            values.extend(value);
+            values.extend(set.into_iter().flatten());
+        }
+        if self.spare_users.is_empty() {
+            return Err("No spare user constructors left".to_string());
+        }
+        // Off the spare list, its flag is no longer assumed false by `check`
+        let (u_const, _) = self.spare_users.remove(0);
+
+        for value in values {
+            if self.value_to_int.contains_key(&value) {
+                continue;
+            }
+            let value_int = Int::from_i64(self.ctx, self.value_to_int.len() as i64);
+            self.value_to_int.insert(value, self.value_to_int.len() as i64);
+            for (user_func, resource_func) in self.attr_funcs.values() {
+                let sides = [(user_func, self.user_mapping.values().collect::<Vec<_>>()), (resource_func, self.resource_mapping.values().collect())];
+                for (func, entities) in sides {
+                    if let Some(Z3Func::Set(func)) = func {
+                        for entity in entities {
+                            self.solver.assert(&func.apply(&[entity, &value_int]).as_bool().unwrap().not());
+                        }
+                    }
+                }
+            }
+        }
+
+        let user_funcs = self.attr_funcs.iter().filter_map(|(name, (user_func, _))| Some((name, user_func.as_ref()?)));
+        assert_attribute_axioms(&self.solver, &u_const, user, user_funcs, &self.value_to_int);
+        self.user_ids.insert(u_const.clone(), user.user_id.clone());
+        self.user_mapping.insert(user.user_id.clone(), u_const);
+        Ok(())
+    }
+
+    /// Checks the current assertion stack with `u_var` kept off the spare constructors that
+    /// `add_user` has not handed out yet.
+    pub fn check(&self) -> SatResult {
+        let inactive: Vec<Bool> = self.spare_users.iter().map(|(_, active)| active.not()).collect();
+        self.solver.check_assumptions(&inactive)
+    }
+}
+
+/// Asserts the value of every attribute in `funcs` for `entity`: the value (and that it has
+/// one) for single attributes, membership of every known value for set attributes.
+fn assert_attribute_axioms<'ctx, 'a, E>(
+    solver: &Solver<'ctx>,
+    entity_const: &Dynamic<'ctx>,
+    entity: &E,
+    funcs: impl Iterator<Item = (&'a AttributeName, &'a Z3Func<'ctx>)>,
+    value_to_int: &HashMap<AttributeValue, i64>,
+) where
+    'ctx: 'a,
+    E: AttributeValueExtractor<AttributeName = AttributeName, AttributeValue = AttributeValue>,
+{
+    let ctx = solver.get_context();
+    let get_int = |val: &AttributeValue| -> i64 { *value_to_int.get(val).unwrap_or(&-1) };
+    for (attr_name, z3_func) in funcs {
+        match z3_func {
+            Z3Func::Single(func, has_func) => {
+                let has_val = has_func.apply(&[entity_const]).as_bool().unwrap();
+                if let Some(val) = entity.get_attribute_value(attr_name) {
+                    solver.assert(&has_val);
+                    solver.assert(&func.apply(&[entity_const]).as_int().unwrap()._eq(&Int::from_i64(ctx, encode_value(&val, &get_int))));
+                } else {
+                    solver.assert(&has_val.not());
+                }
+            },
+            Z3Func::Set(func) => {
+                let entity_values: HashSet<i64> = entity.get_attribute_set(attr_name)
+                    .map(|s| s.iter().map(|v| get_int(v)).collect())
+                    .unwrap_or_default();
+
+                for val_int in value_to_int.values() {
+                    let z3_val = Int::from_i64(ctx, *val_int);
+                    let has_val = func.apply(&[entity_const, &z3_val]).as_bool().unwrap();
+                    if entity_values.contains(val_int) {
+                        solver.assert(&has_val);
+                    } else {
+                        solver.assert(&has_val.not());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Builds the closed datatype `name` with one nullary constructor per `(constructor name, key)`
+/// entry and maps each key to its constructor, both from the same list, so the sort and the
+/// mapping cannot get out of step when entries are reordered or added.
+pub(super) fn enumeration_sort<'ctx, K: Eq + Hash + Clone>(
+    ctx: &'ctx Context,
+    name: &str,
+    variants: &[(String, K)],
+) -> (DatatypeSort<'ctx>, HashMap<K, Dynamic<'ctx>>) {
+    let mut builder = DatatypeBuilder::new(ctx, Symbol::String(name.to_string()));
+    for (constructor_name, _) in variants {
+        builder = builder.variant(constructor_name, vec![]);
+    }
+    let datatype = builder.finish();
+
+    let mapping: HashMap<K, Dynamic> = variants.iter()
+        .zip(&datatype.variants)
+        .map(|((constructor_name, key), variant)| {
+            debug_assert_eq!(variant.constructor.name(), *constructor_name, "{} constructor out of step with its variant list", name);
+            (key.clone(), variant.constructor.apply(&[]))
+        })
+        .collect();
+    assert_eq!(datatype.variants.len(), mapping.len(),
+               "{} sort has {} variants but {} keys are mapped (duplicate ids?)", name, datatype.variants.len(), mapping.len());
+    (datatype, mapping)
+}
+
+/// What `Checker` and `Enumerator` need from an encoding: its solver, the shared (u, r, a) query
+/// variables, the Resource constructors by id and the way back from a satisfying assignment to a
+/// result. `ConstraintModel` is the edocument encoding; `UniversityAbacSolver` implements it too.
+pub trait EnumerationSpace<'ctx> {
+    type Result: Ord + std::fmt::Display;
+
+    fn ctx(&self) -> &'ctx Context;
+    fn solver(&self) -> &Solver<'ctx>;
+    /// `u_var`, `r_var` and `a_var`, in that order
+    fn query_vars(&self) -> [&Dynamic<'ctx>; 3];
+    fn resource_mapping(&self) -> &HashMap<String, Dynamic<'ctx>>;
+    /// Checks the current assertion stack.
+    fn check(&self) -> SatResult;
+    /// The result for the user, resource and action constructors a model assigned.
+    fn result(&self, found_u: &Dynamic<'ctx>, found_r: &Dynamic<'ctx>, found_a: &Dynamic<'ctx>) -> Self::Result;
+}
+
+impl<'ctx> EnumerationSpace<'ctx> for ConstraintModel<'ctx> {
+    type Result = EdocumentAccessResult;
+
+    fn ctx(&self) -> &'ctx Context {
+        self.ctx
+    }
+
+    fn solver(&self) -> &Solver<'ctx> {
+        &self.solver
+    }
+
+    fn query_vars(&self) -> [&Dynamic<'ctx>; 3] {
+        [&self.u_var, &self.r_var, &self.a_var]
+    }
+
+    fn resource_mapping(&self) -> &HashMap<String, Dynamic<'ctx>> {
+        &self.resource_mapping
+    }
+
+    fn check(&self) -> SatResult {
+        ConstraintModel::check(self)
+    }
+
+    fn result(&self, found_u: &Dynamic<'ctx>, found_r: &Dynamic<'ctx>, found_a: &Dynamic<'ctx>) -> EdocumentAccessResult {
+        EdocumentAccessResult {
+            user_id: self.user_ids[found_u].clone(),
+            resource_id: self.resource_ids[found_r].clone(),
+            action: self.actions[found_a].clone(),
+        }
+    }
+}
+
+/// Runs satisfiability checks against a model and keeps the model of the last satisfiable one.
+#[derive(Default)]
+pub struct Checker<'ctx> {
+    last_model: Option<Model<'ctx>>,
+}
+
+impl<'ctx> Checker<'ctx> {
+    /// Checks the model's current assertion stack.
+    pub fn check(&mut self, model: &impl EnumerationSpace<'ctx>) -> SatResult {
+        let result = model.check();
+        if result == SatResult::Sat {
+            self.last_model = model.solver().get_model();
+        }
+        result
+    }
+
+    /// The model from the most recent satisfiable `check`.
+    pub fn last_model(&self) -> Option<Model<'ctx>> {
+        self.last_model.clone()
+    }
+}
+
+/// Enumerates the (u, r, a) triples satisfying a model's current assertion stack, blocking
+/// each found triple in turn. Callers are expected to wrap this in their own push/pop scope.
+pub struct Enumerator {
+    max: Option<u64>,
+    verbosity: Verbosity,
+    in_id_order: bool,
+}
+
+impl Enumerator {
+    pub fn new(max: Option<u64>, verbosity: Verbosity) -> Self {
+        Self { max, verbosity, in_id_order: false }
+    }
+
+    /// Always goes through `for_each_in_id_order`, even without a cap.
+    pub fn in_id_order(mut self) -> Self {
+        self.in_id_order = true;
+        self
+    }
+
+    pub fn enumerate<'ctx, M: EnumerationSpace<'ctx>>(&self, model: &M, checker: &mut Checker<'ctx>) -> Vec<M::Result> {
+        let mut results = Vec::new();
+        self.for_each(model, checker, |result| {
+            results.push(result);
+            true
+        });
+        results
+    }
+
+    /// Enumerates like `enumerate`, then checks once more when the cap was reached, since
+    /// every found triple is blocked by then, to tell a truncated run from an exact fit.
+    pub fn enumerate_capped<'ctx>(&self, model: &ConstraintModel<'ctx>, checker: &mut Checker<'ctx>) -> CappedResults {
+        let results = self.enumerate(model, checker);
+        let truncated = self.max.is_some_and(|max| results.len() as u64 >= max)
+            && checker.check(model) == SatResult::Sat;
+        CappedResults { results, truncated }
+    }
+
+    /// Hands each triple to `on_result` as soon as it is found, stopping early once it returns
+    /// false. Returns how many triples were accepted.
+    ///
+    /// Blocking every found triple on one assertion stack makes each check slower than the
+    /// last. Instead, each resource that has a permitted triple is enumerated in its own scope,
+    /// with only its (user, action) pairs blocked, and then excluded from the outer scope with
+    /// a single clause. The clauses any one check carries stay bounded by the users times the
+    /// actions plus the resources, however many triples there are.
+    ///
+    /// With a cap, which triples fit under it would depend on the order models come in, so
+    /// capped runs go through `for_each_in_id_order` instead, as do enumerators built with
+    /// `in_id_order`.
+    pub fn for_each<'ctx, M: EnumerationSpace<'ctx>>(
+        &self,
+        model: &M,
+        checker: &mut Checker<'ctx>,
+        mut on_result: impl FnMut(M::Result) -> bool,
+    ) -> u64 {
+        if self.max.is_some() || self.in_id_order {
+            return self.for_each_in_id_order(model, checker, on_result);
+        }
+        let [u_var, r_var, a_var] = model.query_vars();
+        let solver = model.solver();
+        let mut found = 0;
+        let mut stopped = false;
+        while !stopped && self.below_max(found) && checker.check(model) == SatResult::Sat {
+            let found_r = checker.last_model().unwrap().eval(r_var, true).unwrap();
+
+            solver.push();
+            solver.assert(&r_var._eq(&found_r));
+            let mut blocked = Vec::new();
+            let mut exhausted = false;
+            while self.below_max(found) {
+                if checker.check(model) != SatResult::Sat {
+                    exhausted = true;
+                    break;
+                }
+                let satisfying = checker.last_model().unwrap();
+                let found_u = satisfying.eval(u_var, true).unwrap();
+                let found_a = satisfying.eval(a_var, true).unwrap();
+
+                let result = model.result(&found_u, &found_r, &found_a);
+                if self.verbosity >= Verbosity::Verbose {
+                    println!("  - {}", result);
+                }
+                if !on_result(result) {
+                    stopped = true;
+                    break;
+                }
+                found += 1;
+
+                let exclusion_constraint = Bool::and(model.ctx(), &[&u_var._eq(&found_u), &r_var._eq(&found_r), &a_var._eq(&found_a)]).not();
+                solver.assert(&exclusion_constraint);
+                blocked.push(exclusion_constraint);
+            }
+            solver.pop(1);
+
+            if exhausted {
+                solver.assert(&r_var._eq(&found_r).not());
+            } else {
+                // Stopped part way through this resource: keep its found triples blocked so a
+                // later check on the caller's scope does not report them again
+                for exclusion_constraint in &blocked {
+                    solver.assert(exclusion_constraint);
+                }
+            }
+        }
+        found
+    }
+
+    /// Visits resources in id order and hands over each resource's triples sorted by user and
+    /// action, so a cap keeps the same triples however the solver picks its models. This costs
+    /// one extra check per resource without triples, and finds all of the last resource's
+    /// triples even when the cap only takes some of them.
+    fn for_each_in_id_order<'ctx, M: EnumerationSpace<'ctx>>(
+        &self,
+        model: &M,
+        checker: &mut Checker<'ctx>,
+        mut on_result: impl FnMut(M::Result) -> bool,
+    ) -> u64 {
+        let [u_var, r_var, a_var] = model.query_vars();
+        let solver = model.solver();
+        let mut resources: Vec<(&String, &Dynamic<'ctx>)> = model.resource_mapping().iter().collect();
+        resources.sort_by_key(|(resource_id, _)| *resource_id);
+
+        let mut found = 0;
+        for (_, r_const) in resources {
+            if !self.below_max(found) {
+                break;
+            }
+            solver.push();
+            solver.assert(&r_var._eq(r_const));
+            let mut triples = Vec::new();
+            while checker.check(model) == SatResult::Sat {
+                let satisfying = checker.last_model().unwrap();
+                let found_u = satisfying.eval(u_var, true).unwrap();
+                let found_a = satisfying.eval(a_var, true).unwrap();
+                solver.assert(&Bool::and(model.ctx(), &[&u_var._eq(&found_u), &a_var._eq(&found_a)]).not());
+                let result = model.result(&found_u, r_const, &found_a);
+                triples.push((result, found_u, found_a));
+            }
+            solver.pop(1);
+            triples.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+            let total = triples.len();
+            let mut blocked = Vec::new();
+            let mut stopped = false;
+            for (result, found_u, found_a) in triples {
+                if !self.below_max(found) {
+                    break;
+                }
+                if self.verbosity >= Verbosity::Verbose {
+                    println!("  - {}", result);
+                }
+                if !on_result(result) {
+                    stopped = true;
+                    break;
+                }
+                found += 1;
+                blocked.push(Bool::and(model.ctx(), &[&u_var._eq(&found_u), &r_var._eq(r_const), &a_var._eq(&found_a)]).not());
+            }
+
+            if blocked.len() == total {
+                solver.assert(&r_var._eq(r_const).not());
+            } else {
+                // Same as in `for_each`: keep what was handed over blocked for the caller's scope
+                for exclusion_constraint in &blocked {
+                    solver.assert(exclusion_constraint);
+                }
+            }
+            if stopped {
+                break;
+            }
+        }
+        found
+    }
+
+    fn below_max(&self, found: u64) -> bool {
+        self.max.is_none_or(|max| found < max)
+    }
+}
+
+fn create_value_mappings(data: &EdocumentAbac) -> (HashMap<AttributeValue, i64>, HashMap<i64, AttributeValue>) {
+    let mut value_to_int = HashMap::new();
+    let mut int_to_value = HashMap::new();
+    let mut counter = 0;
+
+    let mut insert_val = |val: &AttributeValue| {
+        if !value_to_int.contains_key(val) {
+            value_to_int.insert(val.clone(), counter);
+            int_to_value.insert(counter, val.clone());
+            counter += 1;
+        }
+    };
+
+    // Always mapped so that registered custom predicates can be encoded as booleans
+    insert_val(&AttributeValue::Boolean(true));
+    insert_val(&AttributeValue::Boolean(false));
+    for user in &data.users {
+        for attr_name in AttributeName::iter() {
+            if let Some(val) = user.get_attribute_value(&attr_name) { insert_val(&val); }
+            if let Some(set) = user.get_attribute_set(&attr_name) { set.iter().for_each(|v| insert_val(v)); }
+        }
+    }
+    for resource in &data.resources {
+        for attr_name in AttributeName::iter() {
+            if let Some(val) = resource.get_attribute_value(&attr_name) { insert_val(&val); }
+            if let Some(set) = resource.get_attribute_set(&attr_name) { set.iter().for_each(|v| insert_val(v)); }
+        }
+    }
+    for rule in &data.rules {
+        for cond in rule.user_conditions.iter().chain(rule.resource_conditions.iter()).chain(rule.comparison_conditions.iter()) {
+            if let AttributeExpression::AttributeValue(val) = &cond.right { insert_val(val); }
+            if let AttributeExpression::ValueSet(vals) = &cond.right { vals.iter().for_each(|v| insert_val(v)); }
+        }
+    }
+    (value_to_int, int_to_value)
+}
@@ -1,3 +1,6 @@
 mod z3_solver;
+mod constraint_model;
+mod university_solver;
 
-pub use z3_solver::*;
\ No newline at end of file
+pub use z3_solver::*;
+pub use university_solver::{UniversityAbacSolver, UniversityAccessResult};
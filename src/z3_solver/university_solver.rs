@@ -0,0 +1,377 @@
+use z3::ast::{Ast, Bool, Dynamic, Int};
+use z3::{Context, FuncDecl, SatResult, Solver, Sort, Symbol};
+use std::collections::HashMap;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::types::types::{AttributeValueExtractor, ComparisonOperator, Condition};
+use crate::types::university_types::{UniversityAbac, UniversityRule, AttributeName, AttributeValue, AttributeExpression, Action};
+use super::constraint_model::{enumeration_sort, Checker, EnumerationSpace, Enumerator};
+use super::z3_solver::{Verbosity, Z3Func};
+
+/// A (user, resource, action) triple permitted by a university policy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct UniversityAccessResult {
+    pub user_id: String,
+    pub resource_id: String,
+    pub action: Action,
+}
+
+impl std::fmt::Display for UniversityAccessResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} can {} {}", self.user_id, self.action, self.resource_id)
+    }
+}
+
+/// By user, then resource, then action name, like `EdocumentAccessResult`
+impl Ord for UniversityAccessResult {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.user_id, &self.resource_id, self.action.to_string())
+            .cmp(&(&other.user_id, &other.resource_id, other.action.to_string()))
+    }
+}
+
+impl PartialOrd for UniversityAccessResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A university policy encoded for Z3, with the same closed-world layout as the edocument
+/// solver: User, Resource and Action datatypes, `user_<attr>`/`resource_<attr>` value functions
+/// with their `*_defines_<attr>` predicates, and `user_has_<attr>`/`resource_has_<attr>`
+/// membership predicates for `crsTaken`, `crsTaught` and `departments`.
+///
+/// Each rule becomes `conditions ∧ a ∈ actions`, so `rule(; type [ {gradebook}; {readMyScores}; crsTaken ] crs)`
+/// is `resource_type(r) = gradebook ∧ user_has_crsTaken(u, resource_crs(r)) ∧ a = readMyScores`.
+/// `uid` and `student` share the value mapping, so `uid=student` binds the requesting user to the
+/// student a transcript or application belongs to.
+///
+/// University attribute values have no order, so rules comparing them with `>`, `<`, `>=` or `<=`
+/// are rejected by `new` rather than left to match nothing.
+pub struct UniversityAbacSolver<'ctx> {
+    ctx: &'ctx Context,
+    solver: Solver<'ctx>,
+    rules: Vec<UniversityRule>,
+    value_to_int: HashMap<AttributeValue, i64>,
+    attr_funcs: HashMap<AttributeName, (Option<Z3Func<'ctx>>, Option<Z3Func<'ctx>>)>,
+    user_mapping: HashMap<String, Dynamic<'ctx>>,
+    resource_mapping: HashMap<String, Dynamic<'ctx>>,
+    action_mapping: HashMap<Action, Dynamic<'ctx>>,
+    /// Reverse maps from a model value (a sort variant) back to the original id
+    user_ids: HashMap<Dynamic<'ctx>, String>,
+    resource_ids: HashMap<Dynamic<'ctx>, String>,
+    actions: HashMap<Dynamic<'ctx>, Action>,
+    u_var: Dynamic<'ctx>,
+    r_var: Dynamic<'ctx>,
+    a_var: Dynamic<'ctx>,
+}
+
+// One side of a condition once translated: a single value, a set attribute or a literal set
+enum Term<'a> {
+    Value(Int<'a>, Option<Bool<'a>>),
+    Set(FuncDecl<'a>, Dynamic<'a>),
+    Values(Vec<Int<'a>>),
+}
+
+impl<'ctx> UniversityAbacSolver<'ctx> {
+    pub fn new(ctx: &'ctx Context, abac_data: &UniversityAbac) -> Result<Self, String> {
+        use ComparisonOperator::*;
+        for rule in &abac_data.rules {
+            let mut conditions = rule.user_conditions.iter().chain(&rule.resource_conditions).chain(&rule.comparison_conditions);
+            if let Some(cond) = conditions.find(|cond| matches!(cond.operator, GreaterThan | LessThan | GreaterThanOrEqual | LessThanOrEqual)) {
+                return Err(format!("rule {}: `{}` compares by order, but university attribute values have none", rule.id, cond));
+            }
+        }
+
+        let solver = Solver::new(ctx);
+        let value_to_int = create_value_mappings(abac_data);
+        let get_int = |val: &AttributeValue| -> i64 { *value_to_int.get(val).unwrap_or(&-1) };
+
+        let user_variants: Vec<(String, String)> = abac_data.users.iter()
+            .map(|user| (user.user_id.clone(), user.user_id.clone()))
+            .collect();
+        let resource_variants: Vec<(String, String)> = abac_data.resources.iter()
+            .map(|resource| (resource.resource_id.clone(), resource.resource_id.clone()))
+            .collect();
+        let action_variants: Vec<(String, Action)> = Action::iter()
+            .map(|action| (action.to_string(), action))
+            .collect();
+        let (user_dt, user_mapping) = enumeration_sort(ctx, "User", &user_variants);
+        let (resource_dt, resource_mapping) = enumeration_sort(ctx, "Resource", &resource_variants);
+        let (action_dt, action_mapping) = enumeration_sort(ctx, "Action", &action_variants);
+
+        let int_sort = Sort::int(ctx);
+        let bool_sort = Sort::bool(ctx);
+        let mut attr_funcs = HashMap::new();
+        for attr_name in AttributeName::iter() {
+            let user_func = declare_attribute_func(ctx, "user", &attr_name, &user_dt.sort, &int_sort, &bool_sort, &abac_data.users);
+            let resource_func = declare_attribute_func(ctx, "resource", &attr_name, &resource_dt.sort, &int_sort, &bool_sort, &abac_data.resources);
+            attr_funcs.insert(attr_name, (user_func, resource_func));
+        }
+
+        for user in &abac_data.users {
+            for (attr_name, (user_func, _)) in &attr_funcs {
+                if let Some(z3_func) = user_func {
+                    assert_attribute(ctx, &solver, z3_func, &user_mapping[&user.user_id], user, attr_name, &value_to_int, &get_int);
+                }
+            }
+        }
+        for resource in &abac_data.resources {
+            for (attr_name, (_, resource_func)) in &attr_funcs {
+                if let Some(z3_func) = resource_func {
+                    assert_attribute(ctx, &solver, z3_func, &resource_mapping[&resource.resource_id], resource, attr_name, &value_to_int, &get_int);
+                }
+            }
+        }
+
+        let u_var = Dynamic::new_const(ctx, Symbol::String("u".to_string()), &user_dt.sort);
+        let r_var = Dynamic::new_const(ctx, Symbol::String("r".to_string()), &resource_dt.sort);
+        let a_var = Dynamic::new_const(ctx, Symbol::String("a".to_string()), &action_dt.sort);
+
+        let user_ids = user_mapping.iter().map(|(id, c)| (c.clone(), id.clone())).collect();
+        let resource_ids = resource_mapping.iter().map(|(id, c)| (c.clone(), id.clone())).collect();
+        let actions = action_mapping.iter().map(|(action, c)| (c.clone(), action.clone())).collect();
+
+        Ok(Self {
+            ctx,
+            solver,
+            rules: abac_data.rules.clone(),
+            value_to_int,
+            attr_funcs,
+            user_mapping,
+            resource_mapping,
+            action_mapping,
+            user_ids,
+            resource_ids,
+            actions,
+            u_var,
+            r_var,
+            a_var,
+        })
+    }
+
+    /// Enumerates the permitted triples, at most `max` of them, sorted by user, resource and action.
+    pub fn solve_access_control(&mut self, max: Option<u64>) -> Vec<UniversityAccessResult> {
+        self.solver.push();
+        self.solver.assert(&self.user_can_perform_action());
+        let mut results = Enumerator::new(max, Verbosity::Silent).enumerate(self, &mut Checker::default());
+        self.solver.pop(1);
+        results.sort();
+        results
+    }
+
+    /// Checks whether `user_id` may perform `action` on `resource_id` under any rule.
+    pub fn check_access(&mut self, user_id: &str, resource_id: &str, action: Action) -> Result<bool, String> {
+        let u_const = self.user_mapping.get(user_id)
+            .ok_or_else(|| format!("Unknown user: {}", user_id))?;
+        let r_const = self.resource_mapping.get(resource_id)
+            .ok_or_else(|| format!("Unknown resource: {}", resource_id))?;
+        self.solver.push();
+        self.solver.assert(&self.u_var._eq(u_const));
+        self.solver.assert(&self.r_var._eq(r_const));
+        self.solver.assert(&self.a_var._eq(&self.action_mapping[&action]));
+        self.solver.assert(&self.user_can_perform_action());
+        let result = self.solver.check();
+        self.solver.pop(1);
+        Ok(result == SatResult::Sat)
+    }
+
+    fn user_can_perform_action(&self) -> Bool<'ctx> {
+        let grants: Vec<Bool> = self.rules.iter().map(|rule| self.translate_rule(rule)).collect();
+        Bool::or(self.ctx, &grants.iter().collect::<Vec<_>>())
+    }
+
+    fn translate_rule(&self, rule: &UniversityRule) -> Bool<'ctx> {
+        let mut conjuncts: Vec<Bool> = rule.user_conditions.iter()
+            .chain(&rule.resource_conditions)
+            .chain(&rule.comparison_conditions)
+            .map(|cond| self.translate_condition(cond))
+            .collect();
+        let allowed: Vec<Bool> = rule.actions.iter()
+            .map(|action| self.a_var._eq(&self.action_mapping[action]))
+            .collect();
+        conjuncts.push(Bool::or(self.ctx, &allowed.iter().collect::<Vec<_>>()));
+        Bool::and(self.ctx, &conjuncts.iter().collect::<Vec<_>>())
+    }
+
+    // `x [ S` holds when x is a member of S and `S ] x` is the same test written the other way
+    // round; `=` compares two values or, against a literal set, means "one of".
+    fn translate_condition(&self, condition: &Condition<AttributeExpression>) -> Bool<'ctx> {
+        use ComparisonOperator::*;
+        let (Some(left), Some(right)) = (self.translate_term(&condition.left), self.translate_term(&condition.right)) else {
+            return Bool::from_bool(self.ctx, false);
+        };
+        let (member, collection) = match condition.operator {
+            Equals | ContainedIn => (left, right),
+            Contains => (right, left),
+            GreaterThan | LessThan | GreaterThanOrEqual | LessThanOrEqual => unreachable!("ordering conditions are rejected by new"),
+        };
+        match (member, collection) {
+            (Term::Value(value, defined), collection) => {
+                let test = match collection {
+                    Term::Value(other, other_defined) => {
+                        let equal = value._eq(&other);
+                        match other_defined {
+                            Some(other_defined) => Bool::and(self.ctx, &[&other_defined, &equal]),
+                            None => equal,
+                        }
+                    },
+                    Term::Set(func, entity) => func.apply(&[&entity, &value]).as_bool().unwrap(),
+                    Term::Values(values) => {
+                        let one_of: Vec<Bool> = values.iter().map(|v| value._eq(v)).collect();
+                        Bool::or(self.ctx, &one_of.iter().collect::<Vec<_>>())
+                    },
+                };
+                match defined {
+                    Some(defined) => Bool::and(self.ctx, &[&defined, &test]),
+                    None => test,
+                }
+            },
+            _ => Bool::from_bool(self.ctx, false),
+        }
+    }
+
+    // No university attribute is declared for both users and resources, so the side that
+    // declares it decides the entity; `uid` always refers to the requesting user.
+    fn translate_term(&self, expr: &AttributeExpression) -> Option<Term<'ctx>> {
+        let get_int = |val: &AttributeValue| Int::from_i64(self.ctx, *self.value_to_int.get(val).unwrap_or(&-1));
+        match expr {
+            AttributeExpression::AttributeName(name) => {
+                let (user_func, resource_func) = self.attr_funcs.get(name)?;
+                let (z3_func, entity) = match (user_func, resource_func) {
+                    (Some(func), _) => (func, &self.u_var),
+                    (None, Some(func)) => (func, &self.r_var),
+                    (None, None) => return None,
+                };
+                Some(match z3_func {
+                    Z3Func::Single(func, has_func) => Term::Value(
+                        func.apply(&[entity]).as_int().unwrap(),
+                        Some(has_func.apply(&[entity]).as_bool().unwrap()),
+                    ),
+                    Z3Func::Set(func) => Term::Set(func.clone(), entity.clone()),
+                })
+            },
+            AttributeExpression::AttributeValue(value) => Some(Term::Value(get_int(value), None)),
+            AttributeExpression::ValueSet(values) => Some(Term::Values(values.iter().map(get_int).collect())),
+        }
+    }
+}
+
+impl<'ctx> EnumerationSpace<'ctx> for UniversityAbacSolver<'ctx> {
+    type Result = UniversityAccessResult;
+
+    fn ctx(&self) -> &'ctx Context {
+        self.ctx
+    }
+
+    fn solver(&self) -> &Solver<'ctx> {
+        &self.solver
+    }
+
+    fn query_vars(&self) -> [&Dynamic<'ctx>; 3] {
+        [&self.u_var, &self.r_var, &self.a_var]
+    }
+
+    fn resource_mapping(&self) -> &HashMap<String, Dynamic<'ctx>> {
+        &self.resource_mapping
+    }
+
+    fn check(&self) -> SatResult {
+        self.solver.check()
+    }
+
+    fn result(&self, found_u: &Dynamic<'ctx>, found_r: &Dynamic<'ctx>, found_a: &Dynamic<'ctx>) -> UniversityAccessResult {
+        UniversityAccessResult {
+            user_id: self.user_ids[found_u].clone(),
+            resource_id: self.resource_ids[found_r].clone(),
+            action: self.actions[found_a].clone(),
+        }
+    }
+}
+
+fn create_value_mappings(data: &UniversityAbac) -> HashMap<AttributeValue, i64> {
+    let mut value_to_int = HashMap::new();
+    let mut insert_val = |val: &AttributeValue| {
+        let next = value_to_int.len() as i64;
+        value_to_int.entry(val.clone()).or_insert(next);
+    };
+    for attr_name in AttributeName::iter() {
+        for user in &data.users {
+            if let Some(val) = user.get_attribute_value(&attr_name) { insert_val(&val); }
+            if let Some(set) = user.get_attribute_set(&attr_name) { set.iter().for_each(&mut insert_val); }
+        }
+        for resource in &data.resources {
+            if let Some(val) = resource.get_attribute_value(&attr_name) { insert_val(&val); }
+            if let Some(set) = resource.get_attribute_set(&attr_name) { set.iter().for_each(&mut insert_val); }
+        }
+    }
+    for rule in &data.rules {
+        for cond in rule.user_conditions.iter().chain(&rule.resource_conditions).chain(&rule.comparison_conditions) {
+            for expr in [&cond.left, &cond.right] {
+                match expr {
+                    AttributeExpression::AttributeValue(val) => insert_val(val),
+                    AttributeExpression::ValueSet(vals) => vals.iter().for_each(&mut insert_val),
+                    AttributeExpression::AttributeName(_) => {},
+                }
+            }
+        }
+    }
+    value_to_int
+}
+
+// A value or membership function for `attr_name` when any entity has it; sets are the
+// attributes the extractor returns as sets
+fn declare_attribute_func<'ctx, E: AttributeValueExtractor<AttributeName = AttributeName, AttributeValue = AttributeValue>>(
+    ctx: &'ctx Context,
+    prefix: &str,
+    attr_name: &AttributeName,
+    entity_sort: &Sort<'ctx>,
+    int_sort: &Sort<'ctx>,
+    bool_sort: &Sort<'ctx>,
+    entities: &[E],
+) -> Option<Z3Func<'ctx>> {
+    if entities.iter().any(|e| e.get_attribute_set(attr_name).is_some()) {
+        Some(Z3Func::Set(FuncDecl::new(ctx, format!("{}_has_{}", prefix, attr_name), &[entity_sort, int_sort], bool_sort)))
+    } else if entities.iter().any(|e| e.get_attribute_value(attr_name).is_some()) {
+        Some(Z3Func::Single(
+            FuncDecl::new(ctx, format!("{}_{}", prefix, attr_name), &[entity_sort], int_sort),
+            FuncDecl::new(ctx, format!("{}_defines_{}", prefix, attr_name), &[entity_sort], bool_sort),
+        ))
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn assert_attribute<'ctx, E: AttributeValueExtractor<AttributeName = AttributeName, AttributeValue = AttributeValue>>(
+    ctx: &'ctx Context,
+    solver: &Solver<'ctx>,
+    z3_func: &Z3Func<'ctx>,
+    entity_const: &Dynamic<'ctx>,
+    entity: &E,
+    attr_name: &AttributeName,
+    value_to_int: &HashMap<AttributeValue, i64>,
+    get_int: &impl Fn(&AttributeValue) -> i64,
+) {
+    match z3_func {
+        Z3Func::Single(func, has_func) => {
+            let has_val = has_func.apply(&[entity_const]).as_bool().unwrap();
+            if let Some(val) = entity.get_attribute_value(attr_name) {
+                solver.assert(&has_val);
+                solver.assert(&func.apply(&[entity_const]).as_int().unwrap()._eq(&Int::from_i64(ctx, get_int(&val))));
+            } else {
+                solver.assert(&has_val.not());
+            }
+        },
+        Z3Func::Set(func) => {
+            let members: Vec<i64> = entity.get_attribute_set(attr_name)
+                .map(|s| s.iter().map(get_int).collect())
+                .unwrap_or_default();
+            for val_int in value_to_int.values() {
+                let has_val = func.apply(&[entity_const, &Int::from_i64(ctx, *val_int)]).as_bool().unwrap();
+                solver.assert(&if members.contains(val_int) { has_val } else { has_val.not() });
+            }
+        },
+    }
+}
@@ -1,10 +1,18 @@
 use z3::ast::{Ast, Bool, Dynamic, Int};
-use z3::{Config, Context, DatatypeAccessor, DatatypeBuilder, FuncDecl, SatResult, Solver, Sort, Symbol};
+use z3::{Config, Context, DatatypeAccessor, DatatypeBuilder, FuncDecl, Model, SatResult, Solver, Sort, Symbol};
 use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use rayon::prelude::*;
+use strum::IntoEnumIterator;
 
-use crate::types::edocument_types::{EdocumentAbac, EdocumentRule, EdocumentUserAttribute, EdocumentResourceAttribute, AttributeName, AttributeValue, AttributeExpression};
-use crate::types::types::{Condition, AttributeValueExtractor};
+use crate::types::edocument_types::{EdocumentAbac, EdocumentRule, Effect, EdocumentUserAttribute, EdocumentResourceAttribute, AttributeName, AttributeValue, AttributeExpression, Action, ActionHierarchy, Role, Tenant, PartialUserAttributes, PUBLIC_SECURITY_LEVEL, parse_date, parse_working_hours, delegates_within, supervisors_within};
+use crate::types::types::{AbacError, ComparisonOperator, Condition, AttributeValueExtractor};
+use super::constraint_model::{Checker, ConstraintModel, Enumerator};
+pub use super::constraint_model::SortCache;
 
 // This is the original example function.
 pub fn how_to_use_z3_example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -154,235 +162,1623 @@ Found {} matching pairs:", solutions.len());
     Ok(())
 }
 
-/// Solves a real-world ABAC scenario loaded from a JSON file.
+/// Solves a real-world ABAC scenario for an already loaded dataset.
 /// This function initializes the Z3 solver with all users, resources, and their attributes once.
 /// It then iterates through each rule, using Z3's push/pop mechanism for efficient, scoped rule evaluation.
-pub fn solve_real_world_scenario(json_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    println!("--- Running Real-World ABAC Solver from '{}' ---", json_path);
+/// Each rule grants its own actions plus every lower action implied by `action_hierarchy`.
+pub fn solve_real_world_scenario(abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy, verbosity: Verbosity, only_actions: &HashSet<Action>) -> Result<SolveStats, Box<dyn std::error::Error>> {
+    let normal = verbosity >= Verbosity::Normal;
+    if normal { println!("--- Running Real-World ABAC Solver ---"); }
+    let start_time = Instant::now();
     let mut cfg = Config::new();
     // cfg.set_bool_param_value("parallel.enable", true);
     let ctx = Context::new(&cfg);
-    let solver = Solver::new(&ctx);
 
-    // 1. Load and parse the entire dataset from JSON
-    let json_content = fs::read_to_string(json_path)?;
-    let abac_data: EdocumentAbac = serde_json::from_str(&json_content)?;
-    println!("Successfully parsed {} users, {} resources, and {} rules.",
-             abac_data.users.len(), abac_data.resources.len(), abac_data.rules.len());
+    if normal {
+        println!("Loaded {} users, {} resources, and {} rules.",
+                 abac_data.users.len(), abac_data.resources.len(), abac_data.rules.len());
+    }
 
-    // --- Base Context Setup (Done Once) ---
+    let mut abac_solver = EdocumentAbacSolver::new(&ctx, abac_data, action_hierarchy);
+    abac_solver.set_verbosity(verbosity);
+    abac_solver.restrict_actions(only_actions);
+    if normal { println!("Base context with all data axioms is set up."); }
+    abac_solver.print_encoding_summary();
 
-    // 2. Create a unified map for all attribute values (String to Int mapping)
-    let (value_to_int, _int_to_value) = create_value_mappings(&abac_data);
-    let get_int = |val: &AttributeValue| -> i64 { *value_to_int.get(val).unwrap_or(&-1) };
+    let solutions_found = abac_solver.solve_each_rule();
+    Ok(SolveStats::new(abac_data, abac_solver.constraint_count(), solutions_found, start_time.elapsed()))
+}
 
-    // 3. Define User and Resource types as Datatypes (Closed World)
-    let user_dt = {
-        let mut builder = DatatypeBuilder::new(&ctx, Symbol::String("User".to_string()));
-        for user in &abac_data.users {
-            builder = builder.variant(user.user_id.as_str(), vec![]);
+/// The entity a custom predicate registered with `register_predicate` ranges over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredicateArity {
+    User,
+    Resource,
+}
+
+/// What the solver allows when no rule mentions a triple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BasePolicy {
+    /// Only triples granted by a permit rule are allowed
+    #[default]
+    DenyAll,
+    /// Every triple is allowed unless a deny rule matches it
+    AllowAll,
+}
+
+/// How much progress output the solver prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// No output; the default when the solver is used as a library
+    #[default]
+    Silent,
+    /// Per-rule progress and result counts
+    Normal,
+    /// Additionally prints every permitted triple as it is found
+    Verbose,
+}
+
+/// Enumerates every permitted triple with the resources split into `shards` partitions,
+/// each solved on its own thread with its own `Context` and solver.
+///
+/// Every shard encodes the full dataset (all users, resources, attributes and rules); only
+/// the domain of the free `r` variable is narrowed to the shard's resources. Splitting the
+/// rules or attributes instead would drop grants, so the shards can simply be concatenated.
+pub fn solve_sharded(abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy, only_actions: &HashSet<Action>, shards: usize) -> Vec<EdocumentAccessResult> {
+    let shards = shards.max(1);
+    (0..shards).into_par_iter()
+        .flat_map(|shard| {
+            let shard_resources: Vec<String> = abac_data.resources.iter()
+                .enumerate()
+                .filter(|(i, _)| i % shards == shard)
+                .map(|(_, resource)| resource.resource_id.clone())
+                .collect();
+            let ctx = Context::new(&Config::new());
+            let mut abac_solver = EdocumentAbacSolver::new(&ctx, abac_data, action_hierarchy);
+            abac_solver.restrict_actions(only_actions);
+            abac_solver.solve_for_resources(&shard_resources, None)
+        })
+        .collect()
+}
+
+/// Enumerates every permitted triple with one thread per action, each with its own `Context`
+/// and solver over the full dataset whose free `a` variable is pinned to that action. The
+/// actions partition the triples, so the per-action results are simply concatenated. Actions
+/// outside a non-empty `only_actions` get no thread.
+pub fn solve_access_control_parallel(abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy, only_actions: &HashSet<Action>) -> Vec<EdocumentAccessResult> {
+    let actions: Vec<Action> = Action::iter()
+        .filter(|action| only_actions.is_empty() || only_actions.contains(action))
+        .collect();
+    actions.into_par_iter()
+        .flat_map(|action| {
+            let ctx = Context::new(&Config::new());
+            let mut abac_solver = EdocumentAbacSolver::new(&ctx, abac_data, action_hierarchy);
+            abac_solver.restrict_actions(&HashSet::from([action]));
+            abac_solver.solve_access_control(None, None)
+        })
+        .collect()
+}
+
+/// Enumerates every permitted triple on Tokio's blocking thread pool, so an async service
+/// does not stall its runtime while Z3 works.
+///
+/// `z3::Context` is not `Send`, so the context and solver are built inside the blocking task
+/// from the owned `data` and never cross threads.
+///
+/// ```ignore
+/// let data: EdocumentAbacData = serde_json::from_str(&json)?;
+/// let results = solve_access_control_async(data, Some(1000)).await?;
+/// for result in &results {
+///     println!("{}", result);
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn solve_access_control_async(data: EdocumentAbac, max: Option<u64>) -> Result<Vec<EdocumentAccessResult>, AbacError> {
+    tokio::task::spawn_blocking(move || {
+        let ctx = Context::new(&Config::new());
+        let mut abac_solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+        abac_solver.solve_access_control(max, None)
+    })
+    .await
+    .map_err(|e| AbacError::Solver(e.to_string()))
+}
+
+/// Default cap on users and on resources; each becomes a variant of a Z3 datatype sort, and
+/// sorts much larger than this exhaust memory before solving starts.
+pub const DEFAULT_MAX_ENTITIES: usize = 50_000;
+
+/// Fails with `AbacError::TooLarge` when the users or the resources exceed `limit`, so an
+/// oversized dataset is rejected before `EdocumentAbacSolver::new` builds its sorts.
+pub fn check_entity_limit(abac_data: &EdocumentAbac, limit: usize) -> Result<(), AbacError> {
+    let count = abac_data.users.len().max(abac_data.resources.len());
+    if count > limit {
+        return Err(AbacError::TooLarge { count, limit });
+    }
+    Ok(())
+}
+
+/// Summary of one solver run, written as a JSON line by `--stats-out`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SolveStats {
+    /// Seconds since the Unix epoch when the run finished
+    pub timestamp: u64,
+    /// FNV-1a hash of the serialized input dataset, to tell policy versions apart. It is stable
+    /// across runs and builds; see `dataset_fingerprint`
+    pub dataset_hash: String,
+    pub users: usize,
+    pub resources: usize,
+    pub rules: usize,
+    /// Number of base axioms asserted on the solver
+    pub constraint_count: usize,
+    pub solutions_found: usize,
+    pub total_time_secs: f64,
+    pub solutions_per_sec: f64,
+    /// Time between consecutive solutions, when the run timed each one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution_times: Option<SolutionTimes>,
+}
+
+/// Minimum, maximum and mean time taken to find each solution of a run.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SolutionTimes {
+    pub min_secs: f64,
+    pub max_secs: f64,
+    pub mean_secs: f64,
+}
+
+impl SolutionTimes {
+    /// `None` when there are no solutions to time.
+    pub fn from_durations(durations: &[Duration]) -> Option<Self> {
+        let min = durations.iter().min()?;
+        let max = durations.iter().max()?;
+        let total: Duration = durations.iter().sum();
+        Some(Self {
+            min_secs: min.as_secs_f64(),
+            max_secs: max.as_secs_f64(),
+            mean_secs: total.as_secs_f64() / durations.len() as f64,
+        })
+    }
+}
+
+/// 64-bit FNV-1a over the dataset's JSON, as 16 hex digits. Every array is sorted first, since
+/// sets such as a rule's actions serialize in a different order on every run.
+pub fn dataset_fingerprint(abac_data: &EdocumentAbac) -> String {
+    fn sorted(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Array(items) => {
+                let mut items: Vec<serde_json::Value> = items.into_iter().map(sorted).collect();
+                items.sort_by_cached_key(|item| item.to_string());
+                serde_json::Value::Array(items)
+            }
+            serde_json::Value::Object(fields) => serde_json::Value::Object(fields.into_iter().map(|(k, v)| (k, sorted(v))).collect()),
+            other => other,
         }
-        builder.finish()
-    };
-    let resource_dt = {
-        let mut builder = DatatypeBuilder::new(&ctx, Symbol::String("Resource".to_string()));
-        for resource in &abac_data.resources {
-            builder = builder.variant(resource.resource_id.as_str(), vec![]);
+    }
+    let canonical = serde_json::to_value(abac_data).map(sorted).unwrap_or_default().to_string();
+    let hash = canonical.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+/// The part of `SolveStats` that describes the input dataset
+#[derive(Debug, Clone)]
+struct DatasetSummary {
+    hash: String,
+    users: usize,
+    resources: usize,
+    rules: usize,
+}
+
+impl DatasetSummary {
+    fn of(abac_data: &EdocumentAbac) -> Self {
+        Self {
+            hash: dataset_fingerprint(abac_data),
+            users: abac_data.users.len(),
+            resources: abac_data.resources.len(),
+            rules: abac_data.rules.len(),
         }
-        builder.finish()
-    };
+    }
+}
 
-    let user_sort = user_dt.sort;
-    let resource_sort = resource_dt.sort;
-    let int_sort = Sort::int(&ctx);
-    let bool_sort = Sort::bool(&ctx);
+impl SolveStats {
+    pub fn new(abac_data: &EdocumentAbac, constraint_count: usize, solutions_found: usize, total_time: Duration) -> Self {
+        Self::for_dataset(&DatasetSummary::of(abac_data), constraint_count, solutions_found, total_time)
+    }
 
-    // 4. Model all unique attributes as Z3 functions
-    let set_attributes: HashSet<AttributeName> = [
-        AttributeName::Projects,
-        AttributeName::Supervisee,
-        AttributeName::PayrollingPermissions,
-        AttributeName::Recipients,
-    ].iter().cloned().collect();
+    fn for_dataset(dataset: &DatasetSummary, constraint_count: usize, solutions_found: usize, total_time: Duration) -> Self {
+        Self {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            dataset_hash: dataset.hash.clone(),
+            users: dataset.users,
+            resources: dataset.resources,
+            rules: dataset.rules,
+            constraint_count,
+            solutions_found,
+            total_time_secs: total_time.as_secs_f64(),
+            solutions_per_sec: if total_time.is_zero() { 0.0 } else { solutions_found as f64 / total_time.as_secs_f64() },
+            solution_times: None,
+        }
+    }
 
-    let mut attr_funcs: HashMap<AttributeName, (Option<Z3Func>, Option<Z3Func>)> = HashMap::new();
+    /// Prints these stats as a human-readable block.
+    pub fn print(&self) {
+        println!("Total solutions: {}", self.solutions_found);
+        println!("Total time: {:.3}s ({:.1} solutions per second)", self.total_time_secs, self.solutions_per_sec);
+        if let Some(times) = &self.solution_times {
+            println!("Time per solution: avg {:.3}ms, min {:.3}ms, max {:.3}ms",
+                     times.mean_secs * 1000.0, times.min_secs * 1000.0, times.max_secs * 1000.0);
+        }
+    }
 
-    for attr_name in get_all_attribute_names_enum_variants() {
-        let is_set_attr = set_attributes.contains(&attr_name);
-        let mut user_func = None;
-        let mut resource_func = None;
+    /// Appends these stats as one line of newline-delimited JSON.
+    pub fn append_to(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|e| format!("Failed to open stats file {}: {}", path, e))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
 
-        if abac_data.users.iter().any(|u| u.get_attribute_value(&attr_name).is_some() || u.get_attribute_set(&attr_name).is_some()) {
-            user_func = Some(if is_set_attr {
-                Z3Func::Set(FuncDecl::new(&ctx, format!("user_{}", attr_name), &[&user_sort, &int_sort], &bool_sort))
-            } else {
-                Z3Func::Single(FuncDecl::new(&ctx, format!("user_{}", attr_name), &[&user_sort], &int_sort))
-            });
+/// A (user, resource, action) triple permitted by the policy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct EdocumentAccessResult {
+    pub user_id: String,
+    pub resource_id: String,
+    /// Serialized with its camelCase rule name, like every other output
+    #[serde(serialize_with = "serialize_action_name")]
+    pub action: Action,
+}
+
+fn serialize_action_name<S: serde::Serializer>(action: &Action, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(action)
+}
+
+/// By user, then resource, then action name: the order solves return results in
+impl Ord for EdocumentAccessResult {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.user_id, &self.resource_id, self.action.to_string())
+            .cmp(&(&other.user_id, &other.resource_id, other.action.to_string()))
+    }
+}
+
+impl PartialOrd for EdocumentAccessResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl EdocumentAccessResult {
+    /// Writes `results` as CSV with a `user,resource,action` header, in the order given.
+    /// Fields containing a comma, quote or newline are quoted, with quotes doubled.
+    ///
+    /// ```
+    /// use abac_solver::edocument::{Action, EdocumentAccessResult};
+    ///
+    /// let results = [
+    ///     EdocumentAccessResult { user_id: "alice".to_string(), resource_id: "doc1".to_string(), action: Action::View },
+    ///     EdocumentAccessResult { user_id: "bob".to_string(), resource_id: "q3,\"final\"".to_string(), action: Action::Edit },
+    /// ];
+    /// let mut out = Vec::new();
+    /// EdocumentAccessResult::write_csv(&results, &mut out).unwrap();
+    /// assert_eq!(out, b"user,resource,action\nalice,doc1,view\nbob,\"q3,\"\"final\"\"\",edit\n");
+    /// ```
+    pub fn write_csv<W: Write>(results: &[Self], mut w: W) -> std::io::Result<()> {
+        writeln!(w, "user,resource,action")?;
+        for result in results {
+            writeln!(w, "{},{},{}", csv_field(&result.user_id), csv_field(&result.resource_id), csv_field(&result.action.to_string()))?;
         }
-        if abac_data.resources.iter().any(|r| r.get_attribute_value(&attr_name).is_some() || r.get_attribute_set(&attr_name).is_some()) {
-            resource_func = Some(if is_set_attr {
-                Z3Func::Set(FuncDecl::new(&ctx, format!("resource_has_{}", attr_name), &[&resource_sort, &int_sort], &bool_sort))
-            } else {
-                Z3Func::Single(FuncDecl::new(&ctx, format!("resource_{}", attr_name), &[&resource_sort], &int_sort))
-            });
+        Ok(())
+    }
+}
+
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+impl std::fmt::Display for EdocumentAccessResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} can {} {}", self.user_id, self.action, self.resource_id)
+    }
+}
+
+/// Triples found by a capped enumeration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CappedResults {
+    pub results: Vec<EdocumentAccessResult>,
+    /// The cap stopped the enumeration while more triples were still permitted. Finding
+    /// exactly as many triples as the cap allows leaves this false.
+    pub truncated: bool,
+}
+
+impl CappedResults {
+    /// The results in the `--json-out` schema. Actions use their camelCase names, as in rules.
+    ///
+    /// ```
+    /// use abac_solver::edocument::{Action, CappedResults, EdocumentAccessResult};
+    ///
+    /// let capped = CappedResults {
+    ///     results: vec![EdocumentAccessResult { user_id: "alice".to_string(), resource_id: "doc1".to_string(), action: Action::ReadMetaInfo }],
+    ///     truncated: true,
+    /// };
+    /// assert_eq!(capped.to_json(), serde_json::json!({
+    ///     "results": [{"user": "alice", "resource": "doc1", "action": "readMetaInfo"}],
+    ///     "total": 1,
+    ///     "truncated": true,
+    /// }));
+    /// ```
+    pub fn to_json(&self) -> serde_json::Value {
+        let results: Vec<serde_json::Value> = self.results.iter()
+            .map(|result| serde_json::json!({
+                "user": result.user_id,
+                "resource": result.resource_id,
+                "action": result.action.to_string(),
+            }))
+            .collect();
+        serde_json::json!({
+            "results": results,
+            "total": self.results.len(),
+            "truncated": self.truncated,
+        })
+    }
+}
+
+/// An edocument policy over a `ConstraintModel` that can be queried repeatedly.
+///
+/// `new` builds the model, asserting every user and resource attribute once. Each query
+/// constrains the free `u`, `r` and `a` variables inside its own push/pop scope,
+/// so the base state is left untouched between queries.
+pub struct EdocumentAbacSolver<'ctx> {
+    model: ConstraintModel<'ctx>,
+    checker: Checker<'ctx>,
+    rules: Vec<EdocumentRule>,
+    action_hierarchy: ActionHierarchy,
+    /// resource id -> department of the resource's owner, when both are known
+    owner_departments: HashMap<String, String>,
+    /// resource id -> owner's user id, when the resource has an owner
+    resource_owners: HashMap<String, String>,
+    /// user id -> ids of the users the user has delegated authority to
+    delegations: HashMap<String, HashSet<String>>,
+    /// user id -> ids of the user's direct supervisors
+    supervisors: HashMap<String, HashSet<String>>,
+    /// Resources with a newer version in the same document family
+    version_ranks: HashMap<String, i64>,
+    /// resource id -> day (since 1970-01-01) its retention period ends
+    retention_expiry: HashMap<String, i64>,
+    /// user id -> (start, end) of the user's working hours in minutes after midnight
+    working_hours: HashMap<String, (u32, u32)>,
+    /// Resources marked as archived
+    archived_resources: HashSet<String>,
+    /// Whether archived resources only accept read-only actions
+    archived_read_only: bool,
+    /// Resources whose approval status is draft or pending
+    awaiting_approval: HashSet<String>,
+    /// Users employed as contractors or temporary staff
+    external_staff: HashSet<String>,
+    /// Resources at the public security level that are explicitly not confidential
+    public_resources: HashSet<String>,
+    /// Grants that hold regardless of the permit rules, still subject to deny rules and restrictions
+    baseline_grants: Vec<Bool<'ctx>>,
+    /// Extra constraints every grant must satisfy on top of the rules, each with a description
+    /// for `denial_reasons`
+    restrictions: Vec<(String, Bool<'ctx>)>,
+    /// Grants and restrictions grounded over the users again on every query, so they cover
+    /// users added later with `add_user`
+    user_grounded_grants: Vec<UserGroundedGrant>,
+    user_grounded_restrictions: Vec<UserGroundedRestriction>,
+    base_policy: BasePolicy,
+    /// Rules whose matching triples are denied even if a permit rule grants them
+    deny_rules: Vec<EdocumentRule>,
+    verbosity: Verbosity,
+    /// The dataset the solver was built from, for the stats it reports
+    dataset: DatasetSummary,
+}
+
+impl<'ctx> EdocumentAbacSolver<'ctx> {
+    pub fn new(ctx: &'ctx Context, abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy) -> Self {
+        Self::with_spare_users(ctx, abac_data, action_hierarchy, 0)
+    }
+
+    /// Like `new`, but leaves room for `spare_users` calls to `add_user`.
+    pub fn with_spare_users(ctx: &'ctx Context, abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy, spare_users: usize) -> Self {
+        Self::from_model(ctx, ConstraintModel::with_spare_users(ctx, abac_data, spare_users), abac_data, action_hierarchy)
+    }
+
+    /// Like `new`, but reuses the schema sorts in `sorts` instead of declaring them again.
+    /// Solvers built from one `SortCache` share its `Context`.
+    pub fn with_sorts(ctx: &'ctx Context, sorts: &SortCache<'ctx>, abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy) -> Self {
+        Self::from_model(ctx, ConstraintModel::with_sorts(ctx, sorts, abac_data, 0), abac_data, action_hierarchy)
+    }
+
+    fn from_model(ctx: &'ctx Context, model: ConstraintModel<'ctx>, abac_data: &EdocumentAbac, action_hierarchy: &ActionHierarchy) -> Self {
+        // A resource with a supported action set never permits any other action, whoever asks
+        let supported_action_restrictions: Vec<(String, Bool)> = abac_data.resources.iter()
+            .filter_map(|resource| {
+                let supported = resource.supported_actions.as_ref()?;
+                let restriction = model.r_var._eq(&model.resource_mapping[&resource.resource_id])
+                    .implies(&translate_actions_to_z3(ctx, supported, &model.a_var, &model.action_mapping));
+                Some((format!("{} only supports {}", resource.resource_id, action_list(supported)), restriction))
+            })
+            .collect();
+
+        let user_departments: HashMap<&str, &String> = abac_data.users.iter()
+            .filter_map(|user| user.department.as_ref().map(|d| (user.user_id.as_str(), d)))
+            .collect();
+        let owner_departments: HashMap<String, String> = abac_data.resources.iter()
+            .filter_map(|resource| {
+                let owner = resource.owner.as_ref()?;
+                user_departments.get(owner.as_str()).map(|d| (resource.resource_id.clone(), (*d).clone()))
+            })
+            .collect();
+
+        Self {
+            model,
+            checker: Checker::default(),
+            rules: abac_data.rules.iter().filter(|rule| rule.effect == Effect::Permit).cloned().collect(),
+            action_hierarchy: action_hierarchy.clone(),
+            owner_departments,
+            resource_owners: abac_data.resources.iter()
+                .filter_map(|r| Some((r.resource_id.clone(), r.owner.clone()?)))
+                .collect(),
+            delegations: abac_data.delegations(),
+            supervisors: abac_data.supervisors(),
+            version_ranks: abac_data.resource_version_rank(),
+            retention_expiry: abac_data.retention_expiry(),
+            working_hours: abac_data.working_hours(),
+            archived_resources: abac_data.resources.iter()
+                .filter(|r| r.is_archived == Some(true))
+                .map(|r| r.resource_id.clone())
+                .collect(),
+            archived_read_only: true,
+            awaiting_approval: abac_data.resources.iter()
+                .filter(|r| r.approval_status.is_some_and(|status| status.awaits_approval()))
+                .map(|r| r.resource_id.clone())
+                .collect(),
+            external_staff: abac_data.users.iter()
+                .filter(|u| u.contract_type.is_some_and(|contract| contract.is_external()))
+                .map(|u| u.user_id.clone())
+                .collect(),
+            public_resources: abac_data.resources.iter()
+                .filter(|r| r.security_level == Some(PUBLIC_SECURITY_LEVEL) && r.is_confidential == Some(false))
+                .map(|r| r.resource_id.clone())
+                .collect(),
+            baseline_grants: Vec::new(),
+            restrictions: supported_action_restrictions,
+            user_grounded_grants: Vec::new(),
+            user_grounded_restrictions: Vec::new(),
+            base_policy: BasePolicy::default(),
+            deny_rules: abac_data.rules.iter().filter(|rule| rule.effect == Effect::Deny).cloned().collect(),
+            verbosity: Verbosity::default(),
+            dataset: DatasetSummary::of(abac_data),
         }
-        attr_funcs.insert(attr_name, (user_func, resource_func));
     }
 
-    // 5. Assert all user and resource attributes as axioms
-    for (i, user) in abac_data.users.iter().enumerate() {
-        let u_const = user_dt.variants[i].constructor.apply(&[]);
-        for (attr_name, (user_func_opt, _)) in &attr_funcs {
-            if let Some(z3_func) = user_func_opt {
-                match z3_func {
-                    Z3Func::Single(func) => {
-                        if let Some(val) = user.get_attribute_value(attr_name) {
-                            solver.assert(&func.apply(&[&u_const]).as_int().unwrap()._eq(&Int::from_i64(&ctx, get_int(&val))));
-                        }
-                    },
-                    Z3Func::Set(func) => {
-                        let user_values: HashSet<i64> = user.get_attribute_set(attr_name)
-                            .map(|s| s.iter().map(|v| get_int(v)).collect())
-                            .unwrap_or_default();
-                        
-                        for (_, val_int) in &value_to_int {
-                            let z3_val = Int::from_i64(&ctx, *val_int);
-                            let has_val = func.apply(&[&u_const, &z3_val]).as_bool().unwrap();
-                            if user_values.contains(val_int) {
-                                solver.assert(&has_val);
-                            } else {
-                                solver.assert(&has_val.not());
-                            }
-                        }
-                    }
+    /// Adds a user to a running solver without rebuilding the sorts or re-asserting the other
+    /// entities' axioms, so later queries see it like any other user. Needs a spare slot from
+    /// `with_spare_users`; see `ConstraintModel::add_user` for the cost and when it fails.
+    ///
+    /// Restrictions and grants that depend on per-user data (working hours, contract types,
+    /// delegations, supervisors) cover the added user whether they were registered before or after.
+    pub fn add_user(&mut self, user: &EdocumentUserAttribute) -> Result<(), String> {
+        self.model.add_user(user)?;
+        if let Some(window) = user.working_hours.as_deref().and_then(parse_working_hours) {
+            self.working_hours.insert(user.user_id.clone(), window);
+        }
+        if !user.delegated_authority.is_empty() {
+            self.delegations.insert(user.user_id.clone(), user.delegated_authority.clone());
+        }
+        if let Some(supervisor) = &user.supervisor {
+            self.supervisors.entry(user.user_id.clone()).or_default().insert(supervisor.clone());
+        }
+        for report in &user.supervisee {
+            self.supervisors.entry(report.clone()).or_default().insert(user.user_id.clone());
+        }
+        if user.contract_type.is_some_and(|contract| contract.is_external()) {
+            self.external_staff.insert(user.user_id.clone());
+        }
+        Ok(())
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: Verbosity) {
+        self.verbosity = verbosity;
+    }
+
+    /// Prints how the rules are encoded: one summary line at Normal, plus the condition
+    /// and action counts of each rule at Verbose.
+    pub fn print_encoding_summary(&self) {
+        if self.verbosity >= Verbosity::Verbose {
+            for (kind, rules) in [("permit", &self.rules), ("deny", &self.deny_rules)] {
+                for rule in rules {
+                    let conditions = rule.user_conditions.len() + rule.resource_conditions.len() + rule.comparison_conditions.len();
+                    let actions = rule.affected_actions(&self.action_hierarchy).len();
+                    println!("  {} rule {}: {} conditions, {} actions", kind, rule.id, conditions, actions);
                 }
             }
         }
+        if self.verbosity >= Verbosity::Normal {
+            println!("Translated {} rules, {} deny, {} permit.",
+                     self.rules.len() + self.deny_rules.len(), self.deny_rules.len(), self.rules.len());
+        }
     }
-    for (i, resource) in abac_data.resources.iter().enumerate() {
-        let r_const = resource_dt.variants[i].constructor.apply(&[]);
-        for (attr_name, (_, resource_func_opt)) in &attr_funcs {
-            if let Some(z3_func) = resource_func_opt {
-                match z3_func {
-                    Z3Func::Single(func) => {
-                        if let Some(val) = resource.get_attribute_value(attr_name) {
-                            solver.assert(&func.apply(&[&r_const]).as_int().unwrap()._eq(&Int::from_i64(&ctx, get_int(&val))));
-                        }
-                    },
-                    Z3Func::Set(func) => {
-                        let resource_values: HashSet<i64> = resource.get_attribute_set(attr_name)
-                            .map(|s| s.iter().map(|v| get_int(v)).collect())
-                            .unwrap_or_default();
-
-                        for (_, val_int) in &value_to_int {
-                            let z3_val = Int::from_i64(&ctx, *val_int);
-                            let has_val = func.apply(&[&r_const, &z3_val]).as_bool().unwrap();
-                            if resource_values.contains(val_int) {
-                                solver.assert(&has_val);
-                            } else {
-                                solver.assert(&has_val.not());
-                            }
-                        }
-                    }
+
+    /// Under `AllowAll` the permit rules are redundant: every triple is allowed unless a
+    /// deny rule matches it. Under `DenyAll` (the default) only permitted triples are allowed.
+    pub fn set_base_policy(&mut self, base_policy: BasePolicy) {
+        self.base_policy = base_policy;
+    }
+
+    /// Adds a rule that carves its matching triples out of whatever the base policy and
+    /// permit rules allow. It also denies the actions that imply its own under the hierarchy.
+    /// Rules in the data whose `effect` is `Deny` are added this way by `new`.
+    pub fn add_deny_rule(&mut self, rule: EdocumentRule) {
+        self.deny_rules.push(rule);
+    }
+
+    /// Denies `actions` (every action when `None`) to all users whose `attr` is `value`, or whose
+    /// set-valued `attr` contains `value`, whatever the permit rules grant.
+    ///
+    /// Unlike a deny rule, the listed actions are taken literally: denying `view` does not also
+    /// deny the actions the hierarchy derives from it.
+    pub fn deny_by_attribute(&mut self, attr: AttributeName, value: AttributeValue, actions: Option<&[Action]>) {
+        let is_set = matches!(self.model.attr_funcs.get(&attr), Some((Some(Z3Func::Set(_)), _)));
+        let mut matching = EdocumentRule::new(0);
+        matching.user_conditions.push(Condition {
+            left: AttributeExpression::AttributeName(attr),
+            operator: if is_set { ComparisonOperator::Contains } else { ComparisonOperator::Equals },
+            right: AttributeExpression::AttributeValue(value),
+        });
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let user_matches = translate_rule_to_z3(self.model.ctx, &matching, &self.model.attr_funcs, &self.model.u_var, &self.model.r_var, &get_int);
+        let denied: HashSet<Action> = match actions {
+            Some(actions) => actions.iter().cloned().collect(),
+            None => Action::iter().collect(),
+        };
+        let description = format!("users with {} are denied {}", matching.user_conditions[0], action_list(&denied));
+        let denied = translate_actions_to_z3(self.model.ctx, &denied, &self.model.a_var, &self.model.action_mapping);
+        self.restrictions.push((description, Bool::and(self.model.ctx, &[&user_matches, &denied]).not()));
+    }
+
+    /// Renders the base axioms plus the enumeration query as a self-contained SMT-LIB2 script,
+    /// so the same problem can be handed to another solver.
+    pub fn dump_smtlib(&self) -> String {
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        let script = format!("{}(check-sat)\n(get-model)\n", self.model.solver);
+        self.model.solver.pop(1);
+        script
+    }
+
+    /// Registers a custom predicate, e.g. "user is on-call", that rules can reference by name.
+    ///
+    /// `evaluator` is called once per user or resource id to provide the facts, which are
+    /// asserted as base axioms. A rule then matches it like any boolean attribute: in `.abac`
+    /// files with a `custom.` prefix, e.g. `custom.onCall = true`, and in JSON rules as
+    /// `{"AttributeName": {"Custom": "onCall"}}`.
+    pub fn register_predicate(&mut self, name: &str, arity: PredicateArity, evaluator: impl Fn(&str) -> bool) {
+        let (entities, sort) = match arity {
+            PredicateArity::User => (&self.model.user_mapping, self.model.u_var.get_sort()),
+            PredicateArity::Resource => (&self.model.resource_mapping, self.model.r_var.get_sort()),
+        };
+        let func = FuncDecl::new(self.model.ctx, format!("custom_{}", name), &[&sort], &Sort::int(self.model.ctx));
+        let has_func = FuncDecl::new(self.model.ctx, format!("custom_defines_{}", name), &[&sort], &Sort::bool(self.model.ctx));
+        for (id, entity_const) in entities {
+            let value = self.model.value_to_int[&AttributeValue::Boolean(evaluator(id))];
+            self.model.solver.assert(&func.apply(&[entity_const]).as_int().unwrap()._eq(&Int::from_i64(self.model.ctx, value)));
+            self.model.solver.assert(&has_func.apply(&[entity_const]).as_bool().unwrap());
+        }
+
+        let z3_func = Some(Z3Func::Single(func, has_func));
+        let funcs = match arity {
+            PredicateArity::User => (z3_func, None),
+            PredicateArity::Resource => (None, z3_func),
+        };
+        self.model.attr_funcs.insert(AttributeName::Custom(name.to_string()), funcs);
+    }
+
+    /// Narrows every later query to the given actions by constraining the free `a`
+    /// variable to them. An empty set leaves all actions enabled.
+    pub fn restrict_actions(&mut self, actions: &HashSet<Action>) {
+        if actions.is_empty() {
+            return;
+        }
+        let restriction = translate_actions_to_z3(self.model.ctx, actions, &self.model.a_var, &self.model.action_mapping);
+        self.restrictions.push((format!("only {} are queried", action_list(actions)), restriction));
+    }
+
+    /// Only allows `action` on a resource when the acting user is in the department of the resource's owner.
+    ///
+    /// The owner's department is known from the data, so the condition is grounded per resource
+    /// (`r = doc_i ∧ user_department(u) = dept(owner_i)`) instead of quantifying over owners.
+    /// Resources whose owner or owner's department is unknown deny `action` entirely.
+    pub fn require_owner_same_department(&mut self, action: Action) {
+        let user_department = self.translate_expr(&AttributeExpression::AttributeName(AttributeName::Department), &AttributeContext::User, true);
+        let same_department: Vec<Bool> = self.owner_departments.iter()
+            .filter_map(|(resource_id, department)| {
+                let user_department = user_department.as_ref()?;
+                let department_int = self.model.value_to_int.get(&AttributeValue::String(department.clone()))?;
+                Some(Bool::and(self.model.ctx, &[
+                    &self.model.r_var._eq(&self.model.resource_mapping[resource_id]),
+                    &user_department._eq(&Int::from_i64(self.model.ctx, *department_int)),
+                ]))
+            })
+            .collect();
+        let restriction = self.model.a_var._eq(&self.model.action_mapping[&action])
+            .implies(&Bool::or(self.model.ctx, &same_department.iter().collect::<Vec<_>>()));
+        self.restrictions.push((format!("{} needs a user in the owner's department", action), restriction));
+    }
+
+    /// Bell-LaPadula style no-read-up: `actions` on a resource with a security level are only
+    /// allowed when the acting user's clearance level is at least that level. Users without a
+    /// clearance are refused them; resources without a security level are not restricted.
+    pub fn enforce_no_read_up(&mut self, actions: &[Action]) {
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let dominates = Condition {
+            left: AttributeExpression::AttributeName(AttributeName::ClearanceLevel),
+            operator: ComparisonOperator::GreaterThanOrEqual,
+            right: AttributeExpression::AttributeName(AttributeName::SecurityLevel),
+        };
+        let dominates = translate_condition(self.model.ctx, &dominates, &self.model.attr_funcs, &self.model.u_var, &self.model.r_var, &get_int, &AttributeContext::Comparison);
+        let Some((_, has_level, r_var)) = single_attribute_func(&AttributeName::SecurityLevel, &self.model.attr_funcs, &AttributeContext::Resource, &self.model.u_var, &self.model.r_var, true) else {
+            // No resource has a security level
+            return;
+        };
+        let guarded = translate_actions_to_z3(self.model.ctx, &actions.iter().cloned().collect(), &self.model.a_var, &self.model.action_mapping);
+        let restriction = Bool::and(self.model.ctx, &[&guarded, &has_level.apply(&[r_var]).as_bool().unwrap()])
+            .implies(&dominates);
+        let description = format!("{} need a clearance level of at least the security level", action_list(&actions.iter().cloned().collect()));
+        self.restrictions.push((description, restriction));
+    }
+
+    /// Lets every user `view` and `search` public resources without a matching permit rule.
+    ///
+    /// A resource is public when its security level is `PUBLIC_SECURITY_LEVEL` and it is explicitly
+    /// not confidential; a public-level document marked confidential, or with no confidentiality
+    /// set, gets no bypass. Deny rules and restrictions still apply to these grants.
+    pub fn grant_public_read(&mut self) {
+        let public: Vec<Bool> = self.public_resources.iter()
+            .map(|resource_id| self.model.r_var._eq(&self.model.resource_mapping[resource_id]))
+            .collect();
+        let read_actions = Bool::or(self.model.ctx, &[
+            &self.model.a_var._eq(&self.model.action_mapping[&Action::View]),
+            &self.model.a_var._eq(&self.model.action_mapping[&Action::Search]),
+        ]);
+        let grant = Bool::and(self.model.ctx, &[&Bool::or(self.model.ctx, &public.iter().collect::<Vec<_>>()), &read_actions]);
+        self.baseline_grants.push(grant);
+    }
+
+    /// Lets the users a resource's owner delegated authority to perform `actions` on it without a
+    /// matching permit rule. Delegation is transitive through up to `max_depth` intermediaries, so
+    /// with 0 only the owner's direct delegates are granted. Deny rules and restrictions still apply.
+    pub fn grant_delegated_access(&mut self, actions: &[Action], max_depth: usize) {
+        self.user_grounded_grants.push(UserGroundedGrant::Delegated(actions.iter().cloned().collect(), max_depth));
+    }
+
+    fn delegated_grant(&self, actions: &HashSet<Action>, max_depth: usize) -> Bool<'ctx> {
+        let Some((Some(Z3Func::Set(delegated_to)), _)) = self.model.attr_funcs.get(&AttributeName::DelegatedAuthority) else {
+            return Bool::from_bool(self.model.ctx, false);
+        };
+        let Some(user_uid) = self.translate_expr(&AttributeExpression::AttributeName(AttributeName::Uid), &AttributeContext::User, true) else {
+            return Bool::from_bool(self.model.ctx, false);
+        };
+        let delegated: Vec<Bool> = self.resource_owners.iter()
+            .filter_map(|(resource_id, owner)| {
+                let mut delegators = match max_depth {
+                    0 => HashSet::new(),
+                    depth => delegates_within(&self.delegations, owner, depth - 1),
+                };
+                delegators.insert(owner.clone());
+                let holds_authority: Vec<Bool> = delegators.iter()
+                    .filter_map(|delegator| self.model.user_mapping.get(delegator))
+                    .map(|delegator| delegated_to.apply(&[delegator, &user_uid]).as_bool().unwrap())
+                    .collect();
+                if holds_authority.is_empty() {
+                    return None;
+                }
+                Some(Bool::and(self.model.ctx, &[
+                    &self.model.r_var._eq(&self.model.resource_mapping[resource_id]),
+                    &Bool::or(self.model.ctx, &holds_authority.iter().collect::<Vec<_>>()),
+                ]))
+            })
+            .collect();
+        let guarded = translate_actions_to_z3(self.model.ctx, actions, &self.model.a_var, &self.model.action_mapping);
+        Bool::and(self.model.ctx, &[&Bool::or(self.model.ctx, &delegated.iter().collect::<Vec<_>>()), &guarded])
+    }
+
+    /// Grants what `rule` grants, but only to users who supervise the resource's owner, directly
+    /// or up to `max_levels` levels up the management chain; with 1 only direct supervisors
+    /// qualify. Resources without an owner match nobody, and deny rules and restrictions still apply.
+    pub fn add_supervisor_chain_rule(&mut self, rule: &EdocumentRule, max_levels: usize) {
+        self.user_grounded_grants.push(UserGroundedGrant::SupervisorChain(rule.clone(), max_levels));
+    }
+
+    fn supervisor_chain_grant(&self, rule: &EdocumentRule, max_levels: usize) -> Bool<'ctx> {
+        let supervised: Vec<Bool> = self.resource_owners.iter()
+            .filter_map(|(resource_id, owner)| {
+                let chain: Vec<Bool> = supervisors_within(&self.supervisors, owner, max_levels).iter()
+                    .filter_map(|supervisor| self.model.user_mapping.get(supervisor))
+                    .map(|supervisor| self.model.u_var._eq(supervisor))
+                    .collect();
+                if chain.is_empty() {
+                    return None;
                 }
+                Some(Bool::and(self.model.ctx, &[
+                    &self.model.r_var._eq(&self.model.resource_mapping[resource_id]),
+                    &Bool::or(self.model.ctx, &chain.iter().collect::<Vec<_>>()),
+                ]))
+            })
+            .collect();
+        Bool::and(self.model.ctx, &[
+            &self.translate_rule(rule),
+            &Bool::or(self.model.ctx, &supervised.iter().collect::<Vec<_>>()),
+        ])
+    }
+
+    /// Makes `approve` follow the approval workflow: it is only permitted on resources whose
+    /// approval status is draft or pending, and only to users holding one of `approver_roles`.
+    /// Resources that are already approved or rejected, or have no approval status, cannot be approved.
+    pub fn require_approval_workflow(&mut self, approver_roles: &[Role]) {
+        let awaiting: Vec<Bool> = self.awaiting_approval.iter()
+            .map(|resource_id| self.model.r_var._eq(&self.model.resource_mapping[resource_id]))
+            .collect();
+        let holds_approver_role: Vec<Bool> = match self.model.attr_funcs.get(&AttributeName::Role) {
+            Some((Some(Z3Func::Set(user_has_role)), _)) => approver_roles.iter()
+                .filter_map(|role| self.model.value_to_int.get(&AttributeValue::Role(role.clone())))
+                .map(|role_int| user_has_role.apply(&[&self.model.u_var, &Int::from_i64(self.model.ctx, *role_int)]).as_bool().unwrap())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let restriction = self.model.a_var._eq(&self.model.action_mapping[&Action::Approve])
+            .implies(&Bool::and(self.model.ctx, &[
+                &Bool::or(self.model.ctx, &awaiting.iter().collect::<Vec<_>>()),
+                &Bool::or(self.model.ctx, &holds_approver_role.iter().collect::<Vec<_>>()),
+            ]));
+        self.restrictions.push(("approve needs a draft or pending resource and an approver role".to_string(), restriction));
+    }
+
+    /// Keeps users out of resources that belong to another tenant: when the user and the
+    /// resource both have a tenant and the tenants differ, only permit rules marked
+    /// `cross_tenant` can grant access. Baseline grants do not cross tenants either.
+    /// Users or resources without a tenant are not restricted.
+    pub fn enforce_tenant_isolation(&mut self) {
+        let Some((Some(Z3Func::Single(user_tenant, user_has_tenant)), Some(Z3Func::Single(resource_tenant, resource_has_tenant)))) = self.model.attr_funcs.get(&AttributeName::Tenant) else {
+            // Only one side has tenants, so nothing can cross
+            return;
+        };
+        let (u, r) = (&self.model.u_var, &self.model.r_var);
+        let crosses = Bool::and(self.model.ctx, &[
+            &user_has_tenant.apply(&[u]).as_bool().unwrap(),
+            &resource_has_tenant.apply(&[r]).as_bool().unwrap(),
+            &user_tenant.apply(&[u])._eq(&resource_tenant.apply(&[r])).not(),
+        ]);
+        let cross_tenant_grants: Vec<Bool> = self.rules.iter()
+            .filter(|rule| rule.cross_tenant)
+            .map(|rule| self.translate_rule(rule))
+            .collect();
+        let restriction = crosses.implies(&Bool::or(self.model.ctx, &cross_tenant_grants.iter().collect::<Vec<_>>()));
+        self.restrictions.push(("tenant isolation".to_string(), restriction));
+    }
+
+    /// Data residency: users only reach resources in their own region, or in a region paired
+    /// with theirs in `allowed_cross_region` as `(user region, resource region)`. Users without
+    /// a region are refused every resource that has one. Resources without a region stay open
+    /// to everyone, unless `block_unregioned` is set.
+    pub fn enforce_geo_residency(&mut self, allowed_cross_region: &[(String, String)], block_unregioned: bool) {
+        let (u, r) = (&self.model.u_var, &self.model.r_var);
+        let (user_func, resource_func) = match self.model.attr_funcs.get(&AttributeName::Region) {
+            Some((user_func, resource_func)) => (user_func.as_ref(), resource_func.as_ref()),
+            None => (None, None),
+        };
+        let resource_has_region = match resource_func {
+            Some(Z3Func::Single(_, has_region)) => has_region.apply(&[r]).as_bool().unwrap(),
+            _ => Bool::from_bool(self.model.ctx, false),
+        };
+        let in_allowed_region = match (user_func, resource_func) {
+            (Some(Z3Func::Single(user_region, user_has_region)), Some(Z3Func::Single(resource_region, _))) => {
+                let user_region = user_region.apply(&[u]).as_int().unwrap();
+                let resource_region = resource_region.apply(&[r]).as_int().unwrap();
+                let region_int = |region: &String| self.model.value_to_int.get(&AttributeValue::String(region.clone()));
+                let mut allowed = vec![user_region._eq(&resource_region)];
+                allowed.extend(allowed_cross_region.iter()
+                    .filter_map(|(from, to)| Some((region_int(from)?, region_int(to)?)))
+                    .map(|(from, to)| Bool::and(self.model.ctx, &[
+                        &user_region._eq(&Int::from_i64(self.model.ctx, *from)),
+                        &resource_region._eq(&Int::from_i64(self.model.ctx, *to)),
+                    ])));
+                Bool::and(self.model.ctx, &[
+                    &user_has_region.apply(&[u]).as_bool().unwrap(),
+                    &Bool::or(self.model.ctx, &allowed.iter().collect::<Vec<_>>()),
+                ])
             }
+            _ => Bool::from_bool(self.model.ctx, false),
+        };
+        let restriction = if block_unregioned {
+            Bool::and(self.model.ctx, &[&resource_has_region, &in_allowed_region])
+        } else {
+            resource_has_region.implies(&in_allowed_region)
+        };
+        self.restrictions.push(("data residency".to_string(), restriction));
+    }
+
+    /// Keeps contractors and temporary staff from viewing or editing confidential resources,
+    /// whatever the rules grant. Permanent staff and users without a contract type are not
+    /// affected, and neither are resources not marked `is_confidential`.
+    pub fn restrict_external_staff_from_confidential(&mut self) {
+        self.user_grounded_restrictions.push(UserGroundedRestriction::ExternalStaffOffConfidential);
+    }
+
+    fn external_staff_restriction(&self) -> Bool<'ctx> {
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let confidential = Condition {
+            left: AttributeExpression::AttributeName(AttributeName::IsConfidential),
+            operator: ComparisonOperator::Equals,
+            right: AttributeExpression::AttributeValue(AttributeValue::Boolean(true)),
+        };
+        let confidential = translate_condition(self.model.ctx, &confidential, &self.model.attr_funcs, &self.model.u_var, &self.model.r_var, &get_int, &AttributeContext::Resource);
+        let external: Vec<Bool> = self.external_staff.iter()
+            .filter_map(|user_id| self.model.user_mapping.get(user_id))
+            .map(|user| self.model.u_var._eq(user))
+            .collect();
+        let guarded = translate_actions_to_z3(self.model.ctx, &[Action::View, Action::Edit].into_iter().collect(), &self.model.a_var, &self.model.action_mapping);
+        Bool::and(self.model.ctx, &[
+            &Bool::or(self.model.ctx, &external.iter().collect::<Vec<_>>()),
+            &confidential,
+            &guarded,
+        ]).not()
+    }
+
+    /// Makes resources whose retention period has elapsed by `query_date` (`YYYY-MM-DD`) archive-only:
+    /// only read-only actions (`view`, `search`, `readMetaInfo`) stay permitted on them.
+    ///
+    /// Retention ends `retention_period_days` after `created_date`. Resources missing either field,
+    /// with a date that does not parse, or whose retention end overflows, are never archived.
+    pub fn archive_after_retention(&mut self, query_date: &str) -> Result<(), String> {
+        let query_day = parse_date(query_date)
+            .ok_or_else(|| format!("Invalid query date: {}. Expected YYYY-MM-DD", query_date))?;
+        let expired: Vec<&String> = self.retention_expiry.iter()
+            .filter(|(_, expiry)| **expiry <= query_day)
+            .map(|(resource_id, _)| resource_id)
+            .collect();
+        let restriction = self.read_only_on(expired);
+        self.restrictions.push((format!("resources past their retention on {} are read-only", query_date), restriction));
+        Ok(())
+    }
+
+    /// Turns the read-only invariant for archived resources on or off; it is on by default.
+    /// While on, resources with `is_archived` set only accept read-only actions (`view`,
+    /// `search`, `readMetaInfo`), whatever the rules grant. Other resources are not affected.
+    pub fn set_archived_read_only(&mut self, enabled: bool) {
+        self.archived_read_only = enabled;
+    }
+
+    /// `r ∈ resources → a is read-only`
+    fn read_only_on<'a>(&self, resources: impl IntoIterator<Item = &'a String>) -> Bool<'ctx> {
+        let listed: Vec<Bool> = resources.into_iter()
+            .map(|resource_id| self.model.r_var._eq(&self.model.resource_mapping[resource_id]))
+            .collect();
+        let read_only: Vec<Bool> = Action::iter()
+            .filter(Action::is_read_only)
+            .map(|action| self.model.a_var._eq(&self.model.action_mapping[&action]))
+            .collect();
+        Bool::or(self.model.ctx, &listed.iter().collect::<Vec<_>>())
+            .implies(&Bool::or(self.model.ctx, &read_only.iter().collect::<Vec<_>>()))
+    }
+
+    /// Gates every action to requests made at `at_hour` (0-23) within the acting user's working hours.
+    /// A window whose end is not after its start, e.g. `22:00-06:00`, wraps past midnight.
+    ///
+    /// Users without `working_hours`, or with a window that does not parse, are never restricted.
+    pub fn restrict_to_working_hours(&mut self, at_hour: u8) -> Result<(), String> {
+        if at_hour > 23 {
+            return Err(format!("Invalid hour: {}. Expected 0-23", at_hour));
+        }
+        self.user_grounded_restrictions.push(UserGroundedRestriction::OnDutyAt(u32::from(at_hour) * 60));
+        Ok(())
+    }
+
+    fn on_duty_restriction(&self, minute: u32) -> Bool<'ctx> {
+        let off_duty: Vec<Bool> = self.working_hours.iter()
+            .filter(|(_, &(start, end))| {
+                let on_duty = if start < end {
+                    start <= minute && minute < end
+                } else {
+                    minute >= start || minute < end
+                };
+                !on_duty
+            })
+            .map(|(user_id, _)| self.model.u_var._eq(&self.model.user_mapping[user_id]))
+            .collect();
+        Bool::or(self.model.ctx, &off_duty.iter().collect::<Vec<_>>()).not()
+    }
+
+    /// Only allows `action` on the latest version of each document, e.g. older versions stay read-only
+    /// when `action` is `edit`.
+    ///
+    /// Versions are compared within a `document_family` as `major.minor.patch` and asserted as
+    /// `resource_version_rank(r)`: 0 for the latest version, -1 for the one before, and so on.
+    /// Resources without a family or with a version that does not parse rank 0, so they are never
+    /// treated as superseded.
+    pub fn require_latest_version(&mut self, action: Action) {
+        let resource_sort = self.model.r_var.get_sort();
+        let version_rank = FuncDecl::new(self.model.ctx, "resource_version_rank", &[&resource_sort], &Sort::int(self.model.ctx));
+        for (resource_id, resource_const) in &self.model.resource_mapping {
+            let rank = self.version_ranks.get(resource_id).copied().unwrap_or(0);
+            self.model.solver.assert(&version_rank.apply(&[resource_const]).as_int().unwrap()._eq(&Int::from_i64(self.model.ctx, rank)));
         }
+        let latest = version_rank.apply(&[&self.model.r_var]).as_int().unwrap().ge(&Int::from_i64(self.model.ctx, 0));
+        let restriction = self.model.a_var._eq(&self.model.action_mapping[&action]).implies(&latest);
+        self.restrictions.push((format!("{} is only allowed on the latest version", action), restriction));
     }
-    println!("Base context with all data axioms is set up.");
 
-    // --- Rule Evaluation Loop ---
-    let u_var = Dynamic::new_const(&ctx, Symbol::String("u".to_string()), &user_sort);
-    let r_var = Dynamic::new_const(&ctx, Symbol::String("r".to_string()), &resource_sort);
+    /// The model from the most recent satisfiable check made by any query method.
+    ///
+    /// Callers can `eval` their own expressions over the attribute functions with it. The model
+    /// borrows the solver's `Context`, so it cannot outlive it. It describes the assertion stack
+    /// at the time of that check: the query's scoped assertions have since been popped, and any
+    /// later query replaces it.
+    pub fn last_model(&self) -> Option<Model<'ctx>> {
+        self.checker.last_model()
+    }
 
-    for rule in &abac_data.rules {
-        println!("--- Evaluating Rule {} ---", rule.id);
-        solver.push();
+    /// Number of base axioms currently asserted on the solver.
+    pub fn constraint_count(&self) -> usize {
+        self.model.solver.get_assertions().len()
+    }
 
-        println!("translating rule to z3 constraints");
-        let rule_constraint = translate_rule_to_z3(&ctx, rule, &attr_funcs, &u_var, &r_var, &get_int);
-        solver.assert(&rule_constraint);
-        println!("translated");
+    /// Number of users in the solver's `User` sort, including ones added with `add_user`.
+    pub fn user_count(&self) -> usize {
+        self.model.user_mapping.len()
+    }
 
-        let mut solutions = Vec::new();
-        println!("start checking");
-        while solver.check() == SatResult::Sat {
-            let model = solver.get_model().unwrap();
-            let found_u = model.eval(&u_var, true).unwrap();
-            let found_r = model.eval(&r_var, true).unwrap();
-            solutions.push((format!("{}", found_u), format!("{}", found_r)));
-            println!("found a solution");
-            let exclusion_constraint = Bool::and(&ctx, &[&u_var._eq(&found_u), &r_var._eq(&found_r)]).not();
-            solver.assert(&exclusion_constraint);
+    /// Enumerates every permitted (user, resource, action) triple across all rules, sorted by
+    /// user, resource and action. A capped run keeps the same triples from run to run; see
+    /// `Enumerator::for_each`.
+    ///
+    /// With `per_action` limits, each action is enumerated independently with `a` fixed,
+    /// up to its own cap (falling back to `max`), so one action cannot use up the whole budget.
+    pub fn solve_access_control(&mut self, max: Option<u64>, per_action: Option<&HashMap<Action, u64>>) -> Vec<EdocumentAccessResult> {
+        let Some(per_action) = per_action else {
+            self.model.solver.push();
+            self.model.solver.assert(&self.user_can_perform_action());
+            let mut results = self.enumerate(max);
+            self.model.solver.pop(1);
+            results.sort();
+            return results;
+        };
+
+        let mut results = Vec::new();
+        for action in Action::iter() {
+            let cap = per_action.get(&action).copied().or(max);
+            self.model.solver.push();
+            self.model.solver.assert(&self.user_can_perform_action());
+            self.model.solver.assert(&self.model.a_var._eq(&self.model.action_mapping[&action]));
+            results.extend(self.enumerate(cap));
+            self.model.solver.pop(1);
         }
-        println!("checked");
+        results.sort();
+        results
+    }
 
-        if solutions.is_empty() {
-            println!("Result: No matching pairs found.");
-        } else {
-            println!("Result: Found {} matching pairs.", solutions.len());
+    /// Like `solve_access_control` without per-action caps, but also returns the run's stats,
+    /// with the time taken to hand over each solution. Resources are searched in id order, so a
+    /// resource's search time falls on its first solution.
+    pub fn solve_access_control_with_stats(&mut self, max: Option<u64>) -> (Vec<EdocumentAccessResult>, SolveStats) {
+        let start = Instant::now();
+        let mut results = Vec::new();
+        let mut durations = Vec::new();
+        let mut last_found = start;
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        Enumerator::new(max, self.verbosity).in_id_order().for_each(&self.model, &mut self.checker, |result| {
+            durations.push(last_found.elapsed());
+            last_found = Instant::now();
+            results.push(result);
+            true
+        });
+        self.model.solver.pop(1);
+        results.sort();
+        let mut stats = SolveStats::for_dataset(&self.dataset, self.constraint_count(), results.len(), start.elapsed());
+        stats.solution_times = SolutionTimes::from_durations(&durations);
+        (results, stats)
+    }
+
+    /// Like `solve_access_control` without per-action caps, but also reports whether `max`
+    /// cut the enumeration short. `None` enumerates everything and is never truncated.
+    pub fn solve_access_control_capped(&mut self, max: Option<u64>) -> CappedResults {
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        let mut results = Enumerator::new(max, self.verbosity).enumerate_capped(&self.model, &mut self.checker);
+        self.model.solver.pop(1);
+        results.results.sort();
+        results
+    }
+
+    /// Sends every permitted triple through `tx` as soon as it is found, for a display thread
+    /// that shows results while enumeration is still running.
+    ///
+    /// Enumeration stops early, without error, once the receiver is dropped. Returns the number
+    /// of triples delivered.
+    pub fn solve_access_control_channel(&mut self, tx: Sender<EdocumentAccessResult>, max: Option<u64>) -> u64 {
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        let sent = Enumerator::new(max, self.verbosity)
+            .for_each(&self.model, &mut self.checker, |result| tx.send(result).is_ok());
+        self.model.solver.pop(1);
+        sent
+    }
+
+    /// Enumerates permitted triples whose resource is one of `resource_ids`.
+    pub fn solve_for_resources(&mut self, resource_ids: &[String], max: Option<u64>) -> Vec<EdocumentAccessResult> {
+        let in_shard: Vec<Bool> = resource_ids.iter()
+            .filter_map(|id| self.model.resource_mapping.get(id))
+            .map(|r_const| self.model.r_var._eq(r_const))
+            .collect();
+
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        self.model.solver.assert(&Bool::or(self.model.ctx, &in_shard.iter().collect::<Vec<_>>()));
+        let results = self.enumerate(max);
+        self.model.solver.pop(1);
+        results
+    }
+
+    /// Enumerates permitted triples restricted to resources that belong to `tenant`, stopping
+    /// at `max` like `solve_access_control_capped`.
+    pub fn solve_tenant_scoped(&mut self, tenant: Tenant, max: Option<u64>) -> CappedResults {
+        let tenant_value = AttributeExpression::AttributeValue(AttributeValue::Tenant(tenant));
+        let resource_has_tenant = self.translate_expr(&AttributeExpression::AttributeName(AttributeName::Tenant), &AttributeContext::Resource, true)
+            .zip(self.translate_expr(&tenant_value, &AttributeContext::Resource, false))
+            .map(|(resource_tenant, tenant_int)| resource_tenant._eq(&tenant_int))
+            .unwrap_or_else(|| Bool::from_bool(self.model.ctx, false));
+
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        self.model.solver.assert(&resource_has_tenant);
+        let mut results = Enumerator::new(max, self.verbosity).enumerate_capped(&self.model, &mut self.checker);
+        self.model.solver.pop(1);
+        results.results.sort();
+        results
+    }
+
+    /// Summarizes which roles can perform which actions on at least one resource.
+    ///
+    /// Rather than enumerating every grant, each (role, action) pair is decided by one
+    /// satisfiability check with the action fixed and the acting user holding the role.
+    pub fn role_capability_matrix(&mut self) -> Result<HashMap<Role, HashSet<Action>>, String> {
+        let mut matrix: HashMap<Role, HashSet<Action>> = HashMap::new();
+        let Some((Some(Z3Func::Set(user_has_role)), _)) = self.model.attr_funcs.get(&AttributeName::Role) else {
+            return Ok(matrix);
+        };
+        let holds_role: Vec<(Role, Bool)> = Role::iter()
+            .filter_map(|role| {
+                let role_int = *self.model.value_to_int.get(&AttributeValue::Role(role.clone()))?;
+                Some((role, user_has_role.apply(&[&self.model.u_var, &Int::from_i64(self.model.ctx, role_int)]).as_bool().unwrap()))
+            })
+            .collect();
+        let grant = self.user_can_perform_action();
+
+        for (role, holds) in holds_role {
+            for action in Action::iter() {
+                self.model.solver.push();
+                self.model.solver.assert(&holds);
+                self.model.solver.assert(&self.model.a_var._eq(&self.model.action_mapping[&action]));
+                self.model.solver.assert(&grant);
+                let result = self.check();
+                self.model.solver.pop(1);
+
+                match result {
+                    SatResult::Sat => { matrix.entry(role.clone()).or_default().insert(action); },
+                    SatResult::Unsat => {},
+                    SatResult::Unknown => return Err(format!("Z3 returned unknown for role {} and action {}", role, action)),
+                }
+            }
         }
-        solver.pop(1);
+        Ok(matrix)
     }
-    Ok(())
-}
 
-// --- Helper Functions ---
+    /// Checks whether `user_id` has any permission at all with a single satisfiability check.
+    /// Returns the first granting (resource, action) as a witness, or `None` if the user has no access.
+    pub fn user_has_any_access(&mut self, user_id: &str) -> Result<Option<(String, Action)>, String> {
+        let u_const = self.model.user_mapping.get(user_id)
+            .ok_or_else(|| format!("Unknown user: {}", user_id))?
+            .clone();
 
-pub enum Z3Func<'a> {
-    Single(FuncDecl<'a>), // Entity -> Int
-    Set(FuncDecl<'a>),    // (Entity, Int) -> Bool
-}
+        self.model.solver.push();
+        self.model.solver.assert(&self.model.u_var._eq(&u_const));
+        self.model.solver.assert(&self.user_can_perform_action());
+        let witness = self.enumerate(Some(1)).pop();
+        self.model.solver.pop(1);
 
-enum AttributeContext {
-    User,
-    Resource,
-    Comparison,
-}
+        Ok(witness.map(|result| (result.resource_id, result.action)))
+    }
+
+    /// Enumerates every (resource, action) pair `user_id` is permitted, with only the user
+    /// variable fixed. Much cheaper than filtering the full enumeration.
+    pub fn enumerate_for_user(&mut self, user_id: &str) -> Result<Vec<EdocumentAccessResult>, String> {
+        let u_const = self.model.user_mapping.get(user_id)
+            .ok_or_else(|| format!("Unknown user: {}", user_id))?
+            .clone();
 
-fn create_value_mappings(data: &EdocumentAbac) -> (HashMap<AttributeValue, i64>, HashMap<i64, AttributeValue>) {
-    let mut value_to_int = HashMap::new();
-    let mut int_to_value = HashMap::new();
-    let mut counter = 0;
+        self.model.solver.push();
+        self.model.solver.assert(&self.model.u_var._eq(&u_const));
+        self.model.solver.assert(&self.user_can_perform_action());
+        let results = self.enumerate(None);
+        self.model.solver.pop(1);
+        Ok(results)
+    }
+
+    /// Checks whether `user_id` may perform `action` on `resource_id` under any rule.
+    pub fn check_access(&mut self, user_id: &str, resource_id: &str, action: Action) -> Result<bool, String> {
+        let query = self.pin_query(user_id, resource_id, &action)?;
+        self.model.solver.push();
+        self.model.solver.assert(&query);
+        self.model.solver.assert(&self.user_can_perform_action());
+        let result = self.check();
+        self.model.solver.pop(1);
+        Ok(result == SatResult::Sat)
+    }
+
+    /// Checks a batch of `(user_id, resource_id, action)` requests in one solver session,
+    /// asserting the policy once and scoping each request with push/pop. Results are in
+    /// request order; a request naming an unknown user or resource gets its own error
+    /// without affecting the others.
+    pub fn check_access_batch(&mut self, requests: &[(String, String, Action)]) -> Vec<Result<bool, String>> {
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        let decisions = requests.iter()
+            .map(|(user_id, resource_id, action)| {
+                let query = self.pin_query(user_id, resource_id, action)?;
+                self.model.solver.push();
+                self.model.solver.assert(&query);
+                let result = self.check();
+                self.model.solver.pop(1);
+                Ok(result == SatResult::Sat)
+            })
+            .collect();
+        self.model.solver.pop(1);
+        decisions
+    }
 
-    let mut insert_val = |val: &AttributeValue| {
-        if !value_to_int.contains_key(val) {
-            value_to_int.insert(val.clone(), counter);
-            int_to_value.insert(counter, val.clone());
-            counter += 1;
+    /// Returns the rules that grant `action` on `resource_id` to `user_id`.
+    /// Every condition of a returned rule is satisfied by the pair.
+    pub fn granting_rules(&mut self, user_id: &str, resource_id: &str, action: Action) -> Result<Vec<EdocumentRule>, String> {
+        let query = self.pin_query(user_id, resource_id, &action)?;
+        let mut granting = Vec::new();
+        for rule in self.rules.clone() {
+            self.model.solver.push();
+            self.model.solver.assert(&query);
+            self.model.solver.assert(&self.translate_rule(&rule));
+            if self.check() == SatResult::Sat {
+                granting.push(rule);
+            }
+            self.model.solver.pop(1);
         }
-    };
+        Ok(granting)
+    }
 
-    for user in &data.users {
-        for attr_name in get_all_attribute_names_enum_variants() {
-            if let Some(val) = user.get_attribute_value(&attr_name) { insert_val(&val); }
-            if let Some(set) = user.get_attribute_set(&attr_name) { set.iter().for_each(|v| insert_val(v)); }
+    /// Explains what takes `action` on `resource_id` away from `user_id` whatever the permit rules
+    /// grant: each deny rule that matches the triple, then each restriction it breaks.
+    pub fn denial_reasons(&mut self, user_id: &str, resource_id: &str, action: Action) -> Result<Vec<String>, String> {
+        let query = self.pin_query(user_id, resource_id, &action)?;
+        let mut blockers: Vec<(String, Bool<'ctx>)> = self.deny_rules.iter()
+            .map(|rule| (format!("deny rule {}: {}", rule.id, rule.description), self.translate_rule(rule)))
+            .collect();
+        blockers.extend(self.described_restrictions().into_iter()
+            .map(|(description, restriction)| (format!("restriction: {}", description), restriction.not())));
+        let mut reasons = Vec::new();
+        for (reason, blocks) in blockers {
+            self.model.solver.push();
+            self.model.solver.assert(&query);
+            self.model.solver.assert(&blocks);
+            if self.check() == SatResult::Sat {
+                reasons.push(reason);
+            }
+            self.model.solver.pop(1);
         }
+        Ok(reasons)
+    }
+
+    /// u = user_id ∧ r = resource_id ∧ a = action
+    fn pin_query(&self, user_id: &str, resource_id: &str, action: &Action) -> Result<Bool<'ctx>, String> {
+        let u_const = self.model.user_mapping.get(user_id)
+            .ok_or_else(|| format!("Unknown user: {}", user_id))?;
+        let r_const = self.model.resource_mapping.get(resource_id)
+            .ok_or_else(|| format!("Unknown resource: {}", resource_id))?;
+        Ok(Bool::and(self.model.ctx, &[
+            &self.model.u_var._eq(u_const),
+            &self.model.r_var._eq(r_const),
+            &self.model.a_var._eq(&self.model.action_mapping[action]),
+        ]))
     }
-    for resource in &data.resources {
-        for attr_name in get_all_attribute_names_enum_variants() {
-            if let Some(val) = resource.get_attribute_value(&attr_name) { insert_val(&val); }
-            if let Some(set) = resource.get_attribute_set(&attr_name) { set.iter().for_each(|v| insert_val(v)); }
+
+    /// Lists who can do what on one resource, grouping the permitted users by action.
+    pub fn resource_access_report(&mut self, resource_id: &str) -> Result<HashMap<Action, Vec<String>>, String> {
+        let r_const = self.model.resource_mapping.get(resource_id)
+            .ok_or_else(|| format!("Unknown resource: {}", resource_id))?
+            .clone();
+
+        self.model.solver.push();
+        self.model.solver.assert(&self.user_can_perform_action());
+        self.model.solver.assert(&self.model.r_var._eq(&r_const));
+        let results = self.enumerate(None);
+        self.model.solver.pop(1);
+
+        let mut report: HashMap<Action, Vec<String>> = HashMap::new();
+        for result in results {
+            report.entry(result.action).or_default().push(result.user_id);
         }
+        for users in report.values_mut() {
+            users.sort();
+        }
+        Ok(report)
     }
-    for rule in &data.rules {
-        for cond in rule.user_conditions.iter().chain(rule.resource_conditions.iter()).chain(rule.comparison_conditions.iter()) {
-            if let AttributeExpression::AttributeValue(val) = &cond.right { insert_val(val); }
-            if let AttributeExpression::ValueSet(vals) = &cond.right { vals.iter().for_each(|v| insert_val(v)); }
+
+    /// Enumerates the triples granted by a single rule.
+    pub fn solve_rule(&mut self, rule: &EdocumentRule, max: Option<u64>) -> Vec<EdocumentAccessResult> {
+        self.model.solver.push();
+        self.model.solver.assert(&self.translate_rule(rule));
+        for constraint in self.policy_constraints() {
+            self.model.solver.assert(&constraint);
         }
+        let results = self.enumerate(max);
+        self.model.solver.pop(1);
+        results
     }
-    (value_to_int, int_to_value)
+
+    /// Enumerates the triples granted by the baseline grants, such as `grant_public_read`,
+    /// rather than by a rule. Deny rules and restrictions apply as in `solve_rule`.
+    pub fn solve_baseline_grants(&mut self, max: Option<u64>) -> Vec<EdocumentAccessResult> {
+        self.model.solver.push();
+        self.model.solver.assert(&Bool::or(self.model.ctx, &self.baseline_grants.iter().collect::<Vec<_>>()));
+        for constraint in self.policy_constraints() {
+            self.model.solver.assert(&constraint);
+        }
+        let results = self.enumerate(max);
+        self.model.solver.pop(1);
+        results
+    }
+
+    /// Solves each permit rule in turn, then the baseline grants under their own heading, printing
+    /// the count of each at Normal verbosity. Returns the total, which counts a triple once for
+    /// every rule or grant that permits it.
+    pub fn solve_each_rule(&mut self) -> usize {
+        let normal = self.verbosity >= Verbosity::Normal;
+        let report = |solutions: &[EdocumentAccessResult]| {
+            if !normal {
+                return;
+            }
+            if solutions.is_empty() {
+                println!("Result: No matching triples found.");
+            } else {
+                println!("Result: Found {} matching (user, resource, action) triples.", solutions.len());
+            }
+        };
+
+        let mut solutions_found = 0;
+        for rule in self.rules.clone() {
+            if normal { println!("--- Evaluating Rule {} ---", rule.id); }
+            let solutions = self.solve_rule(&rule, None);
+            report(&solutions);
+            solutions_found += solutions.len();
+        }
+        if !self.baseline_grants.is_empty() {
+            if normal { println!("--- Evaluating Baseline Grants ---"); }
+            let solutions = self.solve_baseline_grants(None);
+            report(&solutions);
+            solutions_found += solutions.len();
+        }
+        solutions_found
+    }
+
+    /// Ids of the rules, permit or deny, that no (user, resource, action) in the dataset
+    /// satisfies, checked one rule at a time in its own push/pop scope. Other deny rules and
+    /// restrictions are not applied, so a rule is reported only when its own conditions and
+    /// actions can never match the data.
+    pub fn find_unreachable_rules(&self) -> Vec<usize> {
+        self.rules.iter().chain(&self.deny_rules)
+            .filter(|rule| {
+                self.model.solver.push();
+                self.model.solver.assert(&self.translate_rule(rule));
+                let result = self.model.check();
+                self.model.solver.pop(1);
+                result == SatResult::Unsat
+            })
+            .map(|rule| rule.id)
+            .collect()
+    }
+
+    /// Triples that some permit rule grants and some deny rule takes away again, i.e. the
+    /// places where the deny rules actually override the permits. Restrictions are ignored.
+    pub fn find_conflicts(&mut self) -> Vec<EdocumentAccessResult> {
+        let permits: Vec<Bool> = self.rules.iter().map(|rule| self.translate_rule(rule)).collect();
+        let denials: Vec<Bool> = self.deny_rules.iter().map(|rule| self.translate_rule(rule)).collect();
+        self.model.solver.push();
+        self.model.solver.assert(&Bool::or(self.model.ctx, &permits.iter().collect::<Vec<_>>()));
+        self.model.solver.assert(&Bool::or(self.model.ctx, &denials.iter().collect::<Vec<_>>()));
+        let results = self.enumerate(None);
+        self.model.solver.pop(1);
+        results
+    }
+
+    /// Checks whether `user_id` could perform `action` on a resource that is not in the dataset yet.
+    ///
+    /// The Resource sort is closed, so the hypothetical resource is modeled through a fresh set of
+    /// resource attribute functions that are only constrained at `r`. The base axioms are untouched.
+    pub fn check_hypothetical(&mut self, resource: &EdocumentResourceAttribute, user_id: &str, action: Action) -> Result<bool, String> {
+        let u_const = self.model.user_mapping.get(user_id)
+            .ok_or_else(|| format!("Unknown user: {}", user_id))?;
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let set_attributes = get_set_attribute_names();
+        let resource_sort = self.model.r_var.get_sort();
+        let int_sort = Sort::int(self.model.ctx);
+        let bool_sort = Sort::bool(self.model.ctx);
+
+        let mut hypothetical_axioms = Vec::new();
+        let mut hypothetical_funcs = HashMap::new();
+        for (attr_name, (user_func, _)) in &self.model.attr_funcs {
+            let resource_func = if set_attributes.contains(attr_name) {
+                resource.get_attribute_set(attr_name).map(|values| {
+                    let func = FuncDecl::new(self.model.ctx, format!("hypothetical_resource_has_{}", attr_name), &[&resource_sort, &int_sort], &bool_sort);
+                    let value_ints: HashSet<i64> = values.iter().map(&get_int).collect();
+                    for val_int in self.model.value_to_int.values() {
+                        let has_val = func.apply(&[&self.model.r_var, &Int::from_i64(self.model.ctx, *val_int)]).as_bool().unwrap();
+                        hypothetical_axioms.push(if value_ints.contains(val_int) { has_val } else { has_val.not() });
+                    }
+                    Z3Func::Set(func)
+                })
+            } else {
+                resource.get_attribute_value(attr_name).map(|val| {
+                    let func = FuncDecl::new(self.model.ctx, format!("hypothetical_resource_{}", attr_name), &[&resource_sort], &int_sort);
+                    let has_func = FuncDecl::new(self.model.ctx, format!("hypothetical_resource_defines_{}", attr_name), &[&resource_sort], &bool_sort);
+                    hypothetical_axioms.push(func.apply(&[&self.model.r_var]).as_int().unwrap()._eq(&Int::from_i64(self.model.ctx, encode_value(&val, &get_int))));
+                    hypothetical_axioms.push(has_func.apply(&[&self.model.r_var]).as_bool().unwrap());
+                    Z3Func::Single(func, has_func)
+                })
+            };
+            hypothetical_funcs.insert(attr_name.clone(), (user_func.clone(), resource_func));
+        }
+
+        let grants: Vec<Bool> = self.rules.iter()
+            .map(|rule| self.translate_rule_with(rule, &hypothetical_funcs))
+            .collect();
+
+        self.model.solver.push();
+        for axiom in &hypothetical_axioms {
+            self.model.solver.assert(axiom);
+        }
+        self.model.solver.assert(&self.model.u_var._eq(u_const));
+        self.model.solver.assert(&self.model.a_var._eq(&self.model.action_mapping[&action]));
+        self.model.solver.assert(&Bool::or(self.model.ctx, &grants.iter().collect::<Vec<_>>()));
+        let result = self.check();
+        self.model.solver.pop(1);
+
+        match result {
+            SatResult::Sat => Ok(true),
+            SatResult::Unsat => Ok(false),
+            SatResult::Unknown => Err("Z3 returned unknown for the hypothetical resource".to_string()),
+        }
+    }
+
+    /// Checks whether some user matching `profile` could perform `action` on `resource_id`.
+    ///
+    /// Like `check_hypothetical`, the abstract user gets a fresh set of user attribute functions,
+    /// so no existing user's attributes leak in. Only the attributes in `profile` are constrained;
+    /// the rest are free, so the answer is "some user with this profile can". Permit rules, deny
+    /// rules and baseline grants are evaluated against the profile, and restrictions still apply.
+    pub fn check_abstract(&mut self, profile: &PartialUserAttributes, resource_id: &str, action: Action) -> Result<bool, String> {
+        let r_const = self.model.resource_mapping.get(resource_id)
+            .ok_or_else(|| format!("Unknown resource: {}", resource_id))?;
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let user_sort = self.model.u_var.get_sort();
+        let int_sort = Sort::int(self.model.ctx);
+        let bool_sort = Sort::bool(self.model.ctx);
+
+        let mut abstract_axioms = Vec::new();
+        let mut abstract_funcs = HashMap::new();
+        for (attr_name, (user_func, resource_func)) in &self.model.attr_funcs {
+            let user_func = user_func.as_ref().map(|func| match func {
+                Z3Func::Set(_) => {
+                    let func = FuncDecl::new(self.model.ctx, format!("abstract_user_has_{}", attr_name), &[&user_sort, &int_sort], &bool_sort);
+                    if let Some(values) = profile.sets.get(attr_name) {
+                        let value_ints: HashSet<i64> = values.iter().map(get_int).collect();
+                        for val_int in self.model.value_to_int.values() {
+                            let has_val = func.apply(&[&self.model.u_var, &Int::from_i64(self.model.ctx, *val_int)]).as_bool().unwrap();
+                            abstract_axioms.push(if value_ints.contains(val_int) { has_val } else { has_val.not() });
+                        }
+                    } else if let Some(val) = profile.values.get(attr_name) {
+                        // A single value for a set attribute (e.g. one role) is one the user holds among possibly others
+                        abstract_axioms.push(func.apply(&[&self.model.u_var, &Int::from_i64(self.model.ctx, get_int(val))]).as_bool().unwrap());
+                    }
+                    Z3Func::Set(func)
+                },
+                Z3Func::Single(..) => {
+                    let func = FuncDecl::new(self.model.ctx, format!("abstract_user_{}", attr_name), &[&user_sort], &int_sort);
+                    let has_func = FuncDecl::new(self.model.ctx, format!("abstract_user_defines_{}", attr_name), &[&user_sort], &bool_sort);
+                    if let Some(val) = profile.values.get(attr_name) {
+                        abstract_axioms.push(func.apply(&[&self.model.u_var]).as_int().unwrap()._eq(&Int::from_i64(self.model.ctx, encode_value(val, &get_int))));
+                        abstract_axioms.push(has_func.apply(&[&self.model.u_var]).as_bool().unwrap());
+                    }
+                    Z3Func::Single(func, has_func)
+                },
+            });
+            abstract_funcs.insert(attr_name.clone(), (user_func, resource_func.clone()));
+        }
+
+        let mut grants: Vec<Bool> = self.rules.iter()
+            .map(|rule| self.translate_rule_with(rule, &abstract_funcs))
+            .collect();
+        grants.extend(self.baseline_grants.iter().cloned());
+
+        self.model.solver.push();
+        for axiom in &abstract_axioms {
+            self.model.solver.assert(axiom);
+        }
+        self.model.solver.assert(&self.model.r_var._eq(r_const));
+        self.model.solver.assert(&self.model.a_var._eq(&self.model.action_mapping[&action]));
+        self.model.solver.assert(&Bool::or(self.model.ctx, &grants.iter().collect::<Vec<_>>()));
+        for constraint in self.policy_constraints_with(&abstract_funcs) {
+            self.model.solver.assert(&constraint);
+        }
+        let result = self.check();
+        self.model.solver.pop(1);
+
+        match result {
+            SatResult::Sat => Ok(true),
+            SatResult::Unsat => Ok(false),
+            SatResult::Unknown => Err("Z3 returned unknown for the abstract user".to_string()),
+        }
+    }
+
+    /// user_can_perform_action(u, r, a): the base policy allows the triple (under DenyAll,
+    /// some permit rule matches (u, r) and grants a), no deny rule matches it, and every
+    /// registered restriction holds.
+    fn user_can_perform_action(&self) -> Bool<'ctx> {
+        let granted = match self.base_policy {
+            BasePolicy::DenyAll => {
+                let mut grants: Vec<Bool> = self.rules.iter().map(|rule| self.translate_rule(rule)).collect();
+                grants.extend(self.baseline_grants.iter().cloned());
+                grants.extend(self.user_grounded_grants.iter().map(|grant| match grant {
+                    UserGroundedGrant::Delegated(actions, max_depth) => self.delegated_grant(actions, *max_depth),
+                    UserGroundedGrant::SupervisorChain(rule, max_levels) => self.supervisor_chain_grant(rule, *max_levels),
+                }));
+                Bool::or(self.model.ctx, &grants.iter().collect::<Vec<_>>())
+            }
+            BasePolicy::AllowAll => Bool::from_bool(self.model.ctx, true),
+        };
+        let policy_constraints = self.policy_constraints();
+        let mut conjuncts = vec![&granted];
+        conjuncts.extend(policy_constraints.iter());
+        Bool::and(self.model.ctx, &conjuncts)
+    }
+
+    /// Constraints every grant must satisfy regardless of which permit rule produced it:
+    /// no deny rule matches, every restriction holds, and archived resources stay read-only
+    /// unless that was turned off.
+    fn policy_constraints(&self) -> Vec<Bool<'ctx>> {
+        self.policy_constraints_with(&self.model.attr_funcs)
+    }
+
+    /// `policy_constraints` with the deny rules read through `attr_funcs`
+    fn policy_constraints_with(&self, attr_funcs: &HashMap<AttributeName, (Option<Z3Func<'ctx>>, Option<Z3Func<'ctx>>)>) -> Vec<Bool<'ctx>> {
+        let denials: Vec<Bool> = self.deny_rules.iter().map(|rule| self.translate_rule_with(rule, attr_funcs)).collect();
+        let mut constraints = vec![Bool::or(self.model.ctx, &denials.iter().collect::<Vec<_>>()).not()];
+        constraints.extend(self.described_restrictions().into_iter().map(|(_, restriction)| restriction));
+        constraints
+    }
+
+    /// Every restriction in force, including the ones grounded per query, with its description
+    fn described_restrictions(&self) -> Vec<(String, Bool<'ctx>)> {
+        let mut restrictions = self.restrictions.clone();
+        restrictions.extend(self.user_grounded_restrictions.iter().map(|restriction| match restriction {
+            UserGroundedRestriction::OnDutyAt(minute) => (
+                format!("users off duty at {:02}:{:02} are denied everything", minute / 60, minute % 60),
+                self.on_duty_restriction(*minute),
+            ),
+            UserGroundedRestriction::ExternalStaffOffConfidential => (
+                "external staff are kept off confidential resources".to_string(),
+                self.external_staff_restriction(),
+            ),
+        }));
+        if self.archived_read_only && !self.archived_resources.is_empty() {
+            restrictions.push(("archived resources are read-only".to_string(), self.read_only_on(&self.archived_resources)));
+        }
+        restrictions
+    }
+
+    /// A rule's conditions together with its actions closed under the hierarchy: granting view
+    /// also grants readMetaInfo, while denying view also denies the actions that imply it.
+    fn translate_rule(&self, rule: &EdocumentRule) -> Bool<'ctx> {
+        self.translate_rule_with(rule, &self.model.attr_funcs)
+    }
+
+    fn translate_rule_with(
+        &self,
+        rule: &EdocumentRule,
+        attr_funcs: &HashMap<AttributeName, (Option<Z3Func<'ctx>>, Option<Z3Func<'ctx>>)>,
+    ) -> Bool<'ctx> {
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        let conditions = translate_rule_to_z3(self.model.ctx, rule, attr_funcs, &self.model.u_var, &self.model.r_var, &get_int);
+        let affected_actions = rule.affected_actions(&self.action_hierarchy);
+        let actions = translate_actions_to_z3(self.model.ctx, &affected_actions, &self.model.a_var, &self.model.action_mapping);
+        Bool::and(self.model.ctx, &[&conditions, &actions])
+    }
+
+    fn translate_expr(&self, expr: &AttributeExpression, context: &AttributeContext, is_left: bool) -> Option<Int<'ctx>> {
+        let get_int = |val: &AttributeValue| -> i64 { *self.model.value_to_int.get(val).unwrap_or(&-1) };
+        translate_expr_to_int(self.model.ctx, expr, &self.model.attr_funcs, &self.model.u_var, &self.model.r_var, &get_int, context, is_left)
+    }
+
+    fn check(&mut self) -> SatResult {
+        self.checker.check(&self.model)
+    }
+
+    fn enumerate(&mut self, max: Option<u64>) -> Vec<EdocumentAccessResult> {
+        Enumerator::new(max, self.verbosity).enumerate(&self.model, &mut self.checker)
+    }
+}
+
+/// Grants worked out from per-user data, grounded when a query is built
+enum UserGroundedGrant {
+    /// `grant_delegated_access(actions, max_depth)`
+    Delegated(HashSet<Action>, usize),
+    /// `add_supervisor_chain_rule(rule, max_levels)`
+    SupervisorChain(EdocumentRule, usize),
+}
+
+/// Restrictions worked out from per-user data, grounded when a query is built
+enum UserGroundedRestriction {
+    /// `restrict_to_working_hours`, as minutes after midnight
+    OnDutyAt(u32),
+    /// `restrict_external_staff_from_confidential`
+    ExternalStaffOffConfidential,
+}
+
+// --- Helper Functions ---
+
+#[derive(Clone)]
+pub enum Z3Func<'a> {
+    Single(FuncDecl<'a>, FuncDecl<'a>), // Entity -> Int, and Entity -> Bool for "has a value"
+    Set(FuncDecl<'a>),    // (Entity, Int) -> Bool
+}
+
+enum AttributeContext {
+    User,
+    Resource,
+    Comparison,
 }
 
-fn get_all_attribute_names_enum_variants() -> HashSet<AttributeName> {
-    use crate::types::edocument_types::AttributeName::*;
+pub(super) fn get_set_attribute_names() -> HashSet<AttributeName> {
     [
-        Role, Position, Tenant, Department, Office, Registered, Projects,
-        Supervisor, Supervisee, PayrollingPermissions, ClearanceLevel,
-        SecurityLevel, Type, Owner, Recipients, IsConfidential,
-        ContainsPersonalInfo, Uid, Rid,
+        AttributeName::Role,
+        AttributeName::Projects,
+        AttributeName::Supervisee,
+        AttributeName::DelegatedAuthority,
+        AttributeName::Certifications,
+        AttributeName::PayrollingPermissions,
+        AttributeName::Recipients,
     ].iter().cloned().collect()
 }
 
+/// `actions` sorted and comma-separated, for descriptions
+fn action_list(actions: &HashSet<Action>) -> String {
+    let mut names: Vec<String> = actions.iter().map(ToString::to_string).collect();
+    names.sort();
+    names.join(", ")
+}
+
+fn translate_actions_to_z3<'a>(
+    ctx: &'a Context,
+    actions: &HashSet<Action>,
+    a_var: &Dynamic<'a>,
+    action_consts: &HashMap<Action, Dynamic<'a>>,
+) -> Bool<'a> {
+    let clauses: Vec<Bool> = actions.iter()
+        .map(|action| a_var._eq(&action_consts[action]))
+        .collect();
+    Bool::or(ctx, &clauses.iter().collect::<Vec<_>>())
+}
+
 fn translate_rule_to_z3<'a>(
     ctx: &'a Context,
     rule: &EdocumentRule,
@@ -415,9 +1811,51 @@ fn translate_condition<'a>(
 ) -> Bool<'a> {
     use crate::types::types::ComparisonOperator::*;
 
+    // `attr = {a b}` is read as "attr is one of a, b", the same as `attr [ {a b}`
+    let equals_one_of = condition.operator == Equals && matches!(condition.right, AttributeExpression::ValueSet(_));
+
+    // `set_attr ] {a b}`: every listed value is a member of the set attribute.
+    // An empty list is false, like every other condition over an empty value set
+    if condition.operator == Contains {
+        let listed: Option<Vec<AttributeValue>> = match &condition.right {
+            AttributeExpression::ValueSet(values) => Some(values.clone()),
+            AttributeExpression::AttributeValue(AttributeValue::StringSet(values)) => {
+                Some(values.iter().map(|v| AttributeValue::String(v.clone())).collect())
+            },
+            _ => None,
+        };
+        if let (Some(listed), AttributeExpression::AttributeName(name)) = (listed, &condition.left) {
+            let Some((set_func, entity_var)) = set_attribute_func(name, attr_funcs, context, u_var, r_var).filter(|_| !listed.is_empty()) else {
+                return Bool::from_bool(ctx, false);
+            };
+            let members: Vec<Bool> = listed.iter()
+                .map(|v| set_func.apply(&[entity_var, &Int::from_i64(ctx, get_int(v))]).as_bool().unwrap())
+                .collect();
+            return Bool::and(ctx, &members.iter().collect::<Vec<_>>());
+        }
+    }
+
+    // A set attribute on the left of `=` or `[` holds when any of its values does, so
+    // `role = employee` and `role [ {employee manager}` match a user holding several roles
+    if matches!(condition.operator, Equals | ContainedIn) {
+        if let AttributeExpression::AttributeName(name) = &condition.left {
+            let listed = match &condition.right {
+                AttributeExpression::AttributeValue(value) => Some(std::slice::from_ref(value)),
+                AttributeExpression::ValueSet(values) => Some(values.as_slice()),
+                AttributeExpression::AttributeName(_) => None,
+            };
+            if let (Some(listed), Some((set_func, entity_var))) = (listed, set_attribute_func(name, attr_funcs, context, u_var, r_var)) {
+                let members: Vec<Bool> = listed.iter()
+                    .map(|v| set_func.apply(&[entity_var, &Int::from_i64(ctx, get_int(v))]).as_bool().unwrap())
+                    .collect();
+                return Bool::or(ctx, &members.iter().collect::<Vec<_>>());
+            }
+        }
+    }
+
     match condition.operator {
-        Contains | ContainedIn => {
-            let (scalar_expr, set_expr) = if condition.operator == ContainedIn {
+        Contains | ContainedIn | Equals if condition.operator != Equals || equals_one_of => {
+            let (scalar_expr, set_expr) = if condition.operator != Contains {
                 (&condition.left, &condition.right)
             } else {
                 (&condition.right, &condition.left)
@@ -428,47 +1866,57 @@ fn translate_condition<'a>(
                 None => return Bool::from_bool(ctx, false),
             };
 
-            match set_expr {
+            let member = match set_expr {
                 AttributeExpression::AttributeName(name) => {
-                    let (user_func_opt, resource_func_opt) = attr_funcs.get(name).unwrap();
-                    let z3_func_opt = match context {
-                        AttributeContext::User => user_func_opt.as_ref(),
-                        AttributeContext::Resource => resource_func_opt.as_ref(),
-                        AttributeContext::Comparison => user_func_opt.as_ref().or(resource_func_opt.as_ref()),
-                    };
-                    if let Some(Z3Func::Set(set_func)) = z3_func_opt {
-                        let entity_var = match context {
-                            AttributeContext::User => u_var,
-                            AttributeContext::Resource => r_var,
-                            AttributeContext::Comparison => if user_func_opt.is_some() { u_var } else { r_var },
-                        };
-                        set_func.apply(&[entity_var, &scalar_z3]).as_bool().unwrap()
-                    } else {
-                        Bool::from_bool(ctx, false)
+                    match set_attribute_func(name, attr_funcs, context, u_var, r_var) {
+                        Some((set_func, entity_var)) => set_func.apply(&[entity_var, &scalar_z3]).as_bool().unwrap(),
+                        None => Bool::from_bool(ctx, false),
                     }
                 },
                 AttributeExpression::ValueSet(values) => {
                     let or_clauses: Vec<Bool> = values.iter()
-                        .map(|v| scalar_z3._eq(&Int::from_i64(ctx, get_int(v))))
+                        .map(|v| scalar_z3._eq(&Int::from_i64(ctx, encode_value(v, get_int))))
                         .collect();
                     Bool::or(ctx, &or_clauses.iter().collect::<Vec<_>>())
                 },
                 _ => Bool::from_bool(ctx, false),
+            };
+            // As with the comparisons below, an entity without a value for the scalar
+            // attribute is in no set, whatever its unconstrained value function returns
+            match scalar_expr {
+                AttributeExpression::AttributeName(name) => match single_attribute_func(name, attr_funcs, context, u_var, r_var, true) {
+                    Some((_, has_func, entity_var)) => Bool::and(ctx, &[&has_func.apply(&[entity_var]).as_bool().unwrap(), &member]),
+                    None => member,
+                },
+                _ => member,
             }
         },
         _ => {
             let left = translate_expr_to_int(ctx, &condition.left, attr_funcs, u_var, r_var, get_int, context, true);
             let right = translate_expr_to_int(ctx, &condition.right, attr_funcs, u_var, r_var, get_int, context, false);
 
+            // Boundaries: `>` and `<` exclude the threshold, `>=` and `<=` include it.
+            // Thresholds are signed and encoded literally, so `>= 0` and `< 0` are kept
+            // as real constraints rather than being simplified away.
             if let (Some(left), Some(right)) = (left, right) {
-                match condition.operator {
+                let compared = match condition.operator {
                     Equals => left._eq(&right),
                     GreaterThan => left.gt(&right),
                     LessThan => left.lt(&right),
                     GreaterThanOrEqual => left.ge(&right),
                     LessThanOrEqual => left.le(&right),
                     _ => Bool::from_bool(ctx, false),
-                }
+                };
+                // An entity without a value for a compared attribute never satisfies the
+                // comparison, whatever its unconstrained value function returns
+                let defined: Vec<Bool> = [(&condition.left, true), (&condition.right, false)].into_iter()
+                    .filter_map(|(expr, is_left)| match expr {
+                        AttributeExpression::AttributeName(name) => single_attribute_func(name, attr_funcs, context, u_var, r_var, is_left),
+                        _ => None,
+                    })
+                    .map(|(_, has_func, entity_var)| has_func.apply(&[entity_var]).as_bool().unwrap())
+                    .collect();
+                Bool::and(ctx, &defined.iter().chain([&compared]).collect::<Vec<_>>())
             } else {
                 Bool::from_bool(ctx, false)
             }
@@ -476,6 +1924,77 @@ fn translate_condition<'a>(
     }
 }
 
+/// The membership function of set attribute `name` in `context`, with the variable it applies to
+fn set_attribute_func<'a, 'b>(
+    name: &AttributeName,
+    attr_funcs: &'b HashMap<AttributeName, (Option<Z3Func<'a>>, Option<Z3Func<'a>>)>,
+    context: &AttributeContext,
+    u_var: &'b Dynamic<'a>,
+    r_var: &'b Dynamic<'a>,
+) -> Option<(&'b FuncDecl<'a>, &'b Dynamic<'a>)> {
+    let (user_func_opt, resource_func_opt) = attr_funcs.get(name)?;
+    let z3_func_opt = match context {
+        AttributeContext::User => user_func_opt.as_ref(),
+        AttributeContext::Resource => resource_func_opt.as_ref(),
+        AttributeContext::Comparison => user_func_opt.as_ref().or(resource_func_opt.as_ref()),
+    };
+    let Some(Z3Func::Set(set_func)) = z3_func_opt else {
+        return None;
+    };
+    let entity_var = match context {
+        AttributeContext::User => u_var,
+        AttributeContext::Resource => r_var,
+        AttributeContext::Comparison => if user_func_opt.is_some() { u_var } else { r_var },
+    };
+    Some((set_func, entity_var))
+}
+
+/// The value and "has a value" functions of single-valued attribute `name` in `context`,
+/// with the variable they apply to
+fn single_attribute_func<'a, 'b>(
+    name: &AttributeName,
+    attr_funcs: &'b HashMap<AttributeName, (Option<Z3Func<'a>>, Option<Z3Func<'a>>)>,
+    context: &AttributeContext,
+    u_var: &'b Dynamic<'a>,
+    r_var: &'b Dynamic<'a>,
+    is_left: bool,
+) -> Option<(&'b FuncDecl<'a>, &'b FuncDecl<'a>, &'b Dynamic<'a>)> {
+    let (user_func_opt, resource_func_opt) = attr_funcs.get(name)?;
+    let user_side = (user_func_opt.as_ref(), u_var);
+    let resource_side = (resource_func_opt.as_ref(), r_var);
+
+    // The entity variable always follows the function that was picked, so `uid`/`rid`
+    // resolve to the requesting user/resource on either side: `owner = uid` becomes
+    // resource_owner(r) = user_uid(u), and ids share one value mapping with owner,
+    // recipients, projects, etc.
+    let (z3_func_opt, entity_var) = match context {
+        AttributeContext::User => user_side,
+        AttributeContext::Resource => resource_side,
+        AttributeContext::Comparison => {
+            // If it's a comparison, we need to decide whether to use the user or resource function.
+            // A simple heuristic: if a user function exists, use it for the left side of the comparison.
+            // If a resource function exists, use it for the right side.
+            // This handles cases like `user.department == resource.department`.
+            let (first, second) = if is_left { (user_side, resource_side) } else { (resource_side, user_side) };
+            if first.0.is_some() { first } else { second }
+        }
+    };
+
+    match z3_func_opt {
+        Some(Z3Func::Single(func, has_func)) => Some((func, has_func, entity_var)),
+        _ => None,
+    }
+}
+
+/// The integer a single value is encoded as. Integers are encoded as themselves so that
+/// `>`/`<` order them numerically; every other value uses its mapped id.
+pub(super) fn encode_value(val: &AttributeValue, get_int: &impl Fn(&AttributeValue) -> i64) -> i64 {
+    match val {
+        AttributeValue::Integer(i) => *i,
+        _ => get_int(val),
+    }
+}
+
 fn translate_expr_to_int<'a>(
     ctx: &'a Context,
     expr: &AttributeExpression,
@@ -488,42 +2007,10 @@ fn translate_expr_to_int<'a>(
 ) -> Option<Int<'a>> {
     match expr {
         AttributeExpression::AttributeName(name) => {
-            let (user_func_opt, resource_func_opt) = attr_funcs.get(name)?;
-            
-            let z3_func_opt = match context {
-                AttributeContext::User => user_func_opt.as_ref(),
-                AttributeContext::Resource => resource_func_opt.as_ref(),
-                AttributeContext::Comparison => {
-                    // If it's a comparison, we need to decide whether to use the user or resource function.
-                    // A simple heuristic: if a user function exists, use it for the left side of the comparison.
-                    // If a resource function exists, use it for the right side.
-                    // This handles cases like `user.department == resource.department`.
-                    if is_left {
-                        user_func_opt.as_ref().or(resource_func_opt.as_ref())
-                    } else {
-                        resource_func_opt.as_ref().or(user_func_opt.as_ref())
-                    }
-                }
-            };
-
-            if let Some(Z3Func::Single(func)) = z3_func_opt {
-                let entity_var = if user_func_opt.is_some() && (matches!(context, AttributeContext::User) || (matches!(context, AttributeContext::Comparison) && is_left)) {
-                    u_var
-                } else {
-                    r_var
-                };
-                Some(func.apply(&[entity_var]).as_int().unwrap())
-            } else {
-                None
-            }
-        }
-        AttributeExpression::AttributeValue(val) => {
-            if let AttributeValue::Integer(i) = val {
-                Some(Int::from_i64(ctx, *i as i64))
-            } else {
-                Some(Int::from_i64(ctx, get_int(val)))
-            }
+            single_attribute_func(name, attr_funcs, context, u_var, r_var, is_left)
+                .map(|(func, _, entity_var)| func.apply(&[entity_var]).as_int().unwrap())
         }
+        AttributeExpression::AttributeValue(val) => Some(Int::from_i64(ctx, encode_value(val, get_int))),
         AttributeExpression::ValueSet(_) => None,
     }
 }
\ No newline at end of file
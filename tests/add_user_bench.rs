@@ -0,0 +1,46 @@
+//! Compares adding users one at a time to a running solver with rebuilding the solver for
+//! each of them. Run with `cargo test --release --test add_user_bench -- --ignored --nocapture`.
+
+use std::time::Instant;
+
+use abac_solver::edocument::{ActionHierarchy, EdocumentAbacData, EdocumentAbacSolver, EdocumentUserAttribute};
+use z3::{Config, Context};
+
+fn user(i: usize) -> serde_json::Value {
+    serde_json::json!({"user_id": format!("user{}", i), "role": "Employee", "projects": [format!("project{}", i % 10)], "supervisee": []})
+}
+
+#[test]
+#[ignore]
+fn adding_a_user_is_cheaper_than_rebuilding() {
+    let (initial, added) = (500, 20);
+    let resources: Vec<serde_json::Value> = (0..500)
+        .map(|i| serde_json::json!({"resource_id": format!("doc{}", i), "resource_type": "Invoice", "owner": format!("user{}", i), "recipients": []}))
+        .collect();
+    let rule = serde_json::json!({"id": 1, "description": "Employees can view every document",
+        "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}}],
+        "resource_conditions": [], "actions": ["View"], "comparison_conditions": []});
+    let dataset = |users: usize| -> EdocumentAbacData {
+        serde_json::from_value(serde_json::json!({"users": (0..users).map(user).collect::<Vec<_>>(), "resources": resources, "rules": [rule]})).unwrap()
+    };
+    let ctx = Context::new(&Config::new());
+
+    let start = Instant::now();
+    for users in initial..initial + added {
+        let mut solver = EdocumentAbacSolver::new(&ctx, &dataset(users + 1), &ActionHierarchy::new());
+        assert!(solver.user_has_any_access(&format!("user{}", users)).unwrap().is_some());
+    }
+    let rebuild = start.elapsed() / added as u32;
+
+    let mut solver = EdocumentAbacSolver::with_spare_users(&ctx, &dataset(initial), &ActionHierarchy::new(), added);
+    let start = Instant::now();
+    for i in initial..initial + added {
+        let new_user: EdocumentUserAttribute = serde_json::from_value(user(i)).unwrap();
+        solver.add_user(&new_user).unwrap();
+        assert!(solver.user_has_any_access(&new_user.user_id).unwrap().is_some());
+    }
+    let incremental = start.elapsed() / added as u32;
+
+    println!("per user: rebuild {:?}, add_user {:?}", rebuild, incremental);
+    assert!(incremental < rebuild, "add_user took {:?} per user, rebuilding {:?}", incremental, rebuild);
+}
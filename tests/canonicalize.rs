@@ -0,0 +1,58 @@
+//! Canonicalizing the rules must shrink them without changing which triples are permitted.
+
+use abac_solver::edocument::{default_action_hierarchy, ActionHierarchy, EdocumentAbacData};
+use abac_solver::simple_loop::permitted_triples;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+fn rule(user_conditions: Value, resource_conditions: Value, actions: &[&str], effect: &str) -> Value {
+    json!({"id": 0, "description": "", "user_conditions": user_conditions, "resource_conditions": resource_conditions,
+           "actions": actions, "comparison_conditions": [], "effect": effect})
+}
+
+#[test]
+fn equivalent_rules_are_merged_without_changing_the_grants() {
+    let employee = json!({"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}});
+    let invoice = json!({"left": {"AttributeName": "Type"}, "operator": "Equals", "right": {"AttributeValue": {"ResourceType": "Invoice"}}});
+    let staff = |roles: [&str; 2]| json!({"left": {"AttributeName": "Role"}, "operator": "ContainedIn",
+                                          "right": {"ValueSet": [{"Role": roles[0]}, {"Role": roles[1]}]}});
+    let mut data: EdocumentAbacData = serde_json::from_value(json!({
+        "users": [
+            {"user_id": "alice", "role": "Employee", "projects": [], "supervisee": []},
+            {"user_id": "bob", "role": "Manager", "projects": [], "supervisee": []}
+        ],
+        "resources": [
+            {"resource_id": "doc1", "resource_type": "Invoice", "recipients": []},
+            {"resource_id": "doc2", "resource_type": "Contract", "recipients": []}
+        ],
+        "rules": [
+            rule(json!([employee, employee]), json!([invoice]), &["View"], "Permit"),
+            rule(json!([employee]), json!([invoice]), &["Edit"], "Permit"),
+            rule(json!([employee]), json!([invoice]), &["View"], "Permit"),
+            rule(json!([staff(["Manager", "Employee"])]), json!([]), &["Send"], "Permit"),
+            rule(json!([staff(["Employee", "Manager"])]), json!([]), &["Search"], "Permit"),
+            rule(json!([employee]), json!([invoice]), &["Edit"], "Deny"),
+        ]
+    })).unwrap();
+    let before = permitted_triples(&data, &ActionHierarchy::new());
+
+    data.canonicalize();
+    assert_eq!(data.rules.len(), 3, "{:?}", data.rules);
+    assert_eq!(permitted_triples(&data, &ActionHierarchy::new()), before);
+}
+
+#[test]
+fn canonicalizing_the_fixtures_keeps_their_grants() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    for entry in fs::read_dir(fixtures).expect("tests/fixtures is readable") {
+        let fixture = entry.expect("fixture entry is readable").path();
+        let data: EdocumentAbacData = serde_json::from_str(&fs::read_to_string(&fixture).unwrap()).unwrap();
+        for hierarchy in [ActionHierarchy::new(), default_action_hierarchy()] {
+            let before = permitted_triples(&data, &hierarchy);
+            let mut canonical = data.clone();
+            canonical.canonicalize();
+            assert_eq!(permitted_triples(&canonical, &hierarchy), before, "{}", fixture.display());
+        }
+    }
+}
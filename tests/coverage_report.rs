@@ -0,0 +1,38 @@
+//! The coverage report is pure post-processing: given a result list it must name exactly the
+//! users and resources the results never mention.
+
+use abac_solver::coverage_report::coverage_report;
+use abac_solver::edocument::{Action, EdocumentAbacData, EdocumentAccessResult};
+
+fn grant(user_id: &str, resource_id: &str, action: Action) -> EdocumentAccessResult {
+    EdocumentAccessResult { user_id: user_id.to_string(), resource_id: resource_id.to_string(), action }
+}
+
+#[test]
+fn orphaned_resource_and_idle_user_are_reported() {
+    let data: EdocumentAbacData = serde_json::from_value(serde_json::json!({
+        "users": [
+            {"user_id": "alice", "projects": [], "supervisee": []},
+            {"user_id": "bob", "projects": [], "supervisee": []},
+            {"user_id": "carol", "projects": [], "supervisee": []}
+        ],
+        "resources": [
+            {"resource_id": "doc1", "recipients": []},
+            {"resource_id": "doc2", "recipients": []},
+            {"resource_id": "orphan", "recipients": []}
+        ],
+        "rules": []
+    })).unwrap();
+    let results = [
+        grant("alice", "doc1", Action::View),
+        grant("alice", "doc2", Action::View),
+        grant("bob", "doc1", Action::Edit),
+    ];
+
+    let report = coverage_report(&results, &data);
+    assert_eq!(report.users_without_grants, ["carol"]);
+    assert_eq!(report.resources_without_grants, ["orphan"]);
+    assert_eq!(report.grants_per_action.get("view"), Some(&2));
+    assert_eq!(report.grants_per_action.get("edit"), Some(&1));
+    assert_eq!(report.grants_per_action.len(), 2);
+}
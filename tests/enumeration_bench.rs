@@ -0,0 +1,82 @@
+//! Times both enumeration paths on generated datasets, to check that total time grows roughly
+//! linearly with the number of solutions. `--max-solutions` takes the capped path, which
+//! visits resources in id order; writing results without a cap takes the uncapped
+//! `Enumerator::for_each` path. Run with
+//! `cargo test --release --test enumeration_bench -- --ignored --nocapture`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// `n` employees and `n` documents under one rule letting every employee view every document,
+/// so there are `n * n` permitted triples
+fn write_dataset(n: usize) -> PathBuf {
+    let users: Vec<String> = (0..n)
+        .map(|i| format!(r#"{{"user_id": "user{}", "role": "Employee", "projects": [], "supervisee": []}}"#, i))
+        .collect();
+    let resources: Vec<String> = (0..n)
+        .map(|i| format!(r#"{{"resource_id": "doc{}", "resource_type": "Invoice", "owner": "user{}", "recipients": []}}"#, i, i))
+        .collect();
+    let rule = r#"{"id": 1, "description": "Employees can view every document",
+        "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}}],
+        "resource_conditions": [], "actions": ["View"], "comparison_conditions": []}"#;
+    let dataset = format!(r#"{{"users": [{}], "resources": [{}], "rules": [{}]}}"#, users.join(","), resources.join(","), rule);
+
+    let input = std::env::temp_dir().join(format!("abac-solver-bench-{}-{}.json", std::process::id(), n));
+    fs::write(&input, dataset).expect("dataset is writable");
+    input
+}
+
+fn time_solve(input: &Path, args: &[&str]) -> Duration {
+    let start = Instant::now();
+    let status = Command::new(env!("CARGO_BIN_EXE_abac-solver"))
+        .args(["--solver", "z3", "--quiet"])
+        .args(args)
+        .arg("--json-path")
+        .arg(input)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .expect("solver binary runs");
+    assert!(status.success());
+    start.elapsed()
+}
+
+fn assert_linear(path: &str, timings: &[(u32, Duration)]) {
+    let per_solution: Vec<f64> = timings.iter()
+        .map(|(solutions, elapsed)| {
+            println!("{}: {} solutions: {:?} ({:?} per solution)", path, solutions, elapsed, *elapsed / *solutions);
+            elapsed.as_secs_f64() / f64::from(*solutions)
+        })
+        .collect();
+    // Quadratic growth would make each solution of the larger run cost about four times as much
+    assert!(per_solution[1] < per_solution[0] * 2.5,
+            "{}: per-solution time grew from {:.4}s to {:.4}s", path, per_solution[0], per_solution[1]);
+}
+
+#[test]
+#[ignore]
+fn capped_enumeration_time_grows_linearly() {
+    let input = write_dataset(60);
+    let timings: Vec<(u32, Duration)> = [500, 2000].into_iter()
+        .map(|max| (max, time_solve(&input, &["--max-solutions", &max.to_string()])))
+        .collect();
+    fs::remove_file(&input).ok();
+    assert_linear("capped", &timings);
+}
+
+#[test]
+#[ignore]
+fn uncapped_enumeration_time_grows_linearly() {
+    let results = std::env::temp_dir().join(format!("abac-solver-bench-{}-results.json", std::process::id()));
+    let timings: Vec<(u32, Duration)> = [30, 60].into_iter()
+        .map(|n| {
+            let input = write_dataset(n);
+            let elapsed = time_solve(&input, &["--results-out", results.to_str().unwrap()]);
+            fs::remove_file(&input).ok();
+            ((n * n) as u32, elapsed)
+        })
+        .collect();
+    fs::remove_file(&results).ok();
+    assert_linear("uncapped", &timings);
+}
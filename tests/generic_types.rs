@@ -0,0 +1,49 @@
+//! Both domains convert into the shared generic representation with their attributes, conditions
+//! and actions spelled the same way.
+
+use abac_solver::edocument::EdocumentAbacData;
+use abac_solver::types::generic_types::{GenericAbacData, GenericValue};
+use abac_solver::types::types::{AttributeExpression, ComparisonOperator};
+use abac_solver::university::{UniversityAbacParser, UniversityDomainParser};
+
+#[test]
+fn edocument_data_converts_attribute_by_attribute() {
+    let data: EdocumentAbacData = serde_json::from_value(serde_json::json!({
+        "users": [{"user_id": "alice", "role": "Manager", "clearance_level": 2, "projects": ["p2", "p1"], "supervisee": []}],
+        "resources": [{"resource_id": "doc1", "owner": "alice", "is_confidential": true, "recipients": []}],
+        "rules": [{"id": 1, "description": "Managers can view and edit", "resource_conditions": [], "comparison_conditions": [],
+                   "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Manager"}}}],
+                   "actions": ["View", "Edit"]}]
+    })).unwrap();
+    let generic = GenericAbacData::from(data);
+
+    let alice = &generic.subjects[0];
+    assert_eq!(alice.id, "alice");
+    assert_eq!(alice.attributes["clearanceLevel"], GenericValue::Int(2));
+    assert_eq!(alice.attributes["projects"], GenericValue::Set(vec!["p1".to_string(), "p2".to_string()]));
+    assert!(!alice.attributes.contains_key("department"));
+
+    let doc1 = &generic.resources[0];
+    assert_eq!(doc1.attributes["owner"], GenericValue::String("alice".to_string()));
+    assert_eq!(doc1.attributes["isConfidential"], GenericValue::Bool(true));
+
+    let rule = &generic.rules[0];
+    assert_eq!(rule.actions, ["edit", "view"]);
+    assert_eq!(rule.user_conditions[0].left, AttributeExpression::AttributeName("role".to_string()));
+    assert_eq!(rule.user_conditions[0].operator, ComparisonOperator::Equals);
+}
+
+#[test]
+fn university_data_converts_from_abac_files() {
+    let content = "userAttrib(csStu1, position=student, department=cs, crsTaken={cs101})\n\
+                   resourceAttrib(cs101gradebook, type=gradebook, crs=cs101)\n\
+                   rule(; type [ {gradebook}; {readMyScores}; crsTaken ] crs)\n";
+    let data = UniversityAbacParser::new(UniversityDomainParser).parse(content).unwrap();
+    let generic = GenericAbacData::from(data);
+
+    assert_eq!(generic.subjects[0].id, "csStu1");
+    assert_eq!(generic.subjects[0].attributes["crsTaken"], GenericValue::Set(vec!["cs101".to_string()]));
+    assert_eq!(generic.resources[0].attributes["crs"], GenericValue::String("cs101".to_string()));
+    assert_eq!(generic.rules[0].actions, ["readMyScores"]);
+    assert_eq!(generic.rules[0].comparison_conditions[0].operator, ComparisonOperator::Contains);
+}
@@ -0,0 +1,363 @@
+//! Solves every fixture in `tests/fixtures` and compares the sorted permitted triples with
+//! the file of the same name in `tests/golden`. Run with `UPDATE_GOLDEN=1` to regenerate them.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `tests/<dir>/<name>` as a command-line argument
+fn test_file(dir: &str, name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir).join(name).to_string_lossy().into_owned()
+}
+
+/// The name and path of every fixture in `tests/fixtures`
+fn fixtures() -> impl Iterator<Item = (String, String)> {
+    fs::read_dir(test_file("fixtures", "")).expect("tests/fixtures is readable")
+        .map(|entry| {
+            let fixture = entry.expect("fixture entry is readable").path();
+            (fixture.file_name().unwrap().to_string_lossy().into_owned(), fixture.to_string_lossy().into_owned())
+        })
+}
+
+/// The permitted triples recorded for the fixture `name`
+fn golden(name: &str) -> serde_json::Value {
+    let content = fs::read_to_string(test_file("golden", name))
+        .unwrap_or_else(|_| panic!("missing golden file for {}; run with UPDATE_GOLDEN=1", name));
+    serde_json::from_str(&content).expect("golden file is JSON")
+}
+
+/// A path in the temp dir no other test of this run uses
+fn temp_path(name: &str) -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let unique = format!("abac-solver-{}-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed), name);
+    std::env::temp_dir().join(unique).to_string_lossy().into_owned()
+}
+
+fn run_solver(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_abac-solver")).args(args).output().expect("solver binary runs")
+}
+
+/// Solves `input` quietly with z3 and `args`, returning the run and the triples it wrote with --results-out
+fn solve(input: &str, args: &[&str]) -> (Output, String) {
+    let results = temp_path("results.json");
+    let output = run_solver(&[&["--solver", "z3", "--quiet", "--json-path", input, "--results-out", &results], args].concat());
+    assert!(output.status.success(), "solver failed on {} {:?}:\n{}", input, args, String::from_utf8_lossy(&output.stderr));
+    let content = fs::read_to_string(&results).expect("solver wrote its results");
+    fs::remove_file(&results).ok();
+    (output, content)
+}
+
+#[test]
+fn solver_output_matches_golden_files() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    let mut mismatches = Vec::new();
+
+    for (name, fixture) in fixtures() {
+        let (_, actual_content) = solve(&fixture, &[]);
+        if update {
+            fs::write(test_file("golden", &name), &actual_content).expect("golden file is writable");
+            continue;
+        }
+
+        let expected = golden(&name);
+        let actual: serde_json::Value = serde_json::from_str(&actual_content).expect("results are JSON");
+        if expected != actual {
+            mismatches.push(format!("{}:\nexpected {}\n  actual {}", name, expected, actual));
+        }
+    }
+
+    assert!(mismatches.is_empty(), "solver output differs from golden files:\n{}", mismatches.join("\n"));
+}
+
+#[test]
+fn max_solutions_reports_whether_the_cap_was_hit() {
+    // set_membership.json permits 7 triples and supported_actions.json exactly 3
+    for (name, truncated) in [("set_membership.json", true), ("supported_actions.json", false)] {
+        let (output, content) = solve(&test_file("fixtures", name), &["--max-solutions", "3"]);
+        let results: serde_json::Value = serde_json::from_str(&content).expect("results are JSON");
+        assert_eq!(results.as_array().map(Vec::len), Some(3), "{} should stop at the cap", name);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.contains("stopped at --max-solutions"), truncated, "{}:\n{}", name, stdout);
+    }
+}
+
+#[test]
+fn tenant_scoped_results_are_written_like_the_full_enumeration() {
+    let fixture = test_file("fixtures", "tenant_match.json");
+    for (tenant, expected) in [("largeBank", 1), ("newsAgency", 0)] {
+        let (_, content) = solve(&fixture, &["--tenant", tenant, "--max-solutions", "5"]);
+        let results: serde_json::Value = serde_json::from_str(&content).expect("results are JSON");
+        assert_eq!(results.as_array().map(Vec::len), Some(expected), "tenant {}", tenant);
+    }
+
+    // Single queries have no results to write, so output flags are refused rather than ignored
+    let output = run_solver(&["--solver", "z3", "--quiet", "--verify", "expectations.txt", "--results-out", "results.json", "--json-path", &fixture]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn repeated_solves_write_identical_results() {
+    let fixture = test_file("fixtures", "set_membership.json");
+    for args in [&[][..], &["--max-solutions", "3"]] {
+        let runs: Vec<String> = (0..2).map(|_| solve(&fixture, args).1).collect();
+        assert_eq!(runs[0], runs[1], "{:?}", args);
+    }
+}
+
+#[test]
+fn single_queries_match_expectations() {
+    let fixture = test_file("fixtures", "owner_is_requester.json");
+    let expectations = test_file("expectations", "owner_is_requester.txt");
+    let output = run_solver(&["--solver", "z3", "--quiet", "--json-path", &fixture, "--verify", &expectations]);
+    assert!(output.status.success(), "expectations failed:\n{}", String::from_utf8_lossy(&output.stdout));
+
+    // --only-actions narrows enumeration, not single queries
+    let output = run_solver(&["--solver", "z3", "--quiet", "--only-actions", "view", "--json-path", &fixture, "--verify", &expectations]);
+    assert!(output.status.success(), "expectations failed with --only-actions:\n{}", String::from_utf8_lossy(&output.stdout));
+
+    let unknown = temp_path("unknown.txt");
+    fs::write(&unknown, "mallory can edit doc1\n").expect("expectations file is writable");
+    let output = run_solver(&["--solver", "z3", "--quiet", "--json-path", &fixture, "--verify", &unknown]);
+    fs::remove_file(&unknown).ok();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown user: mallory"));
+}
+
+#[test]
+fn ownership_bindings_hold_per_query() {
+    let output = run_solver(&[
+        "--solver", "z3", "--quiet",
+        "--json-path", &test_file("fixtures", "owner_binding.json"),
+        "--verify", &test_file("expectations", "owner_binding.txt"),
+    ]);
+    assert!(output.status.success(), "expectations failed:\n{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn failed_expectations_explain_the_denial() {
+    let output = run_solver(&[
+        "--solver", "z3", "--quiet",
+        "--json-path", &test_file("fixtures", "tenant_match.json"),
+        "--verify", &test_file("expectations", "tenant_match_denials.txt"),
+    ]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("rule 1: comparison condition `tenant = tenant` does not hold"), "{}", stdout);
+    assert!(stdout.contains("rule 1: matches every condition but grants {view}, not edit"), "{}", stdout);
+}
+
+#[test]
+fn failed_expectations_report_deny_rules_and_restrictions() {
+    let cases = [
+        ("deny_override", "taken away by deny rule 2: NewsAgency users never view LargeBank documents"),
+        ("archived_read_only", "taken away by restriction: archived resources are read-only"),
+    ];
+    for (name, expected) in cases {
+        let output = run_solver(&[
+            "--solver", "z3", "--quiet",
+            "--json-path", &test_file("fixtures", &format!("{}.json", name)),
+            "--verify", &test_file("expectations", &format!("{}_denials.txt", name)),
+        ]);
+        assert!(!output.status.success());
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("granted by rule 1"), "{}", stdout);
+        assert!(stdout.contains(expected), "{}", stdout);
+        assert!(!stdout.contains("no rule grants"), "{}", stdout);
+    }
+}
+
+#[test]
+fn validate_reports_every_bad_value() {
+    let cases = [
+        ("two_bad_values.json", ["users[1]", "Manger", "resources[0]", "Invocie"]),
+        ("two_bad_values.abac", ["line 2", "manger", "line 4", "veiw"]),
+    ];
+    for (name, expected) in cases {
+        let output = run_solver(&["--solver", "validate", "--json-path", &test_file("invalid", name)]);
+        assert!(!output.status.success(), "{} should fail validation", name);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for fragment in expected {
+            assert!(stdout.contains(fragment), "{} output is missing {}:\n{}", name, fragment, stdout);
+        }
+    }
+}
+
+#[test]
+fn lint_reports_unreachable_rules() {
+    let output = run_solver(&["--lint", "--json-path", &test_file("fixtures", "dead_rule.json")]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("unreachable: rule 2 "), "{}", stdout);
+    assert!(!stdout.contains("unreachable: rule 1 "), "{}", stdout);
+}
+
+#[test]
+fn lint_reports_triples_a_deny_rule_overrides() {
+    let output = run_solver(&["--lint", "--json-path", &test_file("fixtures", "deny_override.json")]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let overridden: Vec<&str> = stdout.lines().filter(|line| line.starts_with("overridden: ")).collect();
+    assert_eq!(overridden, ["overridden: a permit rule grants 'bob can view doc1' but a deny rule takes it away"], "{}", stdout);
+}
+
+#[test]
+fn parallel_actions_match_the_serial_results() {
+    for (name, fixture) in fixtures() {
+        let (_, content) = solve(&fixture, &["--parallel-actions"]);
+        let actual: serde_json::Value = serde_json::from_str(&content).expect("results are JSON");
+        assert_eq!(golden(&name), actual, "{} differs between the serial and parallel solvers", name);
+    }
+}
+
+#[test]
+fn user_reports_are_subsets_of_the_full_enumeration() {
+    let fixture = test_file("fixtures", "set_membership.json");
+    let permitted: Vec<(String, String, String)> = golden("set_membership.json").as_array().unwrap().iter()
+        .map(|t| (t["user_id"].as_str().unwrap().to_string(), t["resource_id"].as_str().unwrap().to_string(), t["action"].as_str().unwrap().to_lowercase()))
+        .collect();
+    let fixture_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&fixture).unwrap()).unwrap();
+
+    for user in fixture_json["users"].as_array().unwrap() {
+        let user_id = user["user_id"].as_str().unwrap();
+        let output = run_solver(&["--solver", "z3", "--quiet", "--json-path", &fixture, "--report", &format!("user:{}", user_id)]);
+        assert!(output.status.success(), "report for {} failed", user_id);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reported: Vec<(String, String, String)> = stdout.lines()
+            .filter_map(|line| line.strip_prefix("  ")?.split_once(": "))
+            .map(|(resource, action)| (user_id.to_string(), resource.to_string(), action.to_lowercase()))
+            .collect();
+        let expected = permitted.iter().filter(|t| t.0 == user_id).count();
+        assert_eq!(reported.len(), expected, "{}", stdout);
+        for triple in &reported {
+            assert!(permitted.contains(triple), "{:?} is not in the full enumeration", triple);
+        }
+    }
+}
+
+#[test]
+fn analyze_reads_the_data_path_outside_the_repo() {
+    let work_dir = temp_path("analyze");
+    fs::create_dir_all(&work_dir).expect("temp dir is writable");
+    let abac = Path::new(&work_dir).join("policy.abac");
+    fs::write(&abac, "\
+userAttrib(stu1, position=student, department=cs, crsTaken={cs101})
+resourceAttrib(cs101gradebook, type=gradebook, crs=cs101)
+rule(; type [ {gradebook}; {readMyScores}; crsTaken ] crs)
+").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_abac-solver"))
+        .args(["--solver", "analyze", "--data-path"])
+        .arg(&abac)
+        .current_dir(&work_dir)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("solver binary runs");
+    // Select the university domain at the prompt
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), b"1\n").unwrap();
+    let output = child.wait_with_output().expect("solver binary finishes");
+    let parsed = fs::read_to_string(Path::new(&work_dir).join("output").join("parsed_university.json"));
+    fs::remove_dir_all(&work_dir).ok();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(parsed.expect("parsed JSON was written").contains("cs101gradebook"));
+}
+
+#[test]
+fn compare_finds_no_disagreement() {
+    for (name, fixture) in fixtures() {
+        let output = run_solver(&["--solver", "compare", "--json-path", &fixture]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}: {}", name, stdout);
+        assert!(stdout.contains("0 disagreement(s)"), "{}: {}", name, stdout);
+    }
+}
+
+#[test]
+fn abac_input_solves_like_the_json_fixture() {
+    for name in ["recipients_and_clearance", "deny_override"] {
+        let results: Vec<serde_json::Value> = [test_file("abac", &format!("{}.abac", name)), test_file("fixtures", &format!("{}.json", name))]
+            .iter()
+            .map(|input| serde_json::from_str(&solve(input, &[]).1).expect("results are JSON"))
+            .collect();
+        assert!(!results[0].as_array().unwrap().is_empty(), "{}", name);
+        assert_eq!(results[0], results[1], "{}", name);
+    }
+}
+
+#[test]
+fn dump_smt_writes_the_declared_functions() {
+    let dump = temp_path("dump.smt2");
+    let output = run_solver(&["--solver", "z3", "--quiet", "--json-path", &test_file("fixtures", "dual_role.json"), "--dump-smt", &dump]);
+    let script = fs::read_to_string(&dump);
+    fs::remove_file(&dump).ok();
+
+    assert!(output.status.success());
+    let script = script.expect("solver wrote the SMT-LIB dump");
+    assert!(script.contains("(declare-fun user_role"), "{}", script);
+    assert!(script.contains("(check-sat)"));
+}
+
+#[test]
+fn strict_mode_rejects_dangling_owners() {
+    let input = temp_path("dangling.json");
+    fs::write(&input, r#"{
+  "users": [{"user_id": "alice", "projects": [], "supervisee": []}],
+  "resources": [{"resource_id": "doc1", "owner": "ghost", "recipients": ["alice"]}],
+  "rules": []
+}"#).expect("temp input is writable");
+
+    let strict = run_solver(&["--solver", "z3", "--quiet", "--strict", "--json-path", &input]);
+    let lenient = run_solver(&["--stats", "json", "--json-path", &input]);
+    fs::remove_file(&input).ok();
+
+    assert!(!strict.status.success());
+    let stderr = String::from_utf8_lossy(&strict.stderr);
+    assert!(stderr.contains("doc1: owner 'ghost'"), "{}", stderr);
+    assert!(!stderr.contains("'alice'"), "{}", stderr);
+
+    assert!(lenient.status.success());
+    let stderr = String::from_utf8_lossy(&lenient.stderr);
+    assert!(stderr.contains("warning: resource doc1: owner 'ghost' is not a known user"), "{}", stderr);
+}
+
+#[test]
+fn stats_count_the_rules_as_written() {
+    let input = temp_path("stats-rules.json");
+    fs::write(&input, r#"{
+  "users": [{"user_id": "alice", "role": "Employee", "projects": [], "supervisee": []}],
+  "resources": [{"resource_id": "doc1", "recipients": []}],
+  "rules": [
+    {"id": 1, "description": "Employees can view", "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}}],
+     "resource_conditions": [], "actions": ["View"], "comparison_conditions": []},
+    {"id": 2, "description": "Employees can edit", "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}}],
+     "resource_conditions": [], "actions": ["Edit"], "comparison_conditions": []}
+  ]
+}"#).expect("temp input is writable");
+    let output = run_solver(&["--stats", "json", "--json-path", &input]);
+    fs::remove_file(&input).ok();
+
+    assert!(output.status.success());
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stats are JSON");
+    // Solving merges the two rules into one; the profile describes the file as written
+    assert_eq!(stats["rules"], 2);
+}
+
+#[test]
+fn solving_without_a_json_input_is_an_error() {
+    let output = run_solver(&["--solver", "z3", "--quiet", "--data-path", &test_file("abac", "recipients_and_clearance.abac")]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No input given"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
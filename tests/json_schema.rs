@@ -0,0 +1,100 @@
+//! The schema printed by `--emit-schema` accepts every fixture and a generated dataset, and
+//! rejects values outside the enums. The validator below covers the keywords the schema uses.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use abac_solver::example_data::edocument_with_access_level::generate_edocument_data_with_clearance;
+use serde_json::Value;
+
+fn emitted_schema() -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_abac-solver"))
+        .arg("--emit-schema")
+        .output()
+        .expect("solver binary runs");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("schema is JSON")
+}
+
+/// Where `instance` breaks `schema`, or `None` if it conforms
+fn violation(root: &Value, schema: &Value, instance: &Value, path: &str) -> Option<String> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let name = reference.strip_prefix("#/$defs/").expect("local references only");
+        return violation(root, &root["$defs"][name], instance, path);
+    }
+    if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+        let matching = options.iter().filter(|option| violation(root, option, instance, path).is_none()).count();
+        return (matching != 1).then(|| format!("{}: {} matches {} of the alternatives", path, instance, matching));
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return (!values.contains(instance)).then(|| format!("{}: {} is not one of {:?}", path, instance, values));
+    }
+    let type_ok = match schema.get("type").and_then(Value::as_str) {
+        Some("object") => instance.is_object(),
+        Some("array") => instance.is_array(),
+        Some("string") => instance.is_string(),
+        Some("integer") => instance.is_i64() || instance.is_u64(),
+        Some("boolean") => instance.is_boolean(),
+        Some("null") => instance.is_null(),
+        _ => true,
+    };
+    if !type_ok {
+        return Some(format!("{}: {} is not of type {}", path, instance, schema["type"]));
+    }
+    if let (Some(pattern), Some(text)) = (schema.get("pattern").and_then(Value::as_str), instance.as_str()) {
+        if !regex::Regex::new(pattern).unwrap().is_match(text) {
+            return Some(format!("{}: {:?} does not match {}", path, text, pattern));
+        }
+    }
+    if let Some(object) = instance.as_object() {
+        let properties = schema["properties"].as_object().unwrap();
+        for name in schema["required"].as_array().unwrap() {
+            if !object.contains_key(name.as_str().unwrap()) {
+                return Some(format!("{}: missing {}", path, name));
+            }
+        }
+        for (name, value) in object {
+            let Some(property) = properties.get(name) else {
+                return Some(format!("{}: unexpected property {}", path, name));
+            };
+            if let Some(found) = violation(root, property, value, &format!("{}.{}", path, name)) {
+                return Some(found);
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), instance.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            if let Some(found) = violation(root, items, item, &format!("{}[{}]", path, i)) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn fixtures_and_generated_data_match_the_schema() {
+    let schema = emitted_schema();
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    for entry in fs::read_dir(fixtures).expect("tests/fixtures is readable") {
+        let fixture = entry.expect("fixture entry is readable").path();
+        let instance: Value = serde_json::from_str(&fs::read_to_string(&fixture).unwrap()).unwrap();
+        assert_eq!(violation(&schema, &schema, &instance, "$"), None, "{}", fixture.display());
+    }
+
+    let generated = serde_json::to_value(generate_edocument_data_with_clearance(30, 30, 5, 5, 5)).unwrap();
+    assert_eq!(violation(&schema, &schema, &generated, "$"), None);
+}
+
+#[test]
+fn schema_rejects_unknown_enum_values() {
+    let schema = emitted_schema();
+    let instance = serde_json::json!({
+        "users": [{"user_id": "alice", "role": "Manger", "projects": [], "supervisee": []}],
+        "resources": [],
+        "rules": []
+    });
+    let found = violation(&schema, &schema, &instance, "$").expect("Manger is not a role");
+    assert!(found.starts_with("$.users[0].role"), "{}", found);
+}
@@ -0,0 +1,34 @@
+//! The brute-force loops report progress per rule; once a run finishes, the bar has to
+//! have counted every (rule, user, resource) combination exactly once.
+
+use std::fs;
+use std::path::Path;
+
+use abac_solver::edocument::EdocumentAbacData;
+use abac_solver::simple_loop::{improved_simple_loop_with_progress, parallel_indexed_loop_with_progress, simple_loop_with_progress};
+use indicatif::ProgressBar;
+
+fn load_fixture() -> (EdocumentAbacData, u64) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("set_membership.json");
+    let data: EdocumentAbacData = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let combinations = (data.rules.len() * data.users.len() * data.resources.len()) as u64;
+    (data, combinations)
+}
+
+#[test]
+fn progress_counts_every_combination() {
+    let (data, combinations) = load_fixture();
+    assert!(combinations > 0);
+
+    let bar = ProgressBar::hidden();
+    simple_loop_with_progress(data.clone(), Some(&bar)).unwrap();
+    assert_eq!((bar.position(), bar.length()), (combinations, Some(combinations)));
+
+    let bar = ProgressBar::hidden();
+    improved_simple_loop_with_progress(data.clone(), Some(&bar)).unwrap();
+    assert_eq!((bar.position(), bar.length()), (combinations, Some(combinations)));
+
+    let bar = ProgressBar::hidden();
+    parallel_indexed_loop_with_progress(data, Some(&bar)).unwrap();
+    assert_eq!((bar.position(), bar.length()), (combinations, Some(combinations)));
+}
@@ -0,0 +1,19 @@
+//! Parse errors point at the column of the field that failed, even when the same text
+//! appears earlier on the line.
+
+use abac_solver::edocument::{EdocumentAbacParser, EdocumentDomainParser};
+
+fn error_column(content: &str) -> String {
+    let error = EdocumentAbacParser::new(EdocumentDomainParser).parse(content).unwrap_err().to_string();
+    error.split(':').next().unwrap().to_string()
+}
+
+#[test]
+fn columns_come_from_the_field_not_the_first_match() {
+    // The user id is the same text as the bad field
+    assert_eq!(error_column("userAttrib(role=manger, role=manger)"), "Parse error at line 1, column 25");
+    // The resource id is the same text as the bad type
+    assert_eq!(error_column("resourceAttrib(invoce, type=invoce)"), "Parse error at line 1, column 29");
+    // The actions section holds the same text as the bad effect
+    assert_eq!(error_column("rule(; ; {view}; effect = view)"), "Parse error at line 1, column 27");
+}
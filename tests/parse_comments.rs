@@ -0,0 +1,41 @@
+//! Comments and blank lines in policy files are skipped but still count towards line numbers.
+
+use abac_solver::edocument::{EdocumentAbacParser, EdocumentDomainParser, Role};
+
+#[test]
+fn a_file_of_only_comments_and_blank_lines_is_empty() {
+    let data = EdocumentAbacParser::new(EdocumentDomainParser)
+        .parse("# users\n\n   # indented comment\n\n# rules\n")
+        .unwrap();
+    assert!(data.users.is_empty() && data.resources.is_empty() && data.rules.is_empty());
+}
+
+#[test]
+fn trailing_comments_are_stripped() {
+    let content = "userAttrib(alice, role=manager) # team lead\nresourceAttrib(doc1, type=invoice, owner=alice)# q3\nrule(role = manager; ; {view}) # managers see everything\n";
+    let data = EdocumentAbacParser::new(EdocumentDomainParser).parse(content).unwrap();
+    assert!(data.users[0].roles.contains(&Role::Manager));
+    assert_eq!(data.resources[0].owner.as_deref(), Some("alice"));
+    assert_eq!(data.rules.len(), 1);
+
+    let data = EdocumentAbacParser::new(EdocumentDomainParser).with_comment_marker("//")
+        .parse("// users\nuserAttrib(alice, role=manager) // team lead\n")
+        .unwrap();
+    assert!(data.users[0].roles.contains(&Role::Manager));
+}
+
+#[test]
+fn markers_inside_values_are_kept() {
+    let content = "userAttrib(alice, role=manager, department=R#D, projects={Q#3 apollo}) # team lead\n";
+    let data = EdocumentAbacParser::new(EdocumentDomainParser).parse(content).unwrap();
+    assert_eq!(data.users[0].department.as_deref(), Some("R#D"));
+    assert!(data.users[0].projects.contains("Q#3"));
+    assert!(data.users[0].projects.contains("apollo"));
+}
+
+#[test]
+fn errors_count_skipped_lines() {
+    let content = "# users\n\nuserAttrib(alice, role=manager) # ok\n\n# the next line is wrong\nuserAttrib(bob, role=auditor)\n";
+    let error = EdocumentAbacParser::new(EdocumentDomainParser).parse(content).unwrap_err().to_string();
+    assert!(error.starts_with("Parse error at line 6,"), "{}", error);
+}
@@ -0,0 +1,34 @@
+//! Enum values in policy files: other spellings of a value resolve to it, and a misspelled
+//! value is reported with the closest valid one.
+
+use abac_solver::edocument::{EdocumentAbacParser, EdocumentDomainParser, Position, Tenant};
+
+#[test]
+fn case_and_underscores_do_not_matter() {
+    let parser = EdocumentAbacParser::new(EdocumentDomainParser);
+    for spelling in ["senior_office_manager", "seniorOfficeManager", "SeniorOfficeManager", "SENIOROFFICEMANAGER"] {
+        let data = parser.parse(&format!("userAttrib(alice, position={}, tenant=LARGE_BANK)", spelling)).expect(spelling);
+        assert_eq!(data.users[0].position, Some(Position::SeniorOfficeManager), "{}", spelling);
+        assert_eq!(data.users[0].tenant, Some(Tenant::LargeBank), "{}", spelling);
+    }
+    // Similar names of different variants stay distinct
+    let data = parser.parse("userAttrib(alice, tenant=large_bank_leasing)").unwrap();
+    assert_eq!(data.users[0].tenant, Some(Tenant::LargeBankLeasing));
+}
+
+#[test]
+fn one_character_typo_suggests_the_intended_value() {
+    let parser = EdocumentAbacParser::new(EdocumentDomainParser);
+    let cases = [
+        ("userAttrib(alice, role=manger)", "Unknown role: 'manger', did you mean 'manager'?"),
+        ("userAttrib(alice, tenant=largeBnak)", "Unknown tenant: 'largeBnak', did you mean 'largeBank'?"),
+        ("resourceAttrib(doc1, type=invoce)", "Unknown resource_type: 'invoce', did you mean 'invoice'?"),
+    ];
+    for (line, expected) in cases {
+        let error = parser.parse(line).expect_err(line).to_string();
+        assert!(error.contains(expected), "{}: {}", line, error);
+    }
+
+    let error = parser.parse("userAttrib(alice, role=auditor)").expect_err("auditor is not a role").to_string();
+    assert!(error.contains("Unknown role: auditor"), "{}", error);
+}
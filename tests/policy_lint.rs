@@ -0,0 +1,26 @@
+//! Redundancy checks of the policy linter.
+
+use abac_solver::edocument::EdocumentAbacData;
+use abac_solver::policy_lint::{check_policy, PolicyIssue};
+use serde_json::json;
+
+fn redundancies(rules: serde_json::Value) -> Vec<String> {
+    let data: EdocumentAbacData = serde_json::from_value(json!({"users": [], "resources": [], "rules": rules})).unwrap();
+    check_policy(&[data]).into_iter()
+        .filter_map(|issue| match issue {
+            PolicyIssue::Redundant(msg) => Some(msg),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn a_cross_tenant_rule_is_not_covered_by_a_local_one() {
+    let managers = json!({"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Manager"}}});
+    let rule = |id: u32, cross_tenant: bool| json!({"id": id, "description": "Managers can view", "user_conditions": [managers],
+        "resource_conditions": [], "actions": ["View"], "comparison_conditions": [], "cross_tenant": cross_tenant});
+
+    assert_eq!(redundancies(json!([rule(1, false), rule(2, false)])), ["rule 2 is already covered by rule 1"]);
+    assert!(redundancies(json!([rule(1, false), rule(2, true)])).is_empty());
+    assert!(redundancies(json!([rule(1, true), rule(2, false)])).is_empty());
+}
@@ -0,0 +1,39 @@
+//! Sampling a dataset down for fast iteration keeps the requested number of users and
+//! resources, and a seeded sample is the same on every run.
+
+use abac_solver::edocument::{ActionHierarchy, EdocumentAbacSolver};
+use abac_solver::example_data::edocument_with_access_level::generate_edocument_data_with_clearance;
+use z3::{Config, Context};
+
+#[test]
+fn limit_users_shrinks_the_user_sort() {
+    let mut data = generate_edocument_data_with_clearance(50, 50, 5, 5, 5);
+    data.sample(Some(5), None, None);
+    assert_eq!(data.users.len(), 5);
+    assert_eq!(data.resources.len(), 50);
+
+    let ctx = Context::new(&Config::new());
+    let solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    assert_eq!(solver.user_count(), 5);
+}
+
+#[test]
+fn seeded_samples_are_deterministic() {
+    let data = generate_edocument_data_with_clearance(50, 50, 5, 5, 5);
+    let ids = |seed: u64| {
+        let mut sampled = data.clone();
+        sampled.sample(Some(10), Some(7), Some(seed));
+        let users: Vec<String> = sampled.users.iter().map(|u| u.user_id.clone()).collect();
+        let resources: Vec<String> = sampled.resources.iter().map(|r| r.resource_id.clone()).collect();
+        (users, resources)
+    };
+
+    let (users, resources) = ids(42);
+    assert_eq!((users.len(), resources.len()), (10, 7));
+    assert_eq!(ids(42), (users.clone(), resources));
+    // Sampled users keep their order in the dataset
+    let positions: Vec<usize> = users.iter()
+        .map(|id| data.users.iter().position(|u| &u.user_id == id).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+}
@@ -0,0 +1,32 @@
+//! Serializing an edocument dataset and reading it back must lose nothing: a second
+//! serialization of the deserialized data has to produce exactly the same JSON.
+
+use std::fs;
+use std::path::Path;
+
+use abac_solver::edocument::EdocumentAbacData;
+use abac_solver::example_data::edocument_with_access_level::generate_edocument_data_with_clearance;
+
+fn assert_fixed_point(name: &str, data: &EdocumentAbacData) {
+    let first = serde_json::to_string_pretty(data).expect("dataset serializes");
+    let reparsed: EdocumentAbacData = serde_json::from_str(&first).expect("serialized dataset deserializes");
+    let second = serde_json::to_string_pretty(&reparsed).expect("dataset serializes again");
+    assert_eq!(first, second, "{} changed after a serialize/deserialize round trip", name);
+}
+
+#[test]
+fn generated_dataset_round_trips() {
+    for _ in 0..5 {
+        assert_fixed_point("generated dataset", &generate_edocument_data_with_clearance(50, 50, 5, 5, 5));
+    }
+}
+
+#[test]
+fn fixtures_round_trip() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    for entry in fs::read_dir(fixtures).expect("tests/fixtures is readable") {
+        let fixture = entry.expect("fixture entry is readable").path();
+        let data: EdocumentAbacData = serde_json::from_str(&fs::read_to_string(&fixture).unwrap()).expect("fixture parses");
+        assert_fixed_point(&fixture.display().to_string(), &data);
+    }
+}
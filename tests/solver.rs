@@ -0,0 +1,585 @@
+//! Behaviour of the z3 solver's query methods and opt-in restrictions on small inline datasets.
+
+use abac_solver::edocument::{
+    default_action_hierarchy, Action, ActionHierarchy, AttributeExpression, AttributeName, AttributeValue, BasePolicy,
+    EdocumentAbacData, EdocumentAbacParser, EdocumentAbacSolver, EdocumentDomainParser, EdocumentUserAttribute,
+    PartialUserAttributes, Role, SecurityLevel, parse_date,
+};
+use abac_solver::simple_loop::permitted_triples;
+use abac_solver::z3_solver::{dataset_fingerprint, PredicateArity};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use z3::{Config, Context};
+
+/// A dataset from inline JSON; users and resources only need the fields the test cares about.
+fn data(users: Value, resources: Value, rules: Value) -> EdocumentAbacData {
+    let with_defaults = |entities: Value, defaults: Value| -> Vec<Value> {
+        entities.as_array().unwrap().iter()
+            .map(|entity| {
+                let mut merged = defaults.clone();
+                merged.as_object_mut().unwrap().extend(entity.as_object().unwrap().clone());
+                merged
+            })
+            .collect()
+    };
+    serde_json::from_value(json!({
+        "users": with_defaults(users, json!({"projects": [], "supervisee": []})),
+        "resources": with_defaults(resources, json!({"recipients": []})),
+        "rules": rules,
+    })).unwrap()
+}
+
+/// A permit rule without conditions for `actions`
+fn anyone_can(actions: &[&str]) -> Value {
+    json!({"id": 1, "description": "Anyone", "user_conditions": [], "resource_conditions": [],
+           "actions": actions, "comparison_conditions": []})
+}
+
+#[test]
+fn unused_spare_users_are_never_reported() {
+    let data = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View"])]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::with_spare_users(&ctx, &data, &ActionHierarchy::new(), 3);
+    assert_eq!(solver.solve_access_control(None, None).len(), 1);
+    assert_eq!(solver.solve_access_control_capped(Some(10)).results.len(), 1);
+
+    let carol: EdocumentUserAttribute = serde_json::from_value(json!({"user_id": "carol", "projects": [], "supervisee": []})).unwrap();
+    solver.add_user(&carol).unwrap();
+    assert!(solver.check_access("carol", "doc1", Action::View).unwrap());
+    let users: Vec<String> = solver.solve_access_control(None, None).into_iter().map(|r| r.user_id).collect();
+    assert_eq!(users, ["alice", "carol"]);
+}
+
+#[test]
+fn user_grounded_settings_cover_users_added_later() {
+    let data = data(
+        json!([{"user_id": "alice", "working_hours": "09:00-17:00", "contract_type": "Permanent", "supervisee": ["bob"]},
+               {"user_id": "bob", "working_hours": "09:00-17:00", "contract_type": "Permanent"}]),
+        json!([{"resource_id": "salaries", "owner": "alice", "is_confidential": true},
+               {"resource_id": "notes", "owner": "dave", "is_confidential": false}]),
+        json!([anyone_can(&["View"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::with_spare_users(&ctx, &data, &ActionHierarchy::new(), 2);
+    solver.restrict_to_working_hours(10).unwrap();
+    solver.restrict_external_staff_from_confidential();
+    solver.grant_delegated_access(&[Action::Edit], 0);
+    let chain_rule = serde_json::from_value(json!({"id": 2, "description": "Supervisors can send", "user_conditions": [],
+        "resource_conditions": [], "actions": ["Send"], "comparison_conditions": []})).unwrap();
+    solver.add_supervisor_chain_rule(&chain_rule, 1);
+
+    let carl: EdocumentUserAttribute = serde_json::from_value(json!({"user_id": "carl", "working_hours": "22:00-06:00",
+        "contract_type": "Contractor", "projects": [], "supervisee": []})).unwrap();
+    let dave: EdocumentUserAttribute = serde_json::from_value(json!({"user_id": "dave", "working_hours": "09:00-17:00",
+        "contract_type": "Temporary", "supervisor": "bob", "delegated_authority": ["alice"], "projects": [], "supervisee": []})).unwrap();
+    solver.add_user(&carl).unwrap();
+    solver.add_user(&dave).unwrap();
+
+    // carl is off duty at 10:00, dave is a temp on a confidential resource
+    assert!(!solver.check_access("carl", "notes", Action::View).unwrap());
+    assert!(!solver.check_access("dave", "salaries", Action::View).unwrap());
+    assert!(solver.check_access("dave", "notes", Action::View).unwrap());
+    // alice is dave's delegate, bob is dave's supervisor
+    assert!(solver.check_access("alice", "notes", Action::Edit).unwrap());
+    assert!(!solver.check_access("bob", "notes", Action::Edit).unwrap());
+    assert!(solver.check_access("bob", "notes", Action::Send).unwrap());
+    assert!(!solver.check_access("alice", "notes", Action::Send).unwrap());
+}
+
+#[test]
+fn deny_rules_reach_up_the_hierarchy_but_not_down() {
+    let deny = |id: u32, action: &str| json!({"id": id, "description": "Deny", "user_conditions": [], "resource_conditions": [],
+                                             "actions": [action], "comparison_conditions": [], "effect": "Deny"});
+    let hierarchy = default_action_hierarchy();
+    let ctx = Context::new(&Config::new());
+    for (denied, expected) in [("View", vec!["readMetaInfo", "search"]), ("ReadMetaInfo", vec!["search"])] {
+        let data = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View"]), deny(2, denied)]));
+        let expected: HashSet<(String, String, String)> = expected.into_iter()
+            .map(|action| ("alice".to_string(), "doc1".to_string(), action.to_string()))
+            .collect();
+        assert_eq!(permitted_triples(&data, &hierarchy), expected, "brute force, deny {}", denied);
+
+        let mut solver = EdocumentAbacSolver::new(&ctx, &data, &hierarchy);
+        let solved: HashSet<(String, String, String)> = solver.solve_access_control(None, None).into_iter()
+            .map(|r| (r.user_id, r.resource_id, r.action.to_string()))
+            .collect();
+        assert_eq!(solved, expected, "z3, deny {}", denied);
+    }
+}
+
+#[test]
+fn set_conditions_never_match_a_missing_attribute() {
+    let listed = json!({"ValueSet": [{"ResourceType": "Contract"}, {"ResourceType": "Invoice"}]});
+    let rule = |operator: &str| json!({"id": 1, "description": "Contracts and invoices", "user_conditions": [],
+        "resource_conditions": [{"left": {"AttributeName": "Type"}, "operator": operator, "right": listed}],
+        "actions": ["View"], "comparison_conditions": []});
+    let ctx = Context::new(&Config::new());
+    for operator in ["ContainedIn", "Equals"] {
+        let data = data(json!([{"user_id": "alice"}]),
+                        json!([{"resource_id": "untyped"}, {"resource_id": "contract", "resource_type": "Contract"}]),
+                        json!([rule(operator)]));
+        let expected = HashSet::from([("alice".to_string(), "contract".to_string(), "view".to_string())]);
+        assert_eq!(permitted_triples(&data, &ActionHierarchy::new()), expected, "brute force, {}", operator);
+
+        let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+        assert!(!solver.check_access("alice", "untyped", Action::View).unwrap(), "z3, {}", operator);
+        assert!(solver.check_access("alice", "contract", Action::View).unwrap(), "z3, {}", operator);
+    }
+}
+
+#[test]
+fn deny_rules_apply_to_abstract_users() {
+    let customers = json!({"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Customer"}}});
+    let deny = json!({"id": 2, "description": "Customers cannot view", "user_conditions": [customers], "resource_conditions": [],
+                      "actions": ["View"], "comparison_conditions": [], "effect": "Deny"});
+    let data = data(json!([{"user_id": "alice", "role": "Employee"}, {"user_id": "carl", "role": "Customer"}]),
+                    json!([{"resource_id": "doc1"}]),
+                    json!([anyone_can(&["View"]), deny]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    let profile = |role: Role| PartialUserAttributes {
+        values: [(AttributeName::Role, AttributeValue::Role(role))].into(),
+        ..Default::default()
+    };
+    assert!(solver.check_abstract(&profile(Role::Employee), "doc1", Action::View).unwrap());
+    assert!(!solver.check_abstract(&profile(Role::Customer), "doc1", Action::View).unwrap());
+}
+
+#[test]
+fn only_the_latest_version_of_a_document_is_editable() {
+    let data = data(
+        json!([{"user_id": "alice"}]),
+        json!([{"resource_id": "v1", "document_family": "handbook", "version": "1.0"},
+               {"resource_id": "v1_copy", "document_family": "handbook", "version": "v1.0.0"},
+               {"resource_id": "v2", "document_family": "handbook", "version": "1.10.0-rc1"},
+               {"resource_id": "v1_9", "document_family": "handbook", "version": "1.9"},
+               {"resource_id": "draft", "document_family": "handbook", "version": "next"},
+               {"resource_id": "memo", "version": "0.1"}]),
+        json!([anyone_can(&["View", "Edit"])]),
+    );
+    let expected: HashMap<String, i64> = [("v2", 0), ("v1_9", -1), ("v1", -2), ("v1_copy", -2)].into_iter()
+        .map(|(resource_id, rank)| (resource_id.to_string(), rank))
+        .collect();
+    assert_eq!(data.resource_version_rank(), expected);
+
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.require_latest_version(Action::Edit);
+    for (resource_id, editable) in [("v2", true), ("v1_9", false), ("v1", false), ("draft", true), ("memo", true)] {
+        assert_eq!(solver.check_access("alice", resource_id, Action::Edit).unwrap(), editable, "{}", resource_id);
+        assert!(solver.check_access("alice", resource_id, Action::View).unwrap(), "{}", resource_id);
+    }
+}
+
+#[test]
+fn public_read_is_solved_apart_from_the_rules() {
+    let data = data(
+        json!([{"user_id": "alice"}]),
+        json!([{"resource_id": "public", "security_level": 1, "is_confidential": false},
+               {"resource_id": "public_but_confidential", "security_level": 1, "is_confidential": true},
+               {"resource_id": "public_unmarked", "security_level": 1},
+               {"resource_id": "internal", "security_level": 2, "is_confidential": false}]),
+        json!([anyone_can(&["Edit"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.grant_public_read();
+
+    let mut granted: Vec<(String, String)> = solver.solve_baseline_grants(None).into_iter()
+        .map(|r| (r.resource_id, r.action.to_string()))
+        .collect();
+    granted.sort();
+    assert_eq!(granted, [("public".to_string(), "search".to_string()), ("public".to_string(), "view".to_string())]);
+    assert!(!solver.check_access("alice", "public_but_confidential", Action::View).unwrap());
+    assert_eq!(solver.solve_each_rule(), 4 + 2);
+}
+
+#[test]
+fn deny_by_attribute_overrides_a_granting_rule() {
+    let data = data(json!([{"user_id": "alice", "role": "Employee"}, {"user_id": "carl", "role": "Customer"}]),
+                    json!([{"resource_id": "doc1"}]),
+                    json!([anyone_can(&["View", "Edit"])]));
+    let ctx = Context::new(&Config::new());
+
+    let mut view_only = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    view_only.deny_by_attribute(AttributeName::Role, AttributeValue::Role(Role::Customer), Some(&[Action::View]));
+    assert!(!view_only.check_access("carl", "doc1", Action::View).unwrap());
+    assert!(view_only.check_access("carl", "doc1", Action::Edit).unwrap());
+    assert!(view_only.check_access("alice", "doc1", Action::View).unwrap());
+
+    let mut lockdown = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    lockdown.deny_by_attribute(AttributeName::Role, AttributeValue::Role(Role::Customer), None);
+    let users: HashSet<String> = lockdown.solve_access_control(None, None).into_iter().map(|r| r.user_id).collect();
+    assert_eq!(users, HashSet::from(["alice".to_string()]));
+}
+
+#[test]
+fn the_action_hierarchy_implies_downward_only() {
+    let hierarchy = default_action_hierarchy();
+    let ctx = Context::new(&Config::new());
+    for (granted, expected) in [("View", vec!["readMetaInfo", "search", "view"]), ("ReadMetaInfo", vec!["readMetaInfo"])] {
+        let data = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&[granted])]));
+        let expected: HashSet<(String, String, String)> = expected.into_iter()
+            .map(|action| ("alice".to_string(), "doc1".to_string(), action.to_string()))
+            .collect();
+        assert_eq!(permitted_triples(&data, &hierarchy), expected, "brute force, grant {}", granted);
+
+        let mut solver = EdocumentAbacSolver::new(&ctx, &data, &hierarchy);
+        let solved: HashSet<(String, String, String)> = solver.solve_access_control(None, None).into_iter()
+            .map(|r| (r.user_id, r.resource_id, r.action.to_string()))
+            .collect();
+        assert_eq!(solved, expected, "z3, grant {}", granted);
+    }
+}
+
+#[test]
+fn numeric_thresholds_at_zero_keep_their_boundaries() {
+    let users = json!([{"user_id": "zero", "budget_authority": 0}, {"user_id": "positive", "budget_authority": 5}, {"user_id": "unset"}]);
+    let ctx = Context::new(&Config::new());
+    for (operator, expected) in [
+        ("GreaterThanOrEqual", vec!["positive", "zero"]),
+        ("GreaterThan", vec!["positive"]),
+        ("LessThan", vec![]),
+        ("LessThanOrEqual", vec!["zero"]),
+        ("Equals", vec!["zero"]),
+    ] {
+        let rule = json!({"id": 1, "description": "Budget threshold", "resource_conditions": [], "actions": ["Approve"], "comparison_conditions": [],
+            "user_conditions": [{"left": {"AttributeName": "BudgetAuthority"}, "operator": operator, "right": {"AttributeValue": {"Integer": 0}}}]});
+        let data = data(users.clone(), json!([{"resource_id": "doc1"}]), json!([rule]));
+        let mut brute_force: Vec<String> = permitted_triples(&data, &ActionHierarchy::new()).into_iter().map(|(user, _, _)| user).collect();
+        brute_force.sort();
+        assert_eq!(brute_force, expected, "brute force, {} 0", operator);
+
+        let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+        let solved: Vec<String> = solver.solve_access_control(None, None).into_iter().map(|r| r.user_id).collect();
+        assert_eq!(solved, expected, "z3, {} 0", operator);
+    }
+}
+
+#[test]
+fn budgets_beyond_the_encodable_range_are_rejected() {
+    let json_user = |budget: u64| serde_json::from_value::<EdocumentUserAttribute>(
+        json!({"user_id": "alice", "projects": [], "supervisee": [], "budget_authority": budget}));
+    assert_eq!(json_user(i64::MAX as u64).unwrap().budget_authority, Some(i64::MAX as u64));
+    assert!(json_user(i64::MAX as u64 + 1).is_err());
+
+    let parser = EdocumentAbacParser::new(EdocumentDomainParser);
+    let abac_user = |budget: u64| parser.parse(&format!("userAttrib(alice, projects={{}}, supervisee={{}}, budgetAuthority={})", budget));
+    assert_eq!(abac_user(i64::MAX as u64).unwrap().users[0].budget_authority, Some(i64::MAX as u64));
+    assert!(abac_user(i64::MAX as u64 + 1).is_err());
+}
+
+#[test]
+fn owner_department_restriction_denies_across_departments() {
+    let data = data(
+        json!([{"user_id": "owner", "department": "LargeBankSales"}, {"user_id": "colleague", "department": "LargeBankSales"},
+               {"user_id": "outsider", "department": "LargeBankIT"}, {"user_id": "nobody"}]),
+        json!([{"resource_id": "doc1", "owner": "owner"}, {"resource_id": "orphan", "owner": "nobody"}]),
+        json!([anyone_can(&["View", "Edit"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.require_owner_same_department(Action::Edit);
+
+    assert!(solver.check_access("colleague", "doc1", Action::Edit).unwrap());
+    assert!(!solver.check_access("outsider", "doc1", Action::Edit).unwrap());
+    assert!(solver.check_access("outsider", "doc1", Action::View).unwrap());
+    assert!(!solver.check_access("colleague", "orphan", Action::Edit).unwrap());
+}
+
+#[test]
+fn added_users_are_queried_like_any_other() {
+    let employees_can_view = json!({"id": 1, "description": "Employees can view", "resource_conditions": [], "actions": ["View"], "comparison_conditions": [],
+        "user_conditions": [{"left": {"AttributeName": "Role"}, "operator": "Equals", "right": {"AttributeValue": {"Role": "Employee"}}}]});
+    let data = data(json!([{"user_id": "alice", "role": "Employee"}]), json!([{"resource_id": "doc1", "owner": "alice"}]), json!([employees_can_view]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::with_spare_users(&ctx, &data, &ActionHierarchy::new(), 1);
+    assert_eq!(solver.solve_access_control(None, None).len(), 1);
+
+    let carol: EdocumentUserAttribute = serde_json::from_value(json!({"user_id": "carol", "role": "Employee", "projects": [], "supervisee": []})).unwrap();
+    solver.add_user(&carol).unwrap();
+    assert!(solver.check_access("carol", "doc1", Action::View).unwrap());
+    assert_eq!(solver.solve_access_control(None, None).len(), 2);
+}
+
+#[test]
+fn allow_all_base_policy_permits_what_no_rule_grants() {
+    let data = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1", "owner": "alice"}]), json!([anyone_can(&["View"])]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    assert!(!solver.check_access("alice", "doc1", Action::Edit).unwrap());
+
+    solver.set_base_policy(BasePolicy::AllowAll);
+    assert!(solver.check_access("alice", "doc1", Action::Edit).unwrap());
+}
+
+#[test]
+fn no_read_up_needs_a_dominating_clearance() {
+    let data = data(
+        json!([{"user_id": "alice", "clearance_level": SecurityLevel::Confidential.rank()},
+               {"user_id": "bob", "clearance_level": SecurityLevel::TopSecret.rank()}]),
+        json!([{"resource_id": "plans", "security_level": SecurityLevel::Secret.rank()}]),
+        json!([anyone_can(&["View"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.enforce_no_read_up(&[Action::View, Action::Edit]);
+
+    assert!(!solver.check_access("alice", "plans", Action::View).unwrap());
+    assert!(solver.check_access("bob", "plans", Action::View).unwrap());
+}
+
+#[test]
+fn delegated_access_follows_delegations_up_to_the_depth() {
+    let data = data(
+        json!([{"user_id": "alice", "delegated_authority": ["bob"]}, {"user_id": "bob", "delegated_authority": ["carol"]},
+               {"user_id": "carol"}]),
+        json!([{"resource_id": "doc1", "owner": "alice"}]),
+        json!([]),
+    );
+    let ctx = Context::new(&Config::new());
+
+    let mut direct = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    direct.grant_delegated_access(&[Action::Edit], 0);
+    assert!(direct.check_access("bob", "doc1", Action::Edit).unwrap());
+    assert!(!direct.check_access("carol", "doc1", Action::Edit).unwrap());
+
+    let mut one_hop = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    one_hop.grant_delegated_access(&[Action::Edit], 1);
+    assert!(one_hop.check_access("carol", "doc1", Action::Edit).unwrap());
+    assert!(!one_hop.check_access("carol", "doc1", Action::View).unwrap());
+}
+
+#[test]
+fn supervisor_chain_rule_reaches_up_to_the_level_limit() {
+    let data = data(
+        json!([{"user_id": "ceo", "supervisee": ["lead"]}, {"user_id": "lead", "supervisee": ["dev"]},
+               {"user_id": "dev", "supervisor": "lead"}, {"user_id": "peer"}]),
+        json!([{"resource_id": "review", "resource_type": "Contract", "owner": "dev"}]),
+        json!([]),
+    );
+    let rule = serde_json::from_value(json!({"id": 1, "description": "Managers can view their reports' contracts", "user_conditions": [],
+        "resource_conditions": [{"left": {"AttributeName": "Type"}, "operator": "Equals", "right": {"AttributeValue": {"ResourceType": "Contract"}}}],
+        "actions": ["View"], "comparison_conditions": []})).unwrap();
+    let ctx = Context::new(&Config::new());
+
+    let mut direct = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    direct.add_supervisor_chain_rule(&rule, 1);
+    assert!(direct.check_access("lead", "review", Action::View).unwrap());
+    assert!(!direct.check_access("ceo", "review", Action::View).unwrap());
+    assert!(!direct.check_access("peer", "review", Action::View).unwrap());
+
+    let mut two_levels = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    two_levels.add_supervisor_chain_rule(&rule, 2);
+    assert!(two_levels.check_access("ceo", "review", Action::View).unwrap());
+    assert!(!two_levels.check_access("ceo", "review", Action::Edit).unwrap());
+}
+
+#[test]
+fn approval_workflow_only_approves_pending_work_by_approvers() {
+    let data = data(
+        json!([{"user_id": "alice", "role": "Manager"}, {"user_id": "bob", "role": "Employee"}]),
+        json!([{"resource_id": "draft", "approval_status": "Draft"}, {"resource_id": "pending", "approval_status": "Pending"},
+               {"resource_id": "approved", "approval_status": "Approved"}, {"resource_id": "rejected", "approval_status": "Rejected"}]),
+        json!([anyone_can(&["Approve"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.require_approval_workflow(&[Role::Manager]);
+
+    assert!(solver.check_access("alice", "draft", Action::Approve).unwrap());
+    assert!(solver.check_access("alice", "pending", Action::Approve).unwrap());
+    assert!(!solver.check_access("alice", "approved", Action::Approve).unwrap());
+    assert!(!solver.check_access("alice", "rejected", Action::Approve).unwrap());
+    assert!(!solver.check_access("bob", "pending", Action::Approve).unwrap());
+}
+
+#[test]
+fn tenant_isolation_lets_only_cross_tenant_rules_through() {
+    let mut search_across = anyone_can(&["Search"]);
+    search_across["id"] = json!(2);
+    search_across["cross_tenant"] = json!(true);
+    let data = data(
+        json!([{"user_id": "alice", "tenant": "LargeBank"}, {"user_id": "bob", "tenant": "Reseller"}]),
+        json!([{"resource_id": "statement", "tenant": "LargeBank"}]),
+        json!([anyone_can(&["View"]), search_across]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    assert!(solver.check_access("bob", "statement", Action::View).unwrap());
+
+    solver.enforce_tenant_isolation();
+    assert!(solver.check_access("alice", "statement", Action::View).unwrap());
+    assert!(!solver.check_access("bob", "statement", Action::View).unwrap());
+    assert!(solver.check_access("bob", "statement", Action::Search).unwrap());
+}
+
+#[test]
+fn geo_residency_keeps_users_in_their_allowed_regions() {
+    let data = data(
+        json!([{"user_id": "anna", "region": "eu"}, {"user_id": "sam", "region": "us"}, {"user_id": "ken", "region": "apac"}]),
+        json!([{"resource_id": "ledger", "region": "eu"}, {"resource_id": "handbook"}]),
+        json!([anyone_can(&["View"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.enforce_geo_residency(&[("us".to_string(), "eu".to_string())], false);
+
+    assert!(solver.check_access("anna", "ledger", Action::View).unwrap());
+    assert!(!solver.check_access("ken", "ledger", Action::View).unwrap());
+    assert!(solver.check_access("sam", "ledger", Action::View).unwrap());
+    assert!(solver.check_access("ken", "handbook", Action::View).unwrap());
+
+    let mut strict = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    strict.enforce_geo_residency(&[], true);
+    assert!(!strict.check_access("ken", "handbook", Action::View).unwrap());
+    assert!(!strict.check_access("sam", "ledger", Action::View).unwrap());
+}
+
+#[test]
+fn external_staff_cannot_open_confidential_resources() {
+    let data = data(
+        json!([{"user_id": "alice", "contract_type": "Permanent"}, {"user_id": "carl", "contract_type": "Contractor"},
+               {"user_id": "tess", "contract_type": "Temporary"}]),
+        json!([{"resource_id": "salaries", "is_confidential": true}, {"resource_id": "menu", "is_confidential": false}]),
+        json!([anyone_can(&["View", "Edit", "Search"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.restrict_external_staff_from_confidential();
+
+    assert!(!solver.check_access("carl", "salaries", Action::View).unwrap());
+    assert!(!solver.check_access("tess", "salaries", Action::Edit).unwrap());
+    assert!(solver.check_access("carl", "salaries", Action::Search).unwrap());
+    assert!(solver.check_access("carl", "menu", Action::Edit).unwrap());
+    assert!(solver.check_access("alice", "salaries", Action::Edit).unwrap());
+}
+
+#[test]
+fn archived_resources_are_read_only_until_switched_off() {
+    let data = data(
+        json!([{"user_id": "alice"}]),
+        json!([{"resource_id": "old", "is_archived": true}, {"resource_id": "current", "is_archived": false}]),
+        json!([anyone_can(&["View", "Edit"])]),
+    );
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+
+    assert!(!solver.check_access("alice", "old", Action::Edit).unwrap());
+    assert!(solver.check_access("alice", "old", Action::View).unwrap());
+    assert!(solver.check_access("alice", "current", Action::Edit).unwrap());
+
+    solver.set_archived_read_only(false);
+    assert!(solver.check_access("alice", "old", Action::Edit).unwrap());
+}
+
+#[test]
+fn documents_past_their_retention_period_cannot_be_edited() {
+    assert_eq!(parse_date("2024-02-29"), Some(19782));
+    assert_eq!(parse_date("2024-02-31"), None);
+    assert_eq!(parse_date("2023-02-29"), None);
+    assert_eq!(parse_date("2024-04-31"), None);
+
+    let data = data(
+        json!([{"user_id": "alice"}]),
+        json!([{"resource_id": "expired", "created_date": "2024-01-01", "retention_period_days": 30},
+               {"resource_id": "kept", "created_date": "2024-01-01", "retention_period_days": 365},
+               {"resource_id": "overflowing", "created_date": "2024-01-01", "retention_period_days": i64::MAX}]),
+        json!([anyone_can(&["View", "Edit"])]),
+    );
+    assert_eq!(data.retention_expiry().keys().collect::<HashSet<_>>(), HashSet::from([&"expired".to_string(), &"kept".to_string()]));
+
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    assert!(solver.archive_after_retention("2024-02-31").is_err());
+    solver.archive_after_retention("2024-06-01").unwrap();
+
+    assert!(!solver.check_access("alice", "expired", Action::Edit).unwrap());
+    assert!(solver.check_access("alice", "expired", Action::View).unwrap());
+    assert!(solver.check_access("alice", "kept", Action::Edit).unwrap());
+    assert!(solver.check_access("alice", "overflowing", Action::Edit).unwrap());
+}
+
+#[test]
+fn working_hours_wrap_past_midnight() {
+    let data = data(
+        json!([{"user_id": "alice", "working_hours": "09:00-17:00"}, {"user_id": "bob", "working_hours": "22:00-06:00"}]),
+        json!([{"resource_id": "doc1"}]),
+        json!([anyone_can(&["View"])]),
+    );
+    let ctx = Context::new(&Config::new());
+
+    let mut at_3am = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    at_3am.restrict_to_working_hours(3).unwrap();
+    assert!(!at_3am.check_access("alice", "doc1", Action::View).unwrap());
+    assert!(at_3am.check_access("bob", "doc1", Action::View).unwrap());
+
+    let mut at_10am = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    at_10am.restrict_to_working_hours(10).unwrap();
+    assert!(at_10am.check_access("alice", "doc1", Action::View).unwrap());
+    assert!(!at_10am.check_access("bob", "doc1", Action::View).unwrap());
+}
+
+#[test]
+fn stats_count_every_solution_with_its_time() {
+    let data = data(json!([{"user_id": "alice"}, {"user_id": "bob"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View"])]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    let (results, stats) = solver.solve_access_control_with_stats(None);
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(stats.solutions_found, results.len());
+    assert_eq!((stats.users, stats.resources, stats.rules), (2, 1, 1));
+    assert!(stats.solution_times.is_some());
+    assert_eq!(results, solver.solve_access_control(None, None));
+    assert_eq!(solver.solve_access_control_with_stats(Some(1)).0, solver.solve_access_control(Some(1), None));
+}
+
+#[test]
+fn batch_checks_report_unknown_entities_per_request() {
+    let data = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View"])]));
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+
+    let decisions = solver.check_access_batch(&[
+        ("alice".to_string(), "doc1".to_string(), Action::View),
+        ("mallory".to_string(), "doc1".to_string(), Action::View),
+        ("alice".to_string(), "doc1".to_string(), Action::Edit),
+        ("alice".to_string(), "doc9".to_string(), Action::View),
+    ]);
+    assert_eq!(decisions, vec![
+        Ok(true),
+        Err("Unknown user: mallory".to_string()),
+        Ok(false),
+        Err("Unknown resource: doc9".to_string()),
+    ]);
+}
+
+#[test]
+fn custom_predicates_are_referenced_by_name_from_rules() {
+    let on_call_can_edit = json!({"id": 1, "description": "On-call staff can edit", "resource_conditions": [], "actions": ["Edit"], "comparison_conditions": [],
+        "user_conditions": [{"left": {"AttributeName": {"Custom": "onCall"}}, "operator": "Equals", "right": {"AttributeValue": {"Boolean": true}}}]});
+    let data = data(json!([{"user_id": "alice"}, {"user_id": "bob"}]), json!([{"resource_id": "doc1"}]), json!([on_call_can_edit]));
+    assert_eq!(data.rules[0].user_conditions[0].left, AttributeExpression::AttributeName(AttributeName::Custom("onCall".to_string())));
+    let parsed = EdocumentAbacParser::new(EdocumentDomainParser).parse("rule(custom.onCall = true; ; {edit})").unwrap();
+    assert_eq!(parsed.rules[0].user_conditions, data.rules[0].user_conditions);
+
+    let ctx = Context::new(&Config::new());
+    let mut solver = EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    solver.register_predicate("onCall", PredicateArity::User, |user_id| user_id == "alice");
+    assert!(solver.check_access("alice", "doc1", Action::Edit).unwrap());
+    assert!(!solver.check_access("bob", "doc1", Action::Edit).unwrap());
+}
+
+#[test]
+fn dataset_fingerprints_ignore_set_order_only() {
+    let users = json!([{"user_id": "alice", "projects": ["p1", "p2", "p3"]}]);
+    let a = data(users.clone(), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View", "Edit", "Search", "Approve"])]));
+    let b = data(users, json!([{"resource_id": "doc1"}]), json!([anyone_can(&["Approve", "Search", "Edit", "View"])]));
+    let fingerprint = dataset_fingerprint(&a);
+    assert_eq!(fingerprint.len(), 16);
+    assert_eq!(fingerprint, dataset_fingerprint(&b));
+
+    let c = data(json!([{"user_id": "alice"}]), json!([{"resource_id": "doc1"}]), json!([anyone_can(&["View"])]));
+    assert_ne!(fingerprint, dataset_fingerprint(&c));
+}
@@ -0,0 +1,57 @@
+//! Solvers built from a shared `SortCache` must enumerate the same triples as solvers that
+//! declare their own sorts. The ignored test times construction on `data/edocument_10000.abac`;
+//! run it with `cargo test --release --test sort_cache_bench -- --ignored --nocapture`.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use abac_solver::edocument::{ActionHierarchy, EdocumentAbacData, EdocumentAbacParser, EdocumentAbacSolver, EdocumentDomainParser, SortCache};
+use z3::{Config, Context};
+
+fn triples(solver: &mut EdocumentAbacSolver) -> Vec<(String, String, String)> {
+    let mut triples: Vec<_> = solver.solve_access_control(None, None).into_iter()
+        .map(|r| (r.user_id, r.resource_id, r.action.to_string()))
+        .collect();
+    triples.sort();
+    triples
+}
+
+#[test]
+fn cached_sorts_give_the_same_results() {
+    let ctx = Context::new(&Config::new());
+    let sorts = SortCache::new(&ctx);
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    for entry in fs::read_dir(fixtures).expect("tests/fixtures is readable") {
+        let fixture = entry.expect("fixture entry is readable").path();
+        let data: EdocumentAbacData = serde_json::from_str(&fs::read_to_string(&fixture).unwrap()).unwrap();
+
+        let uncached = triples(&mut EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new()));
+        let cached = triples(&mut EdocumentAbacSolver::with_sorts(&ctx, &sorts, &data, &ActionHierarchy::new()));
+        assert_eq!(uncached, cached, "{} differs with cached sorts", fixture.display());
+    }
+}
+
+#[test]
+#[ignore]
+fn construction_time_with_and_without_the_cache() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("edocument_10000.abac");
+    let data = EdocumentAbacParser::new(EdocumentDomainParser).parse_file(path.to_str().unwrap()).unwrap();
+    let ctx = Context::new(&Config::new());
+    let runs = 3;
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        EdocumentAbacSolver::new(&ctx, &data, &ActionHierarchy::new());
+    }
+    let uncached = start.elapsed() / runs;
+
+    let sorts = SortCache::new(&ctx);
+    let start = Instant::now();
+    for _ in 0..runs {
+        EdocumentAbacSolver::with_sorts(&ctx, &sorts, &data, &ActionHierarchy::new());
+    }
+    let cached = start.elapsed() / runs;
+
+    println!("per construction: own sorts {:?}, shared SortCache {:?}", uncached, cached);
+}
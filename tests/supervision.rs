@@ -0,0 +1,37 @@
+//! The supervisor relation read from the data, and walking it a bounded number of levels up.
+
+use abac_solver::edocument::{supervisors_within, EdocumentAbacData};
+use std::collections::HashSet;
+
+fn data(users: serde_json::Value) -> EdocumentAbacData {
+    serde_json::from_value(serde_json::json!({"users": users, "resources": [], "rules": []})).unwrap()
+}
+
+fn ids(ids: &[&str]) -> HashSet<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn supervisors_come_from_either_side_of_the_relation() {
+    let data = data(serde_json::json!([
+        {"user_id": "ceo", "projects": [], "supervisee": ["lead"]},
+        {"user_id": "lead", "projects": [], "supervisee": []},
+        {"user_id": "dev", "supervisor": "lead", "projects": [], "supervisee": []}
+    ]));
+    let supervisors = data.supervisors();
+
+    assert_eq!(supervisors_within(&supervisors, "dev", 0), ids(&[]));
+    assert_eq!(supervisors_within(&supervisors, "dev", 1), ids(&["lead"]));
+    assert_eq!(supervisors_within(&supervisors, "dev", 2), ids(&["lead", "ceo"]));
+    assert_eq!(supervisors_within(&supervisors, "dev", 10), ids(&["lead", "ceo"]));
+}
+
+#[test]
+fn cycles_stop_the_walk() {
+    let data = data(serde_json::json!([
+        {"user_id": "a", "projects": [], "supervisee": ["b"]},
+        {"user_id": "b", "projects": [], "supervisee": ["a"]}
+    ]));
+
+    assert_eq!(supervisors_within(&data.supervisors(), "a", usize::MAX), ids(&["b"]));
+}